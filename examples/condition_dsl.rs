@@ -0,0 +1,55 @@
+//! Example: Parsing and validating belief conditions written in the condition DSL
+//!
+//! Pass a condition as arguments, or pipe one condition per line on stdin:
+//!
+//! ```text
+//! cargo run --example condition_dsl -- "probability above 0.7 hold 30m"
+//! ```
+
+use preda_sdk::types::{parse_condition, BeliefCondition};
+use std::io::{self, BufRead};
+
+fn check(source: &str) -> bool {
+    match parse_condition(source) {
+        Ok(condition) => {
+            print_condition(&condition);
+            true
+        }
+        Err(err) => {
+            eprintln!("{}", err.render(source));
+            false
+        }
+    }
+}
+
+fn print_condition(condition: &BeliefCondition) {
+    println!("✅ {}", condition);
+    println!("   Persistence window: {}s", condition.persistence_window());
+
+    match condition.validate() {
+        Ok(()) => println!("   Valid"),
+        Err(err) => println!("   ⚠️  Invalid: {}", err),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let ok = if args.is_empty() {
+        let mut ok = true;
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                ok &= check(line.trim());
+            }
+        }
+        ok
+    } else {
+        check(&args.join(" "))
+    };
+
+    if !ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
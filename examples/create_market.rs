@@ -39,7 +39,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   Time bucket size: {} seconds", market.config.time_bucket_size);
     println!("   Min position: {} lamports", market.config.min_position_size);
     println!("   Max position: {} lamports", market.config.max_position_size);
-    println!("   Fee: {}%", market.config.fee_bps as f64 / 100.0);
+    println!("   Placement fee: {}%", market.config.fees.placement_bps as f64 / 100.0);
+    println!("   Withdrawal fee: {}%", market.config.fees.withdrawal_bps as f64 / 100.0);
 
     Ok(())
 }
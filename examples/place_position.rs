@@ -1,8 +1,7 @@
 //! Example: Placing a position in a time-shifted market
 
-use preda_sdk::PredaClient;
-use solana_sdk::{pubkey::Pubkey, signature::Keypair};
-use std::str::FromStr;
+use preda_sdk::{BeliefCondition, MarketType, PredaClient};
+use solana_sdk::signature::Keypair;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -14,12 +13,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("🎯 Placing a position in a time-shifted market...\n");
 
-    // Market address (replace with actual market)
-    let market_address = Pubkey::from_str("11111111111111111111111111111111")?;
+    // Market to trade in (replace with an existing market)
+    let market = client
+        .create_market(
+            MarketType::SentimentTransition,
+            BeliefCondition::SentimentShift {
+                from_polarity: -0.2,
+                to_polarity: 0.6,
+                persistence_window: 3600,
+            },
+            "BTC sentiment turns bullish",
+        )
+        .await?;
+    let market_address = market.address;
 
-    // Calculate time bucket (predict inflection in 24 hours)
+    // Find the grid bucket for the prediction (inflection in 24 hours)
     let current_time = chrono::Utc::now().timestamp();
     let predicted_time = current_time + 86400; // +24 hours
+    let time_bucket = market
+        .bucket_for(predicted_time)
+        .ok_or("prediction is outside the market lifetime")?;
 
     // Position amount (0.1 SOL)
     let amount = 100_000_000; // lamports
@@ -31,7 +44,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Place position
     let position = client
-        .place_position(&market_address, predicted_time, amount)
+        .place_position(&market_address, time_bucket.start, amount)
         .await?;
 
     println!("\n✅ Position placed successfully!");
@@ -0,0 +1,632 @@
+//! Belief condition evaluation against BSI history
+
+use crate::types::belief::{
+    BeliefCondition, BeliefStateIndex, ConditionStatus, ThresholdDirection,
+};
+use crate::types::custom::CustomConditionRegistry;
+
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+/// Evaluates belief conditions against a BSI history
+///
+/// History is expected in chronological order (ascending `last_updated`);
+/// no condition is met on a history out of order.
+pub struct ConditionEvaluator;
+
+impl ConditionEvaluator {
+    /// Evaluate a condition at the latest BSI in the history
+    ///
+    /// Custom conditions are resolved through the global registry.
+    pub fn evaluate(condition: &BeliefCondition, history: &[BeliefStateIndex]) -> ConditionStatus {
+        Self::evaluate_with(condition, history, &CustomConditionRegistry::read_global())
+    }
+
+    /// Evaluate a condition, resolving custom conditions through `registry`
+    pub fn evaluate_with(
+        condition: &BeliefCondition,
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+    ) -> ConditionStatus {
+        if !Self::is_chronological(history) {
+            return ConditionStatus::unmet(condition.persistence_window());
+        }
+        let (runs, required) = Self::predicate(condition, history, registry);
+
+        let Some(&Some(first_true_at)) = runs.last() else {
+            return ConditionStatus::unmet(required);
+        };
+        let run_start = runs
+            .iter()
+            .rposition(|start| *start != Some(first_true_at))
+            .map_or(0, |i| i + 1);
+
+        let latest = history[history.len() - 1].last_updated;
+        let held_for = Self::elapsed(first_true_at, latest).unwrap_or(0);
+        let satisfied_at = history[run_start..]
+            .iter()
+            .map(|b| b.last_updated)
+            .find(|&t| Self::elapsed(first_true_at, t).is_some_and(|held| held >= required));
+
+        ConditionStatus {
+            satisfied: satisfied_at.is_some(),
+            holding: true,
+            first_true_at: Some(first_true_at),
+            satisfied_at,
+            held_for,
+            required,
+        }
+    }
+
+    /// Check if the condition is met at the latest BSI in the history
+    pub fn is_met(condition: &BeliefCondition, history: &[BeliefStateIndex]) -> bool {
+        Self::evaluate(condition, history).satisfied
+    }
+
+    /// Check if the condition is met, resolving custom conditions through `registry`
+    pub fn is_met_with(
+        condition: &BeliefCondition,
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+    ) -> bool {
+        Self::evaluate_with(condition, history, registry).satisfied
+    }
+
+    /// Whether the condition is met at each point of the history
+    ///
+    /// A condition is met once its predicate has held continuously for its
+    /// persistence window.
+    pub fn satisfaction(condition: &BeliefCondition, history: &[BeliefStateIndex]) -> Vec<bool> {
+        Self::satisfaction_with(condition, history, &CustomConditionRegistry::read_global())
+    }
+
+    /// Satisfaction series, resolving custom conditions through `registry`
+    pub fn satisfaction_with(
+        condition: &BeliefCondition,
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+    ) -> Vec<bool> {
+        if !Self::is_chronological(history) {
+            return vec![false; history.len()];
+        }
+        let (runs, required) = Self::predicate(condition, history, registry);
+        Self::persist(&runs, history, required)
+    }
+
+    /// Predicate series of a condition together with the window it must hold for
+    ///
+    /// Each point carries the start of the run the predicate has held through,
+    /// or `None` where it does not hold. Composite conditions fold the
+    /// satisfaction of their children, so they carry no window of their own.
+    /// `Not` negates its child's predicate and keeps the child's window.
+    fn predicate(
+        condition: &BeliefCondition,
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+    ) -> (Vec<Option<i64>>, u64) {
+        match condition {
+            BeliefCondition::All { conditions } => (
+                Self::runs(
+                    &Self::combine(conditions, history, registry, |a, b| a && b),
+                    history,
+                ),
+                0,
+            ),
+            BeliefCondition::Any { conditions } => (
+                Self::runs(
+                    &Self::combine(conditions, history, registry, |a, b| a || b),
+                    history,
+                ),
+                0,
+            ),
+            BeliefCondition::Not { condition } => {
+                let (inner, required) = Self::predicate(condition, history, registry);
+                let negated: Vec<bool> = inner.iter().map(Option::is_none).collect();
+                (Self::runs(&negated, history), required)
+            }
+            BeliefCondition::Sequence { steps, max_gap } => (
+                Self::runs(&Self::sequence(steps, *max_gap, history, registry), history),
+                0,
+            ),
+            BeliefCondition::ProbabilityThreshold {
+                threshold,
+                direction: ThresholdDirection::Cross,
+                persistence_window,
+            } => (Self::crossings(*threshold, history), *persistence_window),
+            primitive => (
+                Self::runs(&Self::instant(primitive, history, registry), history),
+                primitive.persistence_window(),
+            ),
+        }
+    }
+
+    /// Instantaneous truth of a primitive condition at each point of the history
+    fn instant(
+        condition: &BeliefCondition,
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+    ) -> Vec<bool> {
+        match condition {
+            BeliefCondition::SentimentShift {
+                from_polarity,
+                to_polarity,
+                ..
+            } => {
+                let rising = to_polarity >= from_polarity;
+                let mut left_origin = false;
+
+                history
+                    .iter()
+                    .map(|b| {
+                        // The shift only counts once belief has been seen at the origin polarity
+                        let (at_origin, at_target) = if rising {
+                            (b.value <= *from_polarity, b.value >= *to_polarity)
+                        } else {
+                            (b.value >= *from_polarity, b.value <= *to_polarity)
+                        };
+                        left_origin |= at_origin;
+                        left_origin && at_target
+                    })
+                    .collect()
+            }
+            BeliefCondition::ProbabilityThreshold {
+                threshold,
+                direction,
+                ..
+            } => match direction {
+                ThresholdDirection::Above => history
+                    .iter()
+                    .map(|b| b.probability() >= *threshold)
+                    .collect(),
+                ThresholdDirection::Below => history
+                    .iter()
+                    .map(|b| b.probability() <= *threshold)
+                    .collect(),
+                ThresholdDirection::Cross => Self::crossings(*threshold, history)
+                    .iter()
+                    .map(Option::is_some)
+                    .collect(),
+            },
+            BeliefCondition::ModelConsensus {
+                min_models,
+                convergence_band,
+                ..
+            } => history
+                .iter()
+                .map(|b| b.signal_count >= *min_models && b.volatility <= *convergence_band)
+                .collect(),
+            BeliefCondition::NarrativeVelocity {
+                velocity_threshold,
+                acceleration_threshold,
+                ..
+            } => {
+                let mut previous: Option<&BeliefStateIndex> = None;
+                let mut accelerated = false;
+
+                history
+                    .iter()
+                    .map(|b| {
+                        let fast = b.velocity.abs() >= *velocity_threshold;
+                        let was_fast =
+                            previous.is_some_and(|p| p.velocity.abs() >= *velocity_threshold);
+
+                        // Acceleration only matters at the onset of a run; a steady
+                        // high velocity afterwards keeps the condition holding
+                        if fast && !was_fast {
+                            let acceleration = previous.map_or(0.0, |p| Self::acceleration(p, b));
+                            accelerated = acceleration.abs() >= *acceleration_threshold;
+                        }
+                        previous = Some(b);
+                        fast && accelerated
+                    })
+                    .collect()
+            }
+            BeliefCondition::Custom {
+                condition_type,
+                parameters,
+                ..
+            } => registry.holds(condition_type, parameters, history),
+            _ => vec![false; history.len()],
+        }
+    }
+
+    /// Change in velocity per hour between two BSI updates
+    ///
+    /// Dividing by the elapsed time keeps the result independent of how
+    /// often the oracles update. Updates with the same timestamp give 0.
+    fn acceleration(previous: &BeliefStateIndex, current: &BeliefStateIndex) -> f64 {
+        let elapsed = current.last_updated - previous.last_updated;
+        if elapsed <= 0 {
+            return 0.0;
+        }
+        (current.velocity - previous.velocity) * SECONDS_PER_HOUR / elapsed as f64
+    }
+
+    /// Runs of a threshold crossing
+    ///
+    /// A crossing is a sample on the other side of `threshold` from the one
+    /// before it. The condition holds from the latest crossing on, and
+    /// crossing back starts a new run.
+    fn crossings(threshold: f64, history: &[BeliefStateIndex]) -> Vec<Option<i64>> {
+        let mut crossed_at = None;
+        let mut was_above = None;
+
+        history
+            .iter()
+            .map(|b| {
+                let above = b.probability() >= threshold;
+                if was_above.is_some_and(|was_above| was_above != above) {
+                    crossed_at = Some(b.last_updated);
+                }
+                was_above = Some(above);
+                crossed_at
+            })
+            .collect()
+    }
+
+    /// Start of the run each point of a predicate series has held through
+    fn runs(predicate: &[bool], history: &[BeliefStateIndex]) -> Vec<Option<i64>> {
+        let mut run_start: Option<i64> = None;
+
+        predicate
+            .iter()
+            .zip(history)
+            .map(|(&holds, bsi)| {
+                if !holds {
+                    run_start = None;
+                    return None;
+                }
+                Some(*run_start.get_or_insert(bsi.last_updated))
+            })
+            .collect()
+    }
+
+    /// Require each run to have held continuously for `window` seconds
+    fn persist(runs: &[Option<i64>], history: &[BeliefStateIndex], window: u64) -> Vec<bool> {
+        runs.iter()
+            .zip(history)
+            .map(|(start, bsi)| {
+                start
+                    .and_then(|start| Self::elapsed(start, bsi.last_updated))
+                    .is_some_and(|held| held >= window)
+            })
+            .collect()
+    }
+
+    /// Seconds from `start` to `end`, or `None` if `end` comes first
+    fn elapsed(start: i64, end: i64) -> Option<u64> {
+        end.checked_sub(start)
+            .and_then(|seconds| u64::try_from(seconds).ok())
+    }
+
+    /// Whether the history is in ascending `last_updated` order
+    fn is_chronological(history: &[BeliefStateIndex]) -> bool {
+        history
+            .windows(2)
+            .all(|pair| pair[0].last_updated <= pair[1].last_updated)
+    }
+
+    /// Fold the satisfaction series of several conditions point by point
+    fn combine(
+        conditions: &[BeliefCondition],
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+        op: fn(bool, bool) -> bool,
+    ) -> Vec<bool> {
+        conditions
+            .iter()
+            .map(|c| Self::satisfaction_with(c, history, registry))
+            .reduce(|acc, next| acc.iter().zip(&next).map(|(&a, &b)| op(a, b)).collect())
+            .unwrap_or_else(|| vec![false; history.len()])
+    }
+
+    /// Chain steps so each is met no later than `max_gap` seconds after the previous
+    fn sequence(
+        steps: &[BeliefCondition],
+        max_gap: u64,
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+    ) -> Vec<bool> {
+        let mut reached: Option<Vec<bool>> = None;
+
+        for step in steps {
+            let met = Self::satisfaction_with(step, history, registry);
+
+            reached = Some(match reached {
+                None => met,
+                Some(previous) => {
+                    let mut last_previous: Option<i64> = None;
+                    let mut chained = vec![false; history.len()];
+
+                    for i in 0..history.len() {
+                        let now = history[i].last_updated;
+                        if previous[i] {
+                            last_previous = Some(now);
+                        }
+
+                        let continuing = i > 0 && chained[i - 1];
+                        let within_gap = last_previous
+                            .and_then(|t| Self::elapsed(t, now))
+                            .is_some_and(|gap| gap <= max_gap);
+
+                        chained[i] = met[i] && (continuing || within_gap);
+                    }
+                    chained
+                }
+            });
+        }
+
+        reached.unwrap_or_else(|| vec![false; history.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bsi(last_updated: i64, value: f64, velocity: f64) -> BeliefStateIndex {
+        BeliefStateIndex {
+            value,
+            velocity,
+            volatility: 0.05,
+            last_updated,
+            confidence: 0.8,
+            signal_count: 5,
+            domain: "BTC".to_string(),
+        }
+    }
+
+    fn sentiment(persistence_window: u64) -> BeliefCondition {
+        BeliefCondition::SentimentShift {
+            from_polarity: -0.2,
+            to_polarity: 0.6,
+            persistence_window,
+        }
+    }
+
+    fn velocity(persistence_window: u64) -> BeliefCondition {
+        BeliefCondition::NarrativeVelocity {
+            velocity_threshold: 0.5,
+            acceleration_threshold: 0.0,
+            persistence_window,
+        }
+    }
+
+    #[test]
+    fn test_primitive_persistence() {
+        let history = vec![bsi(0, -0.3, 0.0), bsi(100, 0.7, 0.0), bsi(200, 0.8, 0.0)];
+
+        assert!(ConditionEvaluator::is_met(&sentiment(100), &history));
+        assert!(!ConditionEvaluator::is_met(&sentiment(150), &history));
+    }
+
+    #[test]
+    fn test_all_and_any() {
+        let history = vec![bsi(0, -0.3, 0.0), bsi(100, 0.7, 0.0), bsi(200, 0.8, 0.0)];
+
+        let all = BeliefCondition::All {
+            conditions: vec![sentiment(0), velocity(0)],
+        };
+        assert!(!ConditionEvaluator::is_met(&all, &history));
+
+        let any = BeliefCondition::Any {
+            conditions: vec![sentiment(0), velocity(0)],
+        };
+        assert!(ConditionEvaluator::is_met(&any, &history));
+    }
+
+    #[test]
+    fn test_not_requires_persistence() {
+        let stabilizes = BeliefCondition::Not {
+            condition: Box::new(velocity(300)),
+        };
+
+        let history = vec![bsi(0, 0.0, 0.9), bsi(100, 0.0, 0.1), bsi(200, 0.0, 0.1)];
+        assert!(!ConditionEvaluator::is_met(&stabilizes, &history));
+
+        let history = vec![bsi(0, 0.0, 0.9), bsi(100, 0.0, 0.1), bsi(400, 0.0, 0.1)];
+        assert!(ConditionEvaluator::is_met(&stabilizes, &history));
+    }
+
+    #[test]
+    fn test_sentiment_shift_requires_origin() {
+        let condition = sentiment(0);
+
+        let never_bearish = vec![bsi(0, 0.1, 0.0), bsi(100, 0.7, 0.0)];
+        assert!(!ConditionEvaluator::is_met(&condition, &never_bearish));
+
+        let shifted = vec![bsi(0, -0.3, 0.0), bsi(100, 0.1, 0.0), bsi(200, 0.7, 0.0)];
+        assert!(ConditionEvaluator::is_met(&condition, &shifted));
+    }
+
+    #[test]
+    fn test_probability_cross() {
+        let condition = BeliefCondition::ProbabilityThreshold {
+            threshold: 0.7,
+            direction: ThresholdDirection::Cross,
+            persistence_window: 0,
+        };
+
+        // Probability 0.6 -> 0.8 crosses upwards, 0.8 -> 0.6 crosses downwards
+        let upwards = vec![bsi(0, 0.2, 0.0), bsi(100, 0.6, 0.0)];
+        assert!(ConditionEvaluator::is_met(&condition, &upwards));
+
+        let downwards = vec![bsi(0, 0.6, 0.0), bsi(100, 0.2, 0.0)];
+        assert!(ConditionEvaluator::is_met(&condition, &downwards));
+
+        let flat = vec![bsi(0, 0.6, 0.0), bsi(100, 0.5, 0.0)];
+        assert!(!ConditionEvaluator::is_met(&condition, &flat));
+
+        // Crossing back and again is still a crossing
+        let recrossed = vec![bsi(0, 0.6, 0.0), bsi(100, 0.2, 0.0), bsi(200, 0.6, 0.0)];
+        assert!(ConditionEvaluator::is_met(&condition, &recrossed));
+    }
+
+    #[test]
+    fn test_crossing_back_restarts_persistence() {
+        let condition = BeliefCondition::ProbabilityThreshold {
+            threshold: 0.7,
+            direction: ThresholdDirection::Cross,
+            persistence_window: 300,
+        };
+
+        let history = vec![
+            bsi(0, 0.2, 0.0),
+            bsi(100, 0.6, 0.0),
+            bsi(300, 0.2, 0.0),
+            bsi(500, 0.2, 0.0),
+        ];
+        let status = ConditionEvaluator::evaluate(&condition, &history);
+        assert!(status.holding);
+        assert!(!status.satisfied);
+        assert_eq!(status.first_true_at, Some(300));
+        assert_eq!(status.held_for, 200);
+
+        let mut held = history;
+        held.push(bsi(600, 0.2, 0.0));
+        let status = ConditionEvaluator::evaluate(&condition, &held);
+        assert_eq!(status.satisfied_at, Some(600));
+    }
+
+    #[test]
+    fn test_unsorted_history_meets_nothing() {
+        let history = vec![bsi(200, 0.7, 0.0), bsi(0, -0.3, 0.0), bsi(100, 0.8, 0.0)];
+        let status = ConditionEvaluator::evaluate(&sentiment(0), &history);
+        assert!(!status.satisfied);
+        assert!(!status.holding);
+        assert_eq!(
+            ConditionEvaluator::satisfaction(&sentiment(0), &history),
+            vec![false; 3]
+        );
+    }
+
+    #[test]
+    fn test_consensus_band() {
+        let condition = BeliefCondition::ModelConsensus {
+            min_models: 3,
+            convergence_band: 0.1,
+            persistence_window: 0,
+        };
+
+        let mut dispersed = bsi(0, 0.5, 0.0);
+        dispersed.volatility = 0.3;
+        assert!(!ConditionEvaluator::is_met(&condition, &[dispersed]));
+
+        let mut too_few = bsi(0, 0.5, 0.0);
+        too_few.signal_count = 2;
+        assert!(!ConditionEvaluator::is_met(&condition, &[too_few]));
+
+        assert!(ConditionEvaluator::is_met(&condition, &[bsi(0, 0.5, 0.0)]));
+    }
+
+    #[test]
+    fn test_velocity_acceleration() {
+        let condition = BeliefCondition::NarrativeVelocity {
+            velocity_threshold: 0.5,
+            acceleration_threshold: 0.2,
+            persistence_window: 0,
+        };
+
+        let steady = vec![bsi(0, 0.0, 0.6), bsi(100, 0.0, 0.6)];
+        assert!(!ConditionEvaluator::is_met(&condition, &steady));
+
+        let accelerating = vec![bsi(0, 0.0, 0.3), bsi(100, 0.0, 0.6)];
+        assert!(ConditionEvaluator::is_met(&condition, &accelerating));
+    }
+
+    #[test]
+    fn test_acceleration_checked_at_onset() {
+        let condition = BeliefCondition::NarrativeVelocity {
+            velocity_threshold: 0.5,
+            acceleration_threshold: 0.2,
+            persistence_window: 1800,
+        };
+
+        // Velocity jumps, then holds steady for the whole window
+        let history = vec![
+            bsi(0, 0.0, 0.3),
+            bsi(600, 0.0, 0.6),
+            bsi(1200, 0.0, 0.6),
+            bsi(2400, 0.0, 0.6),
+        ];
+        assert!(ConditionEvaluator::is_met(&condition, &history));
+
+        // High from the start: no onset to measure
+        let history = vec![bsi(0, 0.0, 0.6), bsi(1200, 0.0, 0.6), bsi(2400, 0.0, 0.6)];
+        assert!(!ConditionEvaluator::is_met(&condition, &history));
+    }
+
+    #[test]
+    fn test_acceleration_independent_of_sampling_rate() {
+        let condition = BeliefCondition::NarrativeVelocity {
+            velocity_threshold: 0.5,
+            acceleration_threshold: 0.5,
+            persistence_window: 0,
+        };
+
+        // Velocity rises by 0.6 over an hour, sampled every 10 and every 1 minutes
+        let ramp = |step: i64| -> Vec<BeliefStateIndex> {
+            (0..=3600 / step)
+                .map(|i| bsi(i * step, 0.0, 0.6 * (i * step) as f64 / 3600.0))
+                .collect()
+        };
+        assert!(ConditionEvaluator::is_met(&condition, &ramp(600)));
+        assert!(ConditionEvaluator::is_met(&condition, &ramp(60)));
+
+        // Same ramp spread over ten hours accelerates too slowly
+        let slow: Vec<BeliefStateIndex> = (0..=60)
+            .map(|i| bsi(i * 600, 0.0, 0.6 * i as f64 / 60.0))
+            .collect();
+        assert!(!ConditionEvaluator::is_met(&condition, &slow));
+    }
+
+    #[test]
+    fn test_status_reports_persistence() {
+        let condition = sentiment(300);
+        let mut history = vec![bsi(0, -0.3, 0.0), bsi(100, 0.7, 0.0), bsi(250, 0.8, 0.0)];
+
+        let status = ConditionEvaluator::evaluate(&condition, &history);
+        assert!(status.holding);
+        assert!(!status.satisfied);
+        assert_eq!(status.first_true_at, Some(100));
+        assert_eq!(status.held_for, 150);
+        assert_eq!(status.remaining(), 150);
+
+        history.push(bsi(400, 0.9, 0.0));
+        history.push(bsi(500, 0.9, 0.0));
+        let status = condition.evaluate(&history);
+        assert!(status.satisfied);
+        assert_eq!(status.satisfied_at, Some(400));
+        assert_eq!(status.held_for, 400);
+
+        history.push(bsi(600, 0.1, 0.0));
+        let status = condition.evaluate(&history);
+        assert!(!status.holding);
+        assert!(!status.satisfied);
+        assert_eq!(status.first_true_at, None);
+    }
+
+    #[test]
+    fn test_sequence_max_gap() {
+        let spike_then_bullish = BeliefCondition::Sequence {
+            steps: vec![velocity(0), sentiment(0)],
+            max_gap: 600,
+        };
+
+        let in_time = vec![bsi(0, -0.3, 0.9), bsi(300, 0.7, 0.1), bsi(900, 0.7, 0.1)];
+        assert!(ConditionEvaluator::is_met(&spike_then_bullish, &in_time));
+
+        let too_late = vec![bsi(0, -0.3, 0.9), bsi(300, -0.3, 0.1), bsi(900, 0.7, 0.1)];
+        assert!(!ConditionEvaluator::is_met(&spike_then_bullish, &too_late));
+
+        let wrong_order = vec![
+            bsi(0, -0.3, 0.1),
+            bsi(100, 0.7, 0.1),
+            bsi(300, 0.0, 0.9),
+            bsi(600, 0.0, 0.1),
+        ];
+        assert!(!ConditionEvaluator::is_met(
+            &spike_then_bullish,
+            &wrong_order
+        ));
+    }
+}
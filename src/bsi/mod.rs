@@ -5,11 +5,16 @@
 
 pub mod aggregator;
 pub mod calculator;
+pub mod evaluator;
 pub mod monitor;
 
 pub use crate::types::belief::BeliefStateIndex;
 pub use aggregator::SignalAggregator;
 pub use calculator::BsiCalculator;
+pub use crate::types::custom::{
+    CustomConditionHandler, CustomConditionRegistry, CustomParameters, ParameterSpec,
+};
+pub use evaluator::ConditionEvaluator;
 pub use monitor::BeliefMonitor;
 
 use crate::types::belief::BeliefSignal;
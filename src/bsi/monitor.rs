@@ -4,8 +4,11 @@ use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use crate::types::belief::{BeliefInflection, BeliefStateIndex, InflectionType};
+use crate::types::belief::{
+    BeliefCondition, BeliefInflection, BeliefStateIndex, ConditionStatus, InflectionType,
+};
 use crate::error::Result;
+use crate::types::custom::CustomConditionRegistry;
 
 /// Callback type for inflection events
 pub type InflectionCallback = Arc<dyn Fn(BeliefInflection) + Send + Sync>;
@@ -223,6 +226,23 @@ impl BeliefMonitor {
         Ok(persisted && duration as u64 >= self.min_persistence)
     }
 
+    /// Evaluate a market's belief condition against the monitored history
+    pub async fn evaluate_condition(&self, condition: &BeliefCondition) -> ConditionStatus {
+        let history = self.history.read().await;
+        condition.evaluate(&history)
+    }
+
+    /// Evaluate a market's belief condition, resolving custom conditions
+    /// through `registry`
+    pub async fn evaluate_condition_with(
+        &self,
+        condition: &BeliefCondition,
+        registry: &CustomConditionRegistry,
+    ) -> ConditionStatus {
+        let history = self.history.read().await;
+        condition.evaluate_with(&history, registry)
+    }
+
     /// Trigger all registered callbacks
     async fn trigger_callbacks(&self, inflection: BeliefInflection) {
         let callbacks = self.callbacks.read().await;
@@ -297,4 +317,31 @@ mod tests {
             assert_eq!(inflection.inflection_type, InflectionType::ThresholdCrossing);
         }
     }
+
+    #[tokio::test]
+    async fn test_evaluate_condition() {
+        let monitor = BeliefMonitor::new(0.5, 60);
+        let condition = BeliefCondition::SentimentShift {
+            from_polarity: -0.2,
+            to_polarity: 0.6,
+            persistence_window: 100,
+        };
+
+        for (last_updated, value) in [(1000, -0.4), (1100, 0.7), (1200, 0.8)] {
+            let bsi = BeliefStateIndex {
+                value,
+                velocity: 0.0,
+                volatility: 0.0,
+                last_updated,
+                confidence: 0.8,
+                signal_count: 5,
+                domain: "BTC".to_string(),
+            };
+            monitor.update(bsi).await.unwrap();
+        }
+
+        let status = monitor.evaluate_condition(&condition).await;
+        assert!(status.satisfied);
+        assert_eq!(status.first_true_at, Some(1100));
+    }
 }
@@ -9,18 +9,35 @@ use solana_sdk::{
     signer::Signer,
     transaction::Transaction,
 };
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::RwLock;
 
 use crate::{
     bsi::BeliefStateIndex,
     error::{PredaError, Result},
-    market::MarketManager,
+    market::{
+        distribution::InflectionTimeDistribution,
+        maker::{self, MarketMaker, Strategy},
+        merkle::{Hash, PayoutProof},
+        optimizer::{self, Allocation, SizingConfig},
+        orders::OrderEngine,
+        range,
+        risk::{Placement, RiskGuard, RiskLimits},
+        settlement::SettlementReport,
+        simulation::{self, PayoutMatrix},
+        valuation::{self, MarketExposure, MarkToMarket, PortfolioValuation},
+        MarketManager,
+    },
     oracle::OracleClient,
     types::{
-        belief::BeliefCondition,
-        market::{Market, MarketConfig, MarketType},
-        position::{Position, TimeBucket, TimeBucketAggregate},
+        belief::{BeliefCondition, BeliefInflection},
+        custom::CustomConditionRegistry,
+        market::{Market, MarketConfig, MarketType, PricingMode},
+        position::{
+            Listing, Position, PositionGroup, RangeWeighting, TimeBucket, TimeBucketAggregate,
+        },
     },
 };
 
@@ -40,6 +57,16 @@ pub struct PredaClient {
 
     /// Oracle client
     oracle_client: OracleClient,
+
+    /// Client-side exposure limits, checked before placing positions
+    risk_guard: Option<RiskGuard>,
+
+    /// Registry for custom belief conditions; the global registry if unset
+    custom_conditions: Option<CustomConditionRegistry>,
+
+    /// `MarketConfig::domain` of each market seen by risk checks; a market's
+    /// domain never changes
+    domains: RwLock<HashMap<Pubkey, Option<String>>>,
 }
 
 impl PredaClient {
@@ -85,9 +112,33 @@ impl PredaClient {
             program_id,
             market_manager,
             oracle_client,
+            risk_guard: None,
+            custom_conditions: None,
+            domains: RwLock::new(HashMap::new()),
         })
     }
 
+    /// Enforce exposure limits on every position placed by this client
+    ///
+    /// Placements that would exceed a limit fail with
+    /// `PredaError::RiskLimitExceeded` before any transaction is built.
+    pub fn with_risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.risk_guard = Some(RiskGuard::new(limits));
+        self
+    }
+
+    /// Validate custom belief conditions against `registry` instead of the
+    /// global registry
+    pub fn with_custom_conditions(mut self, registry: CustomConditionRegistry) -> Self {
+        self.custom_conditions = Some(registry);
+        self
+    }
+
+    /// Exposure limits in force, if any
+    pub fn risk_limits(&self) -> Option<&RiskLimits> {
+        self.risk_guard.as_ref().map(RiskGuard::limits)
+    }
+
     /// Create a new time-shifted prediction market
     ///
     /// # Arguments
@@ -138,9 +189,11 @@ impl PredaClient {
         config: MarketConfig,
     ) -> Result<Market> {
         // Validate inputs
-        belief_condition
-            .validate()
-            .map_err(|e| PredaError::InvalidBeliefCondition(e))?;
+        let now = chrono::Utc::now().timestamp();
+        match &self.custom_conditions {
+            Some(registry) => belief_condition.validate_for_market_with(&config, now, registry)?,
+            None => belief_condition.validate_for_market(&config, now)?,
+        }
         config
             .validate()
             .map_err(|e| PredaError::Configuration(e))?;
@@ -186,11 +239,173 @@ impl PredaClient {
         time_bucket_start: i64,
         amount: u64,
     ) -> Result<Position> {
+        if self.risk_guard.is_some() {
+            let market = self.get_market(market_address).await?;
+            let time_bucket =
+                TimeBucket::from_duration(time_bucket_start, market.config.time_bucket_size);
+            self.check_risk(market_address, &[(time_bucket, amount)]).await?;
+        }
+
         self.market_manager
             .place_position(&self.keypair, market_address, time_bucket_start, amount)
             .await
     }
 
+    /// Place positions across a range of time buckets
+    ///
+    /// # Arguments
+    /// * `market_address` - Market to place positions in
+    /// * `start` - Start of the first time bucket
+    /// * `end` - End of the last time bucket
+    /// * `amount` - Total amount to stake in lamports
+    /// * `weighting` - How the stake is split across buckets
+    pub async fn place_range_position(
+        &self,
+        market_address: &Pubkey,
+        start: i64,
+        end: i64,
+        amount: u64,
+        weighting: RangeWeighting,
+    ) -> Result<PositionGroup> {
+        if self.risk_guard.is_some() {
+            let market = self.get_market(market_address).await?;
+            let split = range::split_range(&market, start, end, amount, weighting)?;
+            self.check_risk(market_address, &split).await?;
+        }
+
+        self.market_manager
+            .place_range_position(&self.keypair, market_address, start, end, amount, weighting)
+            .await
+    }
+
+    /// Place a batch of positions, e.g. an optimizer `Allocation`
+    pub async fn place_positions(
+        &self,
+        market_address: &Pubkey,
+        stakes: &[(TimeBucket, u64)],
+    ) -> Result<Vec<Position>> {
+        self.check_risk(market_address, stakes).await?;
+        self.market_manager
+            .place_positions(&self.keypair, market_address, stakes)
+            .await
+    }
+
+    /// Place every stake of an allocation
+    pub async fn place_allocation(
+        &self,
+        market_address: &Pubkey,
+        allocation: &Allocation,
+    ) -> Result<Vec<Position>> {
+        self.place_positions(market_address, &allocation.stakes).await
+    }
+
+    /// Run one market-making step with this client's keypair
+    ///
+    /// Like [`MarketMaker::step`], but placements go through
+    /// `place_positions`, so the client's risk limits also apply.
+    pub async fn make_market<S: Strategy>(
+        &self,
+        market_maker: &mut MarketMaker<S>,
+        market_address: &Pubkey,
+    ) -> Result<Vec<Position>> {
+        let market = self.get_market(market_address).await?;
+        let aggregates = self.get_time_bucket_aggregates(market_address).await?;
+        let bsi = self.get_belief_state_index(market_address).await?;
+        let inventory = maker::inventory(&self.get_user_positions(market_address).await?);
+
+        let now = chrono::Utc::now().timestamp();
+        let stakes = market_maker.plan(&market, &aggregates, &inventory, &bsi, now);
+        if stakes.is_empty() {
+            return Ok(vec![]);
+        }
+        self.place_positions(market_address, &stakes).await
+    }
+
+    /// Check stakes about to be placed in a market against the risk limits
+    async fn check_risk(
+        &self,
+        market_address: &Pubkey,
+        stakes: &[(TimeBucket, u64)],
+    ) -> Result<()> {
+        let Some(guard) = &self.risk_guard else {
+            return Ok(());
+        };
+
+        let positions = self
+            .market_manager
+            .get_positions_by_owner(&self.keypair.pubkey())
+            .await?;
+        let mut markets: Vec<Pubkey> = positions.iter().map(|pos| pos.market).collect();
+        markets.push(*market_address);
+        markets.sort();
+        markets.dedup();
+
+        let mut domains = HashMap::new();
+        for market in markets {
+            let cached = self.domains.read().await.get(&market).cloned();
+            let domain = match cached {
+                Some(domain) => domain,
+                None => {
+                    let domain = self.get_market(&market).await?.config.domain;
+                    self.domains.write().await.insert(market, domain.clone());
+                    domain
+                }
+            };
+            if let Some(domain) = domain {
+                domains.insert(market, domain);
+            }
+        }
+
+        let placements: Vec<Placement> = stakes
+            .iter()
+            .map(|&(time_bucket, amount)| Placement {
+                market: *market_address,
+                time_bucket,
+                amount,
+            })
+            .collect();
+        guard.check(
+            &positions,
+            &domains,
+            &placements,
+            chrono::Utc::now().timestamp(),
+        )
+    }
+
+    /// Fire the pending conditional orders of a market whose conditions hold
+    ///
+    /// Fetches the market's aggregates and BSI, places a position for every
+    /// triggered order and records the outcome in `engine`. Triggered orders
+    /// are saved as placing first, so a crash mid-placement cannot place them
+    /// again. An order whose placement fails is marked failed rather than
+    /// aborting the others.
+    pub async fn process_orders(
+        &self,
+        engine: &mut OrderEngine,
+        market_address: &Pubkey,
+    ) -> Result<Vec<Position>> {
+        let aggregates = self.get_time_bucket_aggregates(market_address).await?;
+        let bsi = self.get_belief_state_index(market_address).await?;
+        let now = chrono::Utc::now().timestamp();
+
+        let mut positions = Vec::new();
+        for order in engine.evaluate(market_address, &aggregates, &bsi, now)? {
+            let request = &order.request;
+            match self
+                .place_position(market_address, request.time_bucket_start, request.amount)
+                .await
+            {
+                Ok(position) => {
+                    engine.mark_filled(order.id, &position)?;
+                    positions.push(position);
+                }
+                Err(e) => engine.mark_failed(order.id, e.to_string())?,
+            }
+        }
+
+        Ok(positions)
+    }
+
     /// Get user's positions in a market
     pub async fn get_user_positions(&self, market_address: &Pubkey) -> Result<Vec<Position>> {
         self.market_manager
@@ -198,6 +413,111 @@ impl PredaClient {
             .await
     }
 
+    /// Transfer one of the user's positions to another owner
+    pub async fn transfer_position(
+        &self,
+        position_address: &Pubkey,
+        new_owner: &Pubkey,
+    ) -> Result<Position> {
+        self.market_manager
+            .transfer_position(&self.keypair, position_address, new_owner)
+            .await
+    }
+
+    /// List one of the user's positions for sale
+    ///
+    /// # Arguments
+    /// * `position_address` - Position to sell
+    /// * `price` - Asking price in lamports
+    /// * `expires_at` - Timestamp from which the listing can no longer be bought
+    pub async fn list_position(
+        &self,
+        position_address: &Pubkey,
+        price: u64,
+        expires_at: Option<i64>,
+    ) -> Result<Listing> {
+        self.market_manager
+            .list_position(&self.keypair, position_address, price, expires_at)
+            .await
+    }
+
+    /// Withdraw one of the user's listings
+    pub async fn cancel_listing(&self, listing_address: &Pubkey) -> Result<Signature> {
+        self.market_manager
+            .cancel_listing(&self.keypair, listing_address)
+            .await
+    }
+
+    /// Buy a listed position, paying at most `max_price` lamports
+    pub async fn buy_listing(&self, listing_address: &Pubkey, max_price: u64) -> Result<Position> {
+        self.market_manager
+            .buy_listing(&self.keypair, listing_address, max_price)
+            .await
+    }
+
+    /// Get open listings in a market
+    pub async fn get_listings(&self, market_address: &Pubkey) -> Result<Vec<Listing>> {
+        self.market_manager.get_listings(market_address).await
+    }
+
+    /// Fair price of an open position under the market-implied distribution
+    ///
+    /// The position is settled against the rest of the market's current pool.
+    /// Returns `None` while no time bucket holds stake. See
+    /// [`valuation::fair_value`].
+    pub async fn fair_value(&self, position: &Position) -> Result<Option<u64>> {
+        let market = self.get_market(&position.market).await?;
+        let aggregates = self.get_time_bucket_aggregates(&position.market).await?;
+        InflectionTimeDistribution::from_aggregates(&aggregates)
+            .map(|distribution| {
+                valuation::fair_value(&market, position, &aggregates, &distribution)
+            })
+            .transpose()
+    }
+
+    /// Mark a position to market under the market-implied distribution
+    ///
+    /// Returns `None` while no time bucket holds stake.
+    pub async fn mark_to_market(&self, position: &Position) -> Result<Option<MarkToMarket>> {
+        let market = self.get_market(&position.market).await?;
+        let aggregates = self.get_time_bucket_aggregates(&position.market).await?;
+        InflectionTimeDistribution::from_aggregates(&aggregates)
+            .map(|distribution| position.mark_to_market(&market, &aggregates, &distribution))
+            .transpose()
+    }
+
+    /// Value the user's positions in a market together
+    ///
+    /// Returns `None` if the user holds no positions in the market.
+    pub async fn get_market_exposure(
+        &self,
+        market_address: &Pubkey,
+    ) -> Result<Option<MarketExposure>> {
+        let positions = self.get_user_positions(market_address).await?;
+        if positions.is_empty() {
+            return Ok(None);
+        }
+
+        let market = self.get_market(market_address).await?;
+        let aggregates = self.get_time_bucket_aggregates(market_address).await?;
+        let distribution =
+            InflectionTimeDistribution::from_aggregates(&aggregates).ok_or_else(|| {
+                PredaError::Generic(format!("market {} has no staked buckets", market_address))
+            })?;
+        MarketExposure::mark(&market, &positions, &aggregates, &distribution).map(Some)
+    }
+
+    /// Roll up the user's exposure across markets
+    pub async fn get_portfolio(&self, market_addresses: &[Pubkey]) -> Result<PortfolioValuation> {
+        let mut markets = Vec::new();
+        for market_address in market_addresses {
+            if let Some(exposure) = self.get_market_exposure(market_address).await? {
+                markets.push(exposure);
+            }
+        }
+        Ok(PortfolioValuation::from_markets(markets))
+    }
+
     /// Get all positions for a time bucket
     pub async fn get_time_bucket_positions(
         &self,
@@ -219,17 +539,159 @@ impl PredaClient {
             .await
     }
 
+    /// Get the market-implied distribution of the inflection time
+    ///
+    /// Returns `None` while no time bucket holds stake.
+    pub async fn get_inflection_distribution(
+        &self,
+        market_address: &Pubkey,
+    ) -> Result<Option<InflectionTimeDistribution>> {
+        let aggregates = self.get_time_bucket_aggregates(market_address).await?;
+        Ok(InflectionTimeDistribution::from_aggregates(&aggregates))
+    }
+
+    /// Recommend stakes per bucket for our belief about the inflection time
+    ///
+    /// Other participants' stake is taken from the market's current time
+    /// bucket aggregates.
+    pub async fn optimize_allocation(
+        &self,
+        market: &Market,
+        belief: &InflectionTimeDistribution,
+        sizing: &SizingConfig,
+    ) -> Result<Allocation> {
+        let aggregates = self.get_time_bucket_aggregates(&market.address).await?;
+        optimizer::optimize_allocation(market, &aggregates, belief, sizing)
+    }
+
+    /// Simulate what positions would pay at hypothetical inflection times
+    ///
+    /// Other participants' stake is taken from the market's current time
+    /// bucket aggregates. Positions that are already placed are taken out of
+    /// the aggregates and the pool first, so they are counted once.
+    ///
+    /// In `PricingMode::Lmsr` markets positions pay by their shares instead:
+    /// placed positions use the shares they hold, and new ones the shares
+    /// their net stake would buy from the market maker, in order.
+    pub async fn simulate_payouts(
+        &self,
+        market: &Market,
+        positions: &[Position],
+        inflection_times: &[i64],
+        sharpness: f64,
+    ) -> Result<PayoutMatrix> {
+        if let PricingMode::Lmsr { .. } = market.config.pricing_mode {
+            let holdings = self.share_holdings(market, positions).await?;
+            return Ok(simulation::simulate_share_payouts(
+                market,
+                &holdings,
+                inflection_times,
+                sharpness,
+            ));
+        }
+
+        let aggregates = self.get_time_bucket_aggregates(&market.address).await?;
+        let placed: Vec<Position> = self
+            .market_manager
+            .get_market_positions(&market.address)
+            .await?
+            .into_iter()
+            .filter(|placed| positions.iter().any(|pos| pos.address == placed.address))
+            .collect();
+        let (market, aggregates) = simulation::exclude_positions(market, &aggregates, &placed);
+        simulation::simulate_payouts(&market, positions, &aggregates, inflection_times, sharpness)
+    }
+
+    /// Shares each position holds in an LMSR market, or would buy if new
+    async fn share_holdings(
+        &self,
+        market: &Market,
+        positions: &[Position],
+    ) -> Result<Vec<(Position, u64)>> {
+        let placed = self.market_manager.get_market_positions(&market.address).await?;
+        let mut maker = self.market_manager.get_market_maker(&market.address).await?;
+
+        let mut holdings = Vec::with_capacity(positions.len());
+        for position in positions {
+            let shares = if placed.iter().any(|pos| pos.address == position.address) {
+                self.market_manager.get_position_shares(&position.address).await?
+            } else {
+                let index = maker.bucket_index(position.time_bucket.start).ok_or_else(|| {
+                    PredaError::InvalidTimeBucket(format!(
+                        "No LMSR bucket starts at {}",
+                        position.time_bucket.start
+                    ))
+                })?;
+                let shares =
+                    maker.shares_for_cost(index, position.net_stake(&market.config.fees));
+                if shares > 0 {
+                    maker.buy(index, shares)?;
+                }
+                shares
+            };
+            holdings.push((position.clone(), shares));
+        }
+        Ok(holdings)
+    }
+
     /// Withdraw position before market resolution
+    ///
+    /// Returns `Position::withdrawal_value` to the owner; the placement and
+    /// withdrawal fees are kept.
     pub async fn withdraw_position(&self, position_address: &Pubkey) -> Result<Signature> {
         self.market_manager
             .withdraw_position(&self.keypair, position_address)
             .await
     }
 
+    /// Settle a market's positions against its validated inflections
+    ///
+    /// See [`MarketManager::settle_market`]. Losses settled today count
+    /// toward the daily loss limit of later placements.
+    pub async fn settle_market(
+        &self,
+        market_address: &Pubkey,
+        inflections: &[BeliefInflection],
+    ) -> Result<SettlementReport> {
+        self.market_manager
+            .settle_market(market_address, inflections)
+            .await
+    }
+
+    /// Publish the settlement Merkle root for a market
+    ///
+    /// Build the tree with `SettlementTree::from_report` and publish its root.
+    pub async fn publish_settlement_root(
+        &self,
+        market_address: &Pubkey,
+        root: Hash,
+    ) -> Result<Signature> {
+        self.market_manager
+            .publish_settlement_root(&self.keypair, market_address, root)
+            .await
+    }
+
     /// Claim payout from settled position
-    pub async fn claim_payout(&self, position_address: &Pubkey) -> Result<Signature> {
+    ///
+    /// The proof comes from `SettlementTree::proof` for the published root.
+    pub async fn claim_payout(
+        &self,
+        position_address: &Pubkey,
+        proof: &PayoutProof,
+    ) -> Result<Signature> {
         self.market_manager
-            .claim_payout(&self.keypair, position_address)
+            .claim_payout(&self.keypair, position_address, proof)
+            .await
+    }
+
+    /// Claim refund of a position in a cancelled or expired market
+    ///
+    /// Returns the refunded amount along with the transaction signature.
+    /// Use `SettlementCalculator::refund_amount` to check what is recoverable
+    /// without claiming.
+    pub async fn claim_refund(&self, position_address: &Pubkey) -> Result<(u64, Signature)> {
+        self.market_manager
+            .claim_refund(&self.keypair, position_address)
             .await
     }
 
@@ -257,6 +719,23 @@ impl PredaClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::RiskLimit;
+
+    /// Client on a local RPC endpoint with a throwaway program id
+    fn client() -> PredaClient {
+        let rpc_client = Arc::new(RpcClient::new("http://localhost:8899".to_string()));
+        let program_id = Pubkey::new_unique();
+        PredaClient {
+            market_manager: MarketManager::new(Arc::clone(&rpc_client), program_id),
+            oracle_client: OracleClient::new(Arc::clone(&rpc_client)),
+            rpc_client,
+            keypair: Arc::new(Keypair::new()),
+            program_id,
+            risk_guard: None,
+            custom_conditions: None,
+            domains: RwLock::new(HashMap::new()),
+        }
+    }
 
     #[tokio::test]
     async fn test_client_creation() {
@@ -266,4 +745,151 @@ mod tests {
         // We expect this to potentially fail in test environment
         assert!(result.is_ok() || result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_domain_limit_counts_market_domains() {
+        let client = client().with_risk_limits(RiskLimits {
+            max_domain_stake: Some(15_000_000),
+            ..RiskLimits::default()
+        });
+        let condition = BeliefCondition::SentimentShift {
+            from_polarity: -0.2,
+            to_polarity: 0.6,
+            persistence_window: 3600,
+        };
+        let mut markets = Vec::new();
+        for domain in ["BTC", "ETH"] {
+            let config = MarketConfig {
+                domain: Some(domain.to_string()),
+                ..MarketConfig::default()
+            };
+            let market = client
+                .create_market_with_config(
+                    MarketType::SentimentTransition,
+                    condition.clone(),
+                    domain,
+                    config,
+                )
+                .await
+                .unwrap();
+            markets.push(market);
+        }
+        let (btc, eth) = (&markets[0], &markets[1]);
+
+        client
+            .place_position(&btc.address, btc.created_at, 10_000_000)
+            .await
+            .unwrap();
+        // Another domain has its own limit
+        client
+            .place_position(&eth.address, eth.created_at, 10_000_000)
+            .await
+            .unwrap();
+        assert!(matches!(
+            client
+                .place_position(&btc.address, btc.created_at, 10_000_000)
+                .await,
+            Err(PredaError::RiskLimitExceeded {
+                limit: RiskLimit::DomainStake(domain),
+                ..
+            }) if domain == "BTC"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_custom_conditions_use_client_registry() {
+        use crate::types::custom::{CustomConditionHandler, CustomParameters, ParameterSpec};
+
+        struct Confident;
+
+        impl CustomConditionHandler for Confident {
+            fn parameters(&self) -> Vec<ParameterSpec> {
+                vec![ParameterSpec::required("min_confidence", 0.0, 1.0)]
+            }
+
+            fn holds(
+                &self,
+                parameters: &CustomParameters,
+                history: &[BeliefStateIndex],
+                index: usize,
+            ) -> bool {
+                history[index].confidence >= parameters.get_or("min_confidence", 1.0)
+            }
+        }
+
+        let condition = BeliefCondition::Custom {
+            condition_type: "confident".to_string(),
+            parameters: vec![("min_confidence".to_string(), 0.8)],
+            persistence_window: 600,
+        };
+        let create = |client: PredaClient| {
+            let condition = condition.clone();
+            async move {
+                client
+                    .create_market(MarketType::NarrativeVelocity, condition, "Confident")
+                    .await
+            }
+        };
+
+        // Nothing is registered globally
+        assert!(matches!(
+            create(client()).await,
+            Err(PredaError::InvalidBeliefCondition(_))
+        ));
+
+        let mut registry = CustomConditionRegistry::new();
+        registry.register("confident", Confident);
+        assert!(create(client().with_custom_conditions(registry)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_simulate_lmsr_payouts_by_shares() {
+        let client = client();
+        let config = MarketConfig {
+            expiration_time: chrono::Utc::now().timestamp() + 4 * 3600,
+            pricing_mode: PricingMode::Lmsr {
+                liquidity: 1_000_000_000,
+            },
+            ..MarketConfig::default()
+        };
+        let market = client
+            .create_market_with_config(
+                MarketType::SentimentTransition,
+                BeliefCondition::SentimentShift {
+                    from_polarity: -0.2,
+                    to_polarity: 0.6,
+                    persistence_window: 3600,
+                },
+                "LMSR market",
+                config,
+            )
+            .await
+            .unwrap();
+        let placed = client
+            .place_position(&market.address, market.created_at, 100_000_000)
+            .await
+            .unwrap();
+        let new = Position {
+            address: Pubkey::new_unique(),
+            ..placed.clone()
+        };
+
+        let inside = market.created_at + 1800;
+        let outside = market.created_at + 3 * 3600;
+        let matrix = client
+            .simulate_payouts(&market, &[placed.clone(), new], &[inside, outside], 0.5)
+            .await
+            .unwrap();
+
+        let fees = &market.config.fees;
+        let shares = client
+            .market_manager
+            .get_position_shares(&placed.address)
+            .await
+            .unwrap();
+        assert_eq!(matrix.payouts[0][0], shares - fees.settlement_fee(shares));
+        // The same stake buys fewer shares after the placed position
+        assert!(0 < matrix.payouts[0][1] && matrix.payouts[0][1] < matrix.payouts[0][0]);
+        assert_eq!(matrix.totals()[1], 0);
+    }
 }
@@ -8,9 +8,9 @@ pub type Result<T> = std::result::Result<T, PredaError>;
 /// Comprehensive error types for Preda SDK
 #[derive(Error, Debug)]
 pub enum PredaError {
-    /// Solana client errors
+    /// Solana client errors, boxed to keep `PredaError` small
     #[error("Solana client error: {0}")]
-    SolanaClient(#[from] solana_client::client_error::ClientError),
+    SolanaClient(#[from] Box<solana_client::client_error::ClientError>),
 
     /// Solana SDK errors
     #[error("Solana SDK error: {0}")]
@@ -38,7 +38,7 @@ pub enum PredaError {
 
     /// Invalid belief condition
     #[error("Invalid belief condition: {0}")]
-    InvalidBeliefCondition(String),
+    InvalidBeliefCondition(#[from] ConditionError),
 
     /// Oracle error
     #[error("Oracle error: {0}")]
@@ -60,10 +60,42 @@ pub enum PredaError {
     #[error("Market already resolved at timestamp: {0}")]
     MarketAlreadyResolved(i64),
 
+    /// Position cannot be refunded
+    #[error("Position not refundable: {0}")]
+    NotRefundable(String),
+
+    /// Position cannot be withdrawn
+    #[error("Position not withdrawable: {0}")]
+    NotWithdrawable(String),
+
+    /// Position cannot be transferred or sold
+    #[error("Position not transferable: {0}")]
+    NotTransferable(String),
+
+    /// Secondary-market listing not found
+    #[error("Listing not found: {0}")]
+    ListingNotFound(String),
+
+    /// Payout proof does not match the claim
+    #[error("Invalid payout proof: {0}")]
+    InvalidProof(String),
+
+    /// Payout was already claimed
+    #[error("Payout already claimed: {0}")]
+    AlreadyClaimed(String),
+
     /// Invalid time bucket
     #[error("Invalid time bucket: {0}")]
     InvalidTimeBucket(String),
 
+    /// Conditional order not found
+    #[error("Order not found: {0}")]
+    OrderNotFound(u64),
+
+    /// Local state could not be read or written
+    #[error("Storage error: {0}")]
+    Storage(String),
+
     /// Threshold not met
     #[error("Belief threshold not met: current {current}, required {required}")]
     ThresholdNotMet { current: f64, required: f64 },
@@ -72,6 +104,10 @@ pub enum PredaError {
     #[error("Persistence window not satisfied: duration {duration}s, required {required}s")]
     PersistenceNotSatisfied { duration: u64, required: u64 },
 
+    /// Placement blocked by a client-side risk limit
+    #[error("Risk limit exceeded: {limit} at {value}, limit {max}")]
+    RiskLimitExceeded { limit: RiskLimit, value: u64, max: u64 },
+
     /// Configuration error
     #[error("Configuration error: {0}")]
     Configuration(String),
@@ -89,6 +125,111 @@ pub enum PredaError {
     Generic(String),
 }
 
+/// Client-side risk limit that blocked a placement
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RiskLimit {
+    /// Total active stake in one market
+    #[error("stake in market {0}")]
+    MarketStake(String),
+
+    /// Total active stake across markets of one BSI domain
+    #[error("stake in domain {0}")]
+    DomainStake(String),
+
+    /// Total active stake across all markets
+    #[error("total stake")]
+    TotalStake,
+
+    /// Active stake in one time bucket of a market
+    #[error("stake in bucket starting {0}")]
+    BucketStake(i64),
+
+    /// Net loss realized today (UTC)
+    #[error("daily loss")]
+    DailyLoss,
+}
+
+/// Belief condition validation failure naming the offending field
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{field}: {kind}")]
+pub struct ConditionError {
+    /// Path to the failing field, e.g. `steps[1].threshold`
+    pub field: String,
+
+    /// What is wrong with the field
+    pub kind: ConditionErrorKind,
+}
+
+/// Reasons a belief condition field fails validation
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConditionErrorKind {
+    /// Value is NaN or infinite
+    #[error("value must be finite, got {0}")]
+    NotFinite(f64),
+
+    /// Value outside its allowed range
+    #[error("value {actual} outside allowed range {min} to {max}")]
+    OutOfRange { min: f64, max: f64, actual: f64 },
+
+    /// Value makes the condition trivially satisfied or impossible
+    #[error("degenerate condition: {0}")]
+    Degenerate(String),
+
+    /// Persistence window or gap must be greater than 0
+    #[error("must be greater than 0")]
+    Zero,
+
+    /// Composite condition has too few nested conditions
+    #[error("requires at least {min} conditions, got {actual}")]
+    TooFewConditions { min: usize, actual: usize },
+
+    /// Persistence window cannot complete before the market expires
+    #[error("window {window}s plus one {bucket}s bucket exceeds market lifetime {lifetime}s")]
+    ExceedsMarketLifetime { window: u64, bucket: u64, lifetime: i64 },
+
+    /// Custom condition type has no registered handler
+    #[error("unknown custom condition type {0}")]
+    UnknownConditionType(String),
+
+    /// Custom condition parameter is not declared by its handler
+    #[error("unknown parameter")]
+    UnknownParameter,
+
+    /// Custom condition parameter appears more than once
+    #[error("duplicate parameter")]
+    DuplicateParameter,
+
+    /// Required custom condition parameter is missing
+    #[error("missing required parameter")]
+    MissingParameter,
+
+    /// Custom condition handler rejected the parameters
+    #[error("rejected by handler: {0}")]
+    Rejected(String),
+}
+
+impl ConditionError {
+    /// Create an error for a field
+    pub fn new(field: impl Into<String>, kind: ConditionErrorKind) -> Self {
+        Self {
+            field: field.into(),
+            kind,
+        }
+    }
+
+    /// Prefix the field path with the location of a nested condition
+    pub fn nested(mut self, prefix: &str) -> Self {
+        self.field = format!("{}.{}", prefix, self.field);
+        self
+    }
+}
+
+impl From<solana_client::client_error::ClientError> for PredaError {
+    fn from(err: solana_client::client_error::ClientError) -> Self {
+        PredaError::SolanaClient(Box::new(err))
+    }
+}
+
 impl From<solana_sdk::pubkey::ParsePubkeyError> for PredaError {
     fn from(err: solana_sdk::pubkey::ParsePubkeyError) -> Self {
         PredaError::InvalidPublicKey(err.to_string())
@@ -53,7 +53,7 @@ pub mod types;
 pub use client::PredaClient;
 pub use error::{PredaError, Result};
 pub use types::{
-    belief::{BeliefCondition, BeliefInflection, BeliefSignal},
+    belief::{BeliefCondition, BeliefInflection, BeliefSignal, ConditionStatus},
     market::{Market, MarketState, MarketType},
     position::{Position, TimeBucket},
 };
@@ -0,0 +1,284 @@
+//! Implied inflection-time distribution from time bucket stakes
+//!
+//! Stake in each bucket is read as probability mass spread uniformly over the
+//! bucket. Overlapping buckets add their densities, gaps between buckets have
+//! zero density, and buckets of different widths are handled by working with
+//! densities rather than raw bucket shares.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::position::{TimeBucket, TimeBucketAggregate};
+
+/// Interval of constant probability density
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct DensitySegment {
+    /// Start timestamp (inclusive)
+    pub start: i64,
+
+    /// End timestamp (exclusive)
+    pub end: i64,
+
+    /// Probability that the inflection falls in this interval
+    pub probability: f64,
+}
+
+/// Market-implied probability distribution over the inflection time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InflectionTimeDistribution {
+    segments: Vec<DensitySegment>,
+}
+
+impl DensitySegment {
+    /// Segment width in seconds
+    pub fn width(&self) -> f64 {
+        (self.end - self.start) as f64
+    }
+
+    /// Probability density per second
+    pub fn density(&self) -> f64 {
+        self.probability / self.width()
+    }
+
+    /// Probability mass before `timestamp`
+    fn mass_before(&self, timestamp: f64) -> f64 {
+        let covered = (timestamp - self.start as f64).clamp(0.0, self.width());
+        self.probability * covered / self.width()
+    }
+}
+
+impl InflectionTimeDistribution {
+    /// Build the distribution from a market's time bucket aggregates
+    ///
+    /// Returns `None` when no bucket with a positive width holds stake.
+    pub fn from_aggregates(aggregates: &[TimeBucketAggregate]) -> Option<Self> {
+        let weights: Vec<(TimeBucket, f64)> = aggregates
+            .iter()
+            .map(|agg| (agg.time_bucket, agg.total_staked as f64))
+            .collect();
+        Self::from_weights(&weights)
+    }
+
+    /// Build a distribution from relative weights per bucket
+    ///
+    /// Weights need not sum to 1; non-positive and non-finite weights are
+    /// ignored. Returns `None` when no bucket with a positive width has weight.
+    pub fn from_weights(weights: &[(TimeBucket, f64)]) -> Option<Self> {
+        let buckets: Vec<&(TimeBucket, f64)> = weights
+            .iter()
+            .filter(|(bucket, weight)| {
+                weight.is_finite() && *weight > 0.0 && bucket.end > bucket.start
+            })
+            .collect();
+        let total: f64 = buckets.iter().map(|(_, weight)| weight).sum();
+        if total == 0.0 {
+            return None;
+        }
+
+        let mut boundaries: Vec<i64> = buckets
+            .iter()
+            .flat_map(|(bucket, _)| [bucket.start, bucket.end])
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let segments = boundaries
+            .windows(2)
+            .filter_map(|pair| {
+                let (start, end) = (pair[0], pair[1]);
+                let density: f64 = buckets
+                    .iter()
+                    .filter(|(bucket, _)| bucket.start <= start && end <= bucket.end)
+                    .map(|(bucket, weight)| weight / total / bucket.duration() as f64)
+                    .sum();
+
+                (density > 0.0).then(|| DensitySegment {
+                    start,
+                    end,
+                    probability: density * (end - start) as f64,
+                })
+            })
+            .collect();
+
+        Some(Self { segments })
+    }
+
+    /// Non-overlapping segments of constant density, in time order
+    pub fn segments(&self) -> &[DensitySegment] {
+        &self.segments
+    }
+
+    /// Earliest timestamp with positive density
+    pub fn start(&self) -> i64 {
+        self.segments.first().map_or(0, |segment| segment.start)
+    }
+
+    /// End of the last segment with positive density
+    pub fn end(&self) -> i64 {
+        self.segments.last().map_or(0, |segment| segment.end)
+    }
+
+    /// Probability density per second at `timestamp`
+    pub fn pdf(&self, timestamp: i64) -> f64 {
+        self.segments
+            .iter()
+            .find(|segment| segment.start <= timestamp && timestamp < segment.end)
+            .map_or(0.0, DensitySegment::density)
+    }
+
+    /// Probability that the inflection happens before `timestamp`
+    pub fn cdf(&self, timestamp: i64) -> f64 {
+        self.cdf_at(timestamp as f64)
+    }
+
+    /// Probability that the inflection happens before `timestamp`
+    pub fn probability_before(&self, timestamp: i64) -> f64 {
+        self.cdf(timestamp)
+    }
+
+    /// Probability that the inflection happens in `[start, end)`
+    pub fn probability_between(&self, start: i64, end: i64) -> f64 {
+        (self.cdf(end) - self.cdf(start)).max(0.0)
+    }
+
+    /// Expected inflection time
+    pub fn expected_time(&self) -> f64 {
+        self.segments
+            .iter()
+            .map(|segment| segment.probability * (segment.start as f64 + segment.width() / 2.0))
+            .sum()
+    }
+
+    /// Standard deviation of the inflection time (seconds)
+    pub fn std_dev(&self) -> f64 {
+        let mean = self.expected_time();
+        let variance: f64 = self
+            .segments
+            .iter()
+            .map(|segment| {
+                // Second moment of a uniform interval about the mean
+                let (a, b) = (segment.start as f64 - mean, segment.end as f64 - mean);
+                segment.probability * (a * a + a * b + b * b) / 3.0
+            })
+            .sum();
+        variance.max(0.0).sqrt()
+    }
+
+    /// Timestamp by which the inflection has happened with probability `q`
+    ///
+    /// `q` is clamped to [0, 1]; gaps between buckets are skipped.
+    pub fn quantile(&self, q: f64) -> f64 {
+        let q = if q.is_nan() { 0.5 } else { q.clamp(0.0, 1.0) };
+
+        let mut cumulative = 0.0;
+        for segment in &self.segments {
+            if cumulative + segment.probability >= q {
+                let fraction = (q - cumulative) / segment.probability;
+                return segment.start as f64 + fraction.clamp(0.0, 1.0) * segment.width();
+            }
+            cumulative += segment.probability;
+        }
+
+        self.end() as f64
+    }
+
+    /// Median inflection time
+    pub fn median(&self) -> f64 {
+        self.quantile(0.5)
+    }
+
+    /// Differential entropy of the inflection time (nats)
+    ///
+    /// Unlike the entropy of bucket shares, this does not change when a
+    /// bucket is split into smaller buckets with the same density.
+    pub fn entropy(&self) -> f64 {
+        self.segments
+            .iter()
+            .map(|segment| -segment.probability * segment.density().ln())
+            .sum()
+    }
+
+    fn cdf_at(&self, timestamp: f64) -> f64 {
+        self.segments
+            .iter()
+            .map(|segment| segment.mass_before(timestamp))
+            .sum::<f64>()
+            .min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate(start: i64, end: i64, total_staked: u64) -> TimeBucketAggregate {
+        TimeBucketAggregate {
+            time_bucket: TimeBucket::new(start, end).unwrap(),
+            total_staked,
+            position_count: 1,
+            implied_probability: 0.0,
+            avg_position_size: total_staked,
+        }
+    }
+
+    #[test]
+    fn test_uniform_bucket() {
+        let dist = InflectionTimeDistribution::from_aggregates(&[aggregate(0, 3600, 100)]).unwrap();
+
+        assert_eq!(dist.cdf(1800), 0.5);
+        assert_eq!(dist.median(), 1800.0);
+        assert_eq!(dist.expected_time(), 1800.0);
+        assert!((dist.entropy() - 3600f64.ln()).abs() < 1e-9);
+        assert!((dist.std_dev() - 3600.0 / 12f64.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gaps_and_unequal_buckets() {
+        // 25% over one hour, a two-hour gap, then 75% over three hours
+        let dist = InflectionTimeDistribution::from_aggregates(&[
+            aggregate(10_800, 21_600, 300),
+            aggregate(0, 3600, 100),
+        ])
+        .unwrap();
+
+        assert_eq!(dist.segments().len(), 2);
+        assert_eq!(dist.pdf(5000), 0.0);
+        assert_eq!(dist.probability_before(3600), 0.25);
+        assert_eq!(dist.probability_before(10_800), 0.25);
+        assert_eq!(dist.probability_between(3600, 10_800), 0.0);
+        // Three times the stake over three times the width is the same density
+        assert!((dist.pdf(0) - dist.pdf(12_000)).abs() < 1e-15);
+
+        // The median falls a third of the way into the second bucket
+        assert!((dist.median() - 14_400.0).abs() < 1e-6);
+        assert_eq!(dist.quantile(0.25), 3600.0);
+        assert_eq!(dist.quantile(1.0), 21_600.0);
+        assert_eq!(dist.expected_time(), 0.25 * 1800.0 + 0.75 * 16_200.0);
+    }
+
+    #[test]
+    fn test_overlapping_buckets_and_entropy_invariance() {
+        let whole =
+            InflectionTimeDistribution::from_aggregates(&[aggregate(0, 7200, 200)]).unwrap();
+        let split = InflectionTimeDistribution::from_aggregates(&[
+            aggregate(0, 3600, 100),
+            aggregate(3600, 7200, 100),
+        ])
+        .unwrap();
+        assert!((whole.entropy() - split.entropy()).abs() < 1e-9);
+
+        let overlapping = InflectionTimeDistribution::from_aggregates(&[
+            aggregate(0, 7200, 100),
+            aggregate(3600, 7200, 100),
+        ])
+        .unwrap();
+        assert_eq!(overlapping.segments().len(), 2);
+        assert_eq!(overlapping.probability_before(3600), 0.25);
+        assert!((overlapping.cdf(7200) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_empty_market() {
+        assert!(InflectionTimeDistribution::from_aggregates(&[]).is_none());
+        assert!(InflectionTimeDistribution::from_aggregates(&[aggregate(0, 3600, 0)]).is_none());
+    }
+}
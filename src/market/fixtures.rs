@@ -0,0 +1,63 @@
+//! Shared fixtures for market tests
+//!
+//! Each fixture is a plain value; tests override the fields they care about
+//! with struct update syntax.
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::types::{
+    belief::BeliefCondition,
+    market::{Market, MarketConfig, MarketState, MarketType},
+    position::{Position, PositionStatus, TimeBucket, TimeBucketAggregate},
+};
+
+/// Bucket size of fixture positions and aggregates (seconds)
+pub const BUCKET: u64 = 3600;
+
+/// Active sentiment market created at 0 with the default config and no stake
+pub fn market() -> Market {
+    Market {
+        address: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        market_type: MarketType::SentimentTransition,
+        belief_condition: BeliefCondition::SentimentShift {
+            from_polarity: -0.2,
+            to_polarity: 0.6,
+            persistence_window: 3600,
+        },
+        description: "Test market".to_string(),
+        state: MarketState::Active,
+        config: MarketConfig::default(),
+        created_at: 0,
+        resolved_at: None,
+        total_value_locked: 0,
+        participant_count: 0,
+        oracle_addresses: vec![],
+    }
+}
+
+/// Active position staking `amount` in the bucket starting at `start`
+pub fn position(start: i64, amount: u64) -> Position {
+    Position {
+        address: Pubkey::new_unique(),
+        market: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        time_bucket: TimeBucket::from_duration(start, BUCKET),
+        amount,
+        status: PositionStatus::Active,
+        created_at: 0,
+        settled_at: None,
+        payout: None,
+    }
+}
+
+/// Single-position aggregate of `total_staked` in the bucket starting at `start`
+pub fn aggregate(start: i64, total_staked: u64) -> TimeBucketAggregate {
+    TimeBucketAggregate {
+        time_bucket: TimeBucket::from_duration(start, BUCKET),
+        total_staked,
+        position_count: 1,
+        implied_probability: 0.0,
+        avg_position_size: total_staked,
+    }
+}
@@ -0,0 +1,388 @@
+//! Logarithmic market scoring rule (LMSR) pricing over time buckets
+//!
+//! The market maker sells shares in each time bucket; a share pays 1 lamport
+//! if the inflection lands in its bucket. With `q` shares outstanding per
+//! bucket and liquidity `b`, the cost function is `C(q) = b ln Σ exp(q_i / b)`,
+//! a trade costs the change in `C`, and the price of bucket `i` is
+//! `exp(q_i / b) / Σ exp(q_j / b)`. Prices sum to 1 and the market maker
+//! never loses more than `b ln n` over `n` buckets.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{PredaError, Result},
+    types::{
+        market::{Market, PricingMode},
+        position::TimeBucket,
+    },
+};
+
+/// LMSR market maker state for one market
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LmsrMarketMaker {
+    liquidity: f64,
+    buckets: Vec<TimeBucket>,
+    shares: Vec<u64>,
+    collected: u64,
+}
+
+/// Price quote for buying shares in a bucket
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Quote {
+    /// Bucket the shares pay out on
+    pub bucket: TimeBucket,
+
+    /// Shares bought
+    pub shares: u64,
+
+    /// Total cost (lamports), rounded up
+    pub cost: u64,
+
+    /// Bucket price before the trade
+    pub spot_price: f64,
+
+    /// Bucket price after the trade
+    pub final_price: f64,
+
+    /// Cost per share
+    pub average_price: f64,
+
+    /// Average price relative to the spot price, minus 1
+    pub slippage: f64,
+}
+
+impl LmsrMarketMaker {
+    /// Create a market maker over non-overlapping buckets with no shares sold
+    pub fn new(liquidity: u64, mut buckets: Vec<TimeBucket>) -> Result<Self> {
+        if liquidity == 0 {
+            return Err(PredaError::Configuration(
+                "LMSR liquidity must be greater than 0".to_string(),
+            ));
+        }
+        if buckets.is_empty() {
+            return Err(PredaError::InvalidTimeBucket(
+                "LMSR needs at least one bucket".to_string(),
+            ));
+        }
+
+        buckets.sort_by_key(|bucket| bucket.start);
+        if buckets.windows(2).any(|pair| pair[1].start < pair[0].end) {
+            return Err(PredaError::InvalidTimeBucket(
+                "LMSR buckets must not overlap".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            liquidity: liquidity as f64,
+            shares: vec![0; buckets.len()],
+            buckets,
+            collected: 0,
+        })
+    }
+
+    /// Create a market maker for a market priced with `PricingMode::Lmsr`
+    pub fn for_market(market: &Market, buckets: Vec<TimeBucket>) -> Result<Self> {
+        match market.config.pricing_mode {
+            PricingMode::Lmsr { liquidity } => Self::new(liquidity, buckets),
+            PricingMode::Parimutuel => Err(PredaError::Configuration(
+                "Market is not priced with LMSR".to_string(),
+            )),
+        }
+    }
+
+    /// Liquidity parameter `b` (lamports)
+    pub fn liquidity(&self) -> f64 {
+        self.liquidity
+    }
+
+    /// Buckets in time order
+    pub fn buckets(&self) -> &[TimeBucket] {
+        &self.buckets
+    }
+
+    /// Outstanding shares per bucket
+    pub fn shares(&self) -> &[u64] {
+        &self.shares
+    }
+
+    /// Lamports collected from trades, net of sales
+    pub fn collected(&self) -> u64 {
+        self.collected
+    }
+
+    /// Index of the bucket containing `timestamp`
+    pub fn bucket_index(&self, timestamp: i64) -> Option<usize> {
+        self.buckets
+            .iter()
+            .position(|bucket| bucket.contains(timestamp))
+    }
+
+    /// Current value of the cost function `C(q)`
+    pub fn cost(&self) -> f64 {
+        let max = self.scaled_shares().fold(f64::MIN, f64::max);
+        let sum: f64 = self.scaled_shares().map(|x| (x - max).exp()).sum();
+        self.liquidity * (max + sum.ln())
+    }
+
+    /// Natural log of a bucket's price, exact even when the price underflows
+    fn log_price(&self, index: usize) -> f64 {
+        let max = self.scaled_shares().fold(f64::MIN, f64::max);
+        let sum: f64 = self.scaled_shares().map(|x| (x - max).exp()).sum();
+        self.shares[index] as f64 / self.liquidity - max - sum.ln()
+    }
+
+    /// Instantaneous price of every bucket; sums to 1
+    pub fn prices(&self) -> Vec<f64> {
+        let max = self.scaled_shares().fold(f64::MIN, f64::max);
+        let weights: Vec<f64> = self.scaled_shares().map(|x| (x - max).exp()).collect();
+        let sum: f64 = weights.iter().sum();
+        weights.into_iter().map(|w| w / sum).collect()
+    }
+
+    /// Instantaneous price of a bucket
+    pub fn price(&self, index: usize) -> f64 {
+        self.prices()[index]
+    }
+
+    /// Worst-case loss of the market maker, `b ln n` (lamports)
+    pub fn max_loss(&self) -> u64 {
+        (self.liquidity * (self.buckets.len() as f64).ln()).ceil() as u64
+    }
+
+    /// Cost of buying `shares` in a bucket, rounded up to whole lamports
+    pub fn cost_to_buy(&self, index: usize, shares: u64) -> u64 {
+        let log_price = self.log_price(index);
+        let x = shares as f64 / self.liquidity;
+        // b ln(1 + p (e^x - 1)) = b (x + ln(p + (1 - p) e^-x)), summed in log
+        // space so a price that underflows to 0 is not rounded away
+        let a = log_price;
+        let b = (-log_price.exp()).ln_1p() - x;
+        let (hi, lo) = if a > b { (a, b) } else { (b, a) };
+        let delta = self.liquidity * (x + hi + (lo - hi).exp().ln_1p());
+        delta.max(0.0).ceil() as u64
+    }
+
+    /// Proceeds of selling `shares` back in a bucket, rounded down
+    pub fn proceeds_from_sale(&self, index: usize, shares: u64) -> u64 {
+        let price = self.price(index);
+        let x = shares as f64 / self.liquidity;
+        let delta = -self.liquidity * (price * (-x).exp_m1()).ln_1p();
+        delta.max(0.0).floor() as u64
+    }
+
+    /// Most shares in a bucket that `budget` lamports can buy
+    pub fn shares_for_cost(&self, index: usize, budget: u64) -> u64 {
+        let price = self.price(index);
+        let x = budget as f64 / self.liquidity;
+        let ratio = x.exp_m1() / price;
+        let scaled = if ratio.is_finite() {
+            ratio.ln_1p()
+        } else {
+            x - price.ln()
+        };
+
+        let mut shares = (self.liquidity * scaled).floor().max(0.0) as u64;
+        while shares > 0 && self.cost_to_buy(index, shares) > budget {
+            shares -= 1;
+        }
+        shares
+    }
+
+    /// Quote buying `shares` in a bucket without trading
+    pub fn quote(&self, index: usize, shares: u64) -> Result<Quote> {
+        let bucket = *self.bucket(index)?;
+        if shares == 0 {
+            return Err(PredaError::Generic(
+                "Share amount must be greater than 0".to_string(),
+            ));
+        }
+
+        let spot_price = self.price(index);
+        let cost = self.cost_to_buy(index, shares);
+        let mut after = self.clone();
+        after.shares[index] = after.shares[index].saturating_add(shares);
+        let average_price = cost as f64 / shares as f64;
+
+        Ok(Quote {
+            bucket,
+            shares,
+            cost,
+            spot_price,
+            final_price: after.price(index),
+            average_price,
+            slippage: average_price / spot_price - 1.0,
+        })
+    }
+
+    /// Buy `shares` in a bucket, returning the filled quote
+    ///
+    /// Fails without trading if the bucket's shares or the collected
+    /// lamports would overflow.
+    pub fn buy(&mut self, index: usize, shares: u64) -> Result<Quote> {
+        let quote = self.quote(index, shares)?;
+        let outstanding = self.shares[index].checked_add(shares).ok_or_else(|| {
+            PredaError::Generic(format!("Share count overflows in bucket {}", index))
+        })?;
+        let collected = self
+            .collected
+            .checked_add(quote.cost)
+            .ok_or_else(|| PredaError::Generic("Collected lamports overflow".to_string()))?;
+
+        self.shares[index] = outstanding;
+        self.collected = collected;
+        Ok(quote)
+    }
+
+    /// Sell `shares` back in a bucket, returning the proceeds
+    pub fn sell(&mut self, index: usize, shares: u64) -> Result<u64> {
+        self.bucket(index)?;
+        if shares > self.shares[index] {
+            return Err(PredaError::InsufficientFunds {
+                required: shares,
+                available: self.shares[index],
+            });
+        }
+
+        let proceeds = self.proceeds_from_sale(index, shares).min(self.collected);
+        self.shares[index] -= shares;
+        self.collected -= proceeds;
+        Ok(proceeds)
+    }
+
+    /// Lamports owed to all shares if the inflection happens at `timestamp`
+    pub fn liability(&self, timestamp: i64) -> u64 {
+        self.bucket_index(timestamp)
+            .map_or(0, |index| self.shares[index])
+    }
+
+    fn bucket(&self, index: usize) -> Result<&TimeBucket> {
+        self.buckets
+            .get(index)
+            .ok_or_else(|| PredaError::InvalidTimeBucket(format!("No bucket at index {}", index)))
+    }
+
+    fn scaled_shares(&self) -> impl Iterator<Item = f64> + '_ {
+        self.shares
+            .iter()
+            .map(|&shares| shares as f64 / self.liquidity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn maker(liquidity: u64, count: i64) -> LmsrMarketMaker {
+        let buckets = (0..count)
+            .map(|i| TimeBucket::from_duration(i * 3600, 3600))
+            .collect();
+        LmsrMarketMaker::new(liquidity, buckets).unwrap()
+    }
+
+    #[test]
+    fn test_initial_prices_are_uniform() {
+        let maker = maker(1_000_000_000, 4);
+        assert!(maker.prices().iter().all(|&p| (p - 0.25).abs() < 1e-12));
+        assert_eq!(maker.bucket_index(4000), Some(1));
+        assert_eq!(maker.bucket_index(20_000), None);
+    }
+
+    #[test]
+    fn test_buying_moves_price_with_slippage() {
+        let mut maker = maker(1_000_000_000, 4);
+        let small = maker.quote(0, 1_000_000).unwrap();
+        let large = maker.quote(0, 1_000_000_000).unwrap();
+
+        assert!(small.slippage < large.slippage);
+        assert!(large.final_price > large.spot_price);
+        assert!(large.average_price > large.spot_price && large.average_price < large.final_price);
+
+        let fill = maker.buy(0, 1_000_000_000).unwrap();
+        assert_eq!(fill, large);
+        assert!(maker.price(0) > 0.25 && maker.price(1) < 0.25);
+        assert_eq!(maker.liability(100), 1_000_000_000);
+    }
+
+    #[test]
+    fn test_budget_inverts_cost() {
+        let mut maker = maker(500_000_000, 6);
+        maker.buy(2, 300_000_000).unwrap();
+
+        let shares = maker.shares_for_cost(2, 100_000_000);
+        assert!(maker.cost_to_buy(2, shares) <= 100_000_000);
+        assert!(maker.cost_to_buy(2, shares + 1) > 100_000_000);
+    }
+
+    #[test]
+    fn test_round_trip_never_profits_trader() {
+        let mut maker = maker(1_000_000_000, 3);
+        let cost = maker.buy(1, 400_000_000).unwrap().cost;
+        let proceeds = maker.sell(1, 400_000_000).unwrap();
+
+        assert!(proceeds <= cost && cost - proceeds <= 1);
+        assert!(maker.sell(1, 1).is_err());
+    }
+
+    #[test]
+    fn test_buy_overflow_fails_without_trading() {
+        let mut maker = maker(1_000_000_000, 2);
+        maker.shares[0] = u64::MAX - 1;
+        let before = maker.clone();
+
+        assert!(maker.buy(0, 2).is_err());
+        assert_eq!(maker, before);
+
+        let mut maker = self::maker(1_000_000_000, 2);
+        maker.collected = u64::MAX;
+        assert!(maker.buy(1, 1_000_000).is_err());
+        assert_eq!(maker.shares()[1], 0);
+    }
+
+    #[test]
+    fn test_cost_with_underflowing_price() {
+        let mut maker = maker(1_000_000, 2);
+        maker.buy(1, 745_133_220).unwrap();
+        assert_eq!(maker.price(0), 0.0);
+
+        // Catching up to the other bucket costs about b ln 2
+        let cost = maker.cost_to_buy(0, 745_133_220);
+        assert!((cost as f64 - 1_000_000.0 * 2f64.ln()).abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_invalid_setup() {
+        assert!(LmsrMarketMaker::new(0, vec![TimeBucket::from_duration(0, 3600)]).is_err());
+        assert!(LmsrMarketMaker::new(1, vec![]).is_err());
+        assert!(LmsrMarketMaker::new(
+            1,
+            vec![
+                TimeBucket::from_duration(0, 3600),
+                TimeBucket::from_duration(1800, 3600)
+            ]
+        )
+        .is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prices_sum_to_one_and_loss_is_bounded(
+            liquidity in 1_000_000u64..10_000_000_000,
+            count in 2i64..12,
+            trades in prop::collection::vec((0usize..12, 1u64..5_000_000_000), 0..20),
+        ) {
+            let mut maker = maker(liquidity, count);
+            for (index, shares) in trades {
+                maker.buy(index % count as usize, shares).unwrap();
+            }
+
+            let sum: f64 = maker.prices().iter().sum();
+            prop_assert!((sum - 1.0).abs() < 1e-9);
+
+            for bucket in maker.buckets().to_vec() {
+                let owed = maker.liability(bucket.start);
+                prop_assert!(owed <= maker.collected() + maker.max_loss());
+            }
+        }
+    }
+}
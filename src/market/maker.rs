@@ -0,0 +1,550 @@
+//! Market making: seeding and rebalancing stake across a market's buckets
+//!
+//! New markets start empty, so their implied probabilities say nothing. A
+//! `MarketMaker` stakes across the bucket grid according to a `Strategy`,
+//! typically one that pulls the implied distribution toward a prior over the
+//! inflection time, and keeps its inventory within `InventoryLimits`. Each
+//! `step` reads the latest aggregates and BSI and places whatever the
+//! strategy asks for, so calling it on every update rebalances as other
+//! participants move the market.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use super::{
+    distribution::InflectionTimeDistribution, optimizer, settlement::SettlementCalculator,
+    MarketManager,
+};
+use crate::error::Result;
+use crate::types::{
+    belief::BeliefStateIndex,
+    market::Market,
+    position::{Position, TimeBucket, TimeBucketAggregate},
+};
+
+/// Market data a strategy quotes against
+#[derive(Debug, Clone, Copy)]
+pub struct MakerState<'a> {
+    /// Market being made
+    pub market: &'a Market,
+
+    /// Stake of every participant per bucket, the maker's included
+    pub aggregates: &'a [TimeBucketAggregate],
+
+    /// Maker's active stake per bucket
+    pub inventory: &'a [(TimeBucket, u64)],
+
+    /// Latest BSI of the market
+    pub bsi: &'a BeliefStateIndex,
+
+    /// Most the maker may add this step (lamports)
+    pub budget: u64,
+
+    /// Current timestamp
+    pub now: i64,
+}
+
+/// Decides what stake a market maker adds each step
+pub trait Strategy: Send {
+    /// Stakes to add, per bucket
+    ///
+    /// The maker enforces its limits on the result, so a strategy may ask
+    /// for more than `state.budget`.
+    fn quote(&mut self, state: &MakerState<'_>) -> Vec<(TimeBucket, u64)>;
+}
+
+/// Caps on a market maker's stake (lamports)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InventoryLimits {
+    /// Maximum active stake in the market
+    pub max_inventory: u64,
+
+    /// Maximum active stake in one bucket
+    pub max_bucket_inventory: u64,
+
+    /// Maximum stake added per step
+    pub max_step: u64,
+}
+
+/// Runs a strategy against a market within inventory limits
+pub struct MarketMaker<S: Strategy> {
+    strategy: S,
+    limits: InventoryLimits,
+}
+
+/// Strategy that moves the implied distribution toward a fixed prior
+///
+/// Each step spends the budget on the buckets whose stake falls furthest
+/// short of the prior's share of the pool after the step. On an empty market
+/// this seeds stake in proportion to the prior.
+#[derive(Debug, Clone)]
+pub struct PriorStrategy {
+    prior: InflectionTimeDistribution,
+}
+
+/// Prior over the inflection time from the BSI's trend toward a target
+///
+/// The BSI slope is fitted to the history by least squares, falling back to
+/// the latest velocity spread over the oracle update interval. If the BSI is
+/// heading toward `target`, the prior is a normal curve centred on the
+/// projected crossing time with a standard deviation of `spread` times the
+/// time to get there (at least one bucket); otherwise it is uniform. A
+/// `floor` share is always spread uniformly so every bucket gets some stake.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BsiPrior {
+    /// BSI value at which the market's condition is met
+    pub target: f64,
+
+    /// Standard deviation relative to the projected time to the target
+    pub spread: f64,
+
+    /// Share of probability spread uniformly across buckets
+    pub floor: f64,
+
+    /// Buckets covered in markets without an expiration
+    pub horizon_buckets: usize,
+}
+
+/// `PriorStrategy` that re-estimates its prior from the BSI every step
+#[derive(Debug, Clone)]
+pub struct BsiStrategy {
+    prior: BsiPrior,
+    history: Vec<BeliefStateIndex>,
+}
+
+impl<S: Strategy> MarketMaker<S> {
+    /// Create a market maker
+    pub fn new(strategy: S, limits: InventoryLimits) -> Self {
+        Self { strategy, limits }
+    }
+
+    /// Strategy in use
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Inventory limits in force
+    pub fn limits(&self) -> &InventoryLimits {
+        &self.limits
+    }
+
+    /// Stakes to add this step, within the inventory limits
+    ///
+    /// Quotes for buckets off the market's grid or already over are dropped,
+    /// each is cut to the remaining bucket, market and step capacity and to
+    /// `max_position_size`, and quotes left below `min_position_size` are
+    /// dropped.
+    pub fn plan(
+        &mut self,
+        market: &Market,
+        aggregates: &[TimeBucketAggregate],
+        inventory: &[(TimeBucket, u64)],
+        bsi: &BeliefStateIndex,
+        now: i64,
+    ) -> Vec<(TimeBucket, u64)> {
+        let config = &market.config;
+        let held: u64 = inventory.iter().map(|&(_, amount)| amount).sum();
+        let mut remaining = self
+            .limits
+            .max_step
+            .min(self.limits.max_inventory.saturating_sub(held));
+        let mut buckets: HashMap<i64, u64> = HashMap::new();
+        for &(bucket, amount) in inventory {
+            *buckets.entry(bucket.start).or_default() += amount;
+        }
+
+        let state = MakerState {
+            market,
+            aggregates,
+            inventory,
+            bsi,
+            budget: remaining,
+            now,
+        };
+        let mut stakes = Vec::new();
+        for (bucket, amount) in self.strategy.quote(&state) {
+            if bucket.end <= now || market.validate_bucket(&bucket).is_err() {
+                continue;
+            }
+
+            let in_bucket = buckets.entry(bucket.start).or_default();
+            let amount = amount
+                .min(remaining)
+                .min(self.limits.max_bucket_inventory.saturating_sub(*in_bucket))
+                .min(config.max_position_size);
+            if amount == 0 || amount < config.min_position_size {
+                continue;
+            }
+
+            *in_bucket += amount;
+            remaining -= amount;
+            stakes.push((bucket, amount));
+        }
+
+        stakes
+    }
+
+    /// Run one step against a market, placing the planned stakes
+    pub async fn step(
+        &mut self,
+        manager: &MarketManager,
+        maker: &Keypair,
+        market_address: &Pubkey,
+    ) -> Result<Vec<Position>> {
+        let market = manager.get_market(market_address).await?;
+        let aggregates = manager.get_time_bucket_aggregates(market_address).await?;
+        let bsi = manager.get_belief_state_index(market_address).await?;
+        let positions = manager
+            .get_user_positions(market_address, &maker.pubkey())
+            .await?;
+
+        let inventory = inventory(&positions);
+        let now = chrono::Utc::now().timestamp();
+        let stakes = self.plan(&market, &aggregates, &inventory, &bsi, now);
+        if stakes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        manager
+            .place_positions(maker, market_address, &stakes)
+            .await
+    }
+}
+
+/// Active stake per bucket of a set of positions, in time order
+pub fn inventory(positions: &[Position]) -> Vec<(TimeBucket, u64)> {
+    let mut stakes: Vec<(TimeBucket, u64)> = Vec::new();
+    for position in positions.iter().filter(|pos| pos.is_active()) {
+        match stakes
+            .iter_mut()
+            .find(|(bucket, _)| *bucket == position.time_bucket)
+        {
+            Some((_, amount)) => *amount += position.amount,
+            None => stakes.push((position.time_bucket, position.amount)),
+        }
+    }
+    stakes.sort_by_key(|(bucket, _)| bucket.start);
+    stakes
+}
+
+impl PriorStrategy {
+    /// Create a strategy targeting `prior`
+    pub fn new(prior: InflectionTimeDistribution) -> Self {
+        Self { prior }
+    }
+
+    /// Prior being targeted
+    pub fn prior(&self) -> &InflectionTimeDistribution {
+        &self.prior
+    }
+
+    /// Replace the prior, e.g. after new information
+    pub fn set_prior(&mut self, prior: InflectionTimeDistribution) {
+        self.prior = prior;
+    }
+}
+
+impl Strategy for PriorStrategy {
+    fn quote(&mut self, state: &MakerState<'_>) -> Vec<(TimeBucket, u64)> {
+        let buckets: Vec<TimeBucket> = optimizer::candidate_buckets(state.market, &self.prior)
+            .into_iter()
+            .filter(|bucket| bucket.end > state.now)
+            .collect();
+        let probabilities: Vec<f64> = buckets
+            .iter()
+            .map(|bucket| self.prior.probability_between(bucket.start, bucket.end))
+            .collect();
+        let open: f64 = probabilities.iter().sum();
+        if buckets.is_empty() || open <= 0.0 || state.budget == 0 {
+            return vec![];
+        }
+
+        let staked = |bucket: &TimeBucket| -> u64 {
+            state
+                .aggregates
+                .iter()
+                .filter(|agg| agg.time_bucket == *bucket)
+                .map(|agg| agg.total_staked)
+                .sum()
+        };
+        let pool: u64 = state.aggregates.iter().map(|agg| agg.total_staked).sum();
+        let target_pool = pool.saturating_add(state.budget) as f64;
+
+        // Shortfall of each bucket against its share of the pool after the step
+        let deficits: Vec<u128> = buckets
+            .iter()
+            .zip(&probabilities)
+            .map(|(bucket, &p)| {
+                let target = p / open * target_pool;
+                (target - staked(bucket) as f64).max(0.0).round() as u128
+            })
+            .collect();
+        let spend = deficits.iter().sum::<u128>().min(state.budget as u128);
+
+        buckets
+            .into_iter()
+            .zip(SettlementCalculator::allocate(spend, &deficits))
+            .filter(|&(_, amount)| amount > 0)
+            .map(|(bucket, amount)| (bucket, amount as u64))
+            .collect()
+    }
+}
+
+impl BsiPrior {
+    /// Prior for reaching `target`, with spread 0.5, floor 0.05 and a 48-bucket horizon
+    pub fn new(target: f64) -> Self {
+        Self {
+            target,
+            spread: 0.5,
+            floor: 0.05,
+            horizon_buckets: 48,
+        }
+    }
+
+    /// Estimate the prior from BSI history, oldest first
+    ///
+    /// Returns `None` without history or when the market has no open buckets.
+    pub fn estimate(
+        &self,
+        market: &Market,
+        history: &[BeliefStateIndex],
+        now: i64,
+    ) -> Option<InflectionTimeDistribution> {
+        let latest = history.last()?;
+        let buckets = self.open_buckets(market, now);
+        if buckets.is_empty() {
+            return None;
+        }
+
+        let slope = Self::slope(history).unwrap_or_else(|| {
+            latest.velocity / market.config.oracle_update_frequency.max(1) as f64
+        });
+        let distance = self.target - latest.value;
+        let bucket_size = market.config.time_bucket_size.max(1) as f64;
+
+        // Projected crossing time and its uncertainty, if heading to the target
+        let projection = if distance == 0.0 {
+            Some((now as f64, bucket_size))
+        } else if slope != 0.0 && distance.signum() == slope.signum() {
+            let eta = distance / slope;
+            Some((now as f64 + eta, (self.spread * eta).max(bucket_size)))
+        } else {
+            None
+        };
+
+        let mass: Vec<f64> = match projection {
+            Some((mean, sigma)) => buckets
+                .iter()
+                .map(|bucket| {
+                    let z = (bucket.midpoint() as f64 - mean) / sigma;
+                    (-0.5 * z * z).exp() * bucket.duration() as f64
+                })
+                .collect(),
+            None => buckets.iter().map(|b| b.duration() as f64).collect(),
+        };
+        let total: f64 = mass.iter().sum();
+        let floor = self.floor.clamp(0.0, 1.0);
+        let uniform = 1.0 / buckets.len() as f64;
+
+        let weights: Vec<(TimeBucket, f64)> = buckets
+            .into_iter()
+            .zip(mass)
+            .map(|(bucket, m)| {
+                let shaped = if total > 0.0 { m / total } else { uniform };
+                (bucket, floor * uniform + (1.0 - floor) * shaped)
+            })
+            .collect();
+        InflectionTimeDistribution::from_weights(&weights)
+    }
+
+    /// Grid buckets not yet over, up to the horizon for open-ended markets
+    fn open_buckets(&self, market: &Market, now: i64) -> Vec<TimeBucket> {
+        let mut buckets = Vec::new();
+        let mut next = market.bucket_for(now.max(market.created_at));
+        while let Some(bucket) = next {
+            if buckets.len() >= self.horizon_buckets && market.config.expiration_time == 0 {
+                break;
+            }
+            buckets.push(bucket);
+            next = market.bucket_for(bucket.end);
+        }
+        buckets
+    }
+
+    /// Least-squares BSI slope per second over the history
+    fn slope(history: &[BeliefStateIndex]) -> Option<f64> {
+        let n = history.len() as f64;
+        let mean_t = history.iter().map(|b| b.last_updated as f64).sum::<f64>() / n;
+        let mean_v = history.iter().map(|b| b.value).sum::<f64>() / n;
+        let (cov, var) = history.iter().fold((0.0, 0.0), |(cov, var), b| {
+            let dt = b.last_updated as f64 - mean_t;
+            (cov + dt * (b.value - mean_v), var + dt * dt)
+        });
+
+        (var > 0.0).then(|| cov / var)
+    }
+}
+
+impl BsiStrategy {
+    /// Create a strategy seeded with BSI history, e.g. from
+    /// `BsiCalculator::get_history`
+    pub fn new(prior: BsiPrior, history: Vec<BeliefStateIndex>) -> Self {
+        Self { prior, history }
+    }
+
+    /// BSI history seen so far, oldest first
+    pub fn history(&self) -> &[BeliefStateIndex] {
+        &self.history
+    }
+}
+
+impl Strategy for BsiStrategy {
+    fn quote(&mut self, state: &MakerState<'_>) -> Vec<(TimeBucket, u64)> {
+        let is_new = self
+            .history
+            .last()
+            .is_none_or(|last| last.last_updated < state.bsi.last_updated);
+        if is_new {
+            self.history.push(state.bsi.clone());
+        }
+
+        match self.prior.estimate(state.market, &self.history, state.now) {
+            Some(prior) => PriorStrategy::new(prior).quote(state),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        belief::BeliefCondition,
+        market::{MarketConfig, MarketType},
+    };
+    use solana_client::rpc_client::RpcClient;
+    use std::sync::Arc;
+
+    const HOUR: i64 = 3600;
+
+    fn limits() -> InventoryLimits {
+        InventoryLimits {
+            max_inventory: 100_000_000,
+            max_bucket_inventory: 20_000_000,
+            max_step: 50_000_000,
+        }
+    }
+
+    async fn market(manager: &MarketManager) -> Market {
+        let now = chrono::Utc::now().timestamp();
+        manager
+            .create_market(
+                &Keypair::new(),
+                MarketType::SentimentTransition,
+                BeliefCondition::SentimentShift {
+                    from_polarity: -0.2,
+                    to_polarity: 0.6,
+                    persistence_window: 3600,
+                },
+                "Test market".to_string(),
+                MarketConfig {
+                    expiration_time: now + 12 * HOUR,
+                    ..MarketConfig::default()
+                },
+            )
+            .await
+            .unwrap()
+    }
+
+    fn bsi(value: f64, last_updated: i64) -> BeliefStateIndex {
+        BeliefStateIndex {
+            value,
+            last_updated,
+            ..BeliefStateIndex::new("BTC".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_seeds_and_rebalances_toward_prior() {
+        let manager = MarketManager::new(
+            Arc::new(RpcClient::new("http://localhost:8899".to_string())),
+            Pubkey::new_unique(),
+        );
+        let market = market(&manager).await;
+        let grid = market.bucket_grid();
+        let prior = InflectionTimeDistribution::from_weights(&[
+            (grid[2], 1.0),
+            (grid[3], 2.0),
+            (grid[4], 1.0),
+        ])
+        .unwrap();
+        let maker = Keypair::new();
+        let mut bot = MarketMaker::new(PriorStrategy::new(prior), limits());
+
+        // Seeding follows the prior
+        let seeded = bot.step(&manager, &maker, &market.address).await.unwrap();
+        let amounts: Vec<u64> = seeded.iter().map(|pos| pos.amount).collect();
+        assert_eq!(amounts, vec![12_500_000, 20_000_000, 12_500_000]);
+
+        // A trader crowds bucket 2; the next step tops up the other buckets
+        manager
+            .place_position(&Keypair::new(), &market.address, grid[2].start, 30_000_000)
+            .await
+            .unwrap();
+        let rebalanced = bot.step(&manager, &maker, &market.address).await.unwrap();
+        assert!(rebalanced.iter().all(|pos| pos.time_bucket != grid[2]));
+        assert!(rebalanced.iter().any(|pos| pos.time_bucket == grid[4]));
+
+        // Limits hold however often it runs
+        for _ in 0..5 {
+            bot.step(&manager, &maker, &market.address).await.unwrap();
+        }
+        let held = inventory(
+            &manager
+                .get_user_positions(&market.address, &maker.pubkey())
+                .await
+                .unwrap(),
+        );
+        assert!(held.iter().map(|(_, a)| a).sum::<u64>() <= limits().max_inventory);
+        assert!(held
+            .iter()
+            .all(|&(_, a)| a <= limits().max_bucket_inventory));
+    }
+
+    #[tokio::test]
+    async fn test_bsi_prior_follows_trend() {
+        let manager = MarketManager::new(
+            Arc::new(RpcClient::new("http://localhost:8899".to_string())),
+            Pubkey::new_unique(),
+        );
+        let market = market(&manager).await;
+        let now = market.created_at;
+        let prior = BsiPrior::new(0.6);
+
+        // Rising 0.1 per hour from 0.0: the target is about six hours out
+        let history: Vec<BeliefStateIndex> = (0..4)
+            .map(|i| bsi(-0.3 + 0.1 * i as f64, now - (3 - i) * HOUR))
+            .collect();
+        let rising = prior.estimate(&market, &history, now).unwrap();
+        assert!((rising.median() - (now + 6 * HOUR) as f64).abs() < HOUR as f64);
+
+        // Falling away from the target gives no timing information
+        let falling: Vec<BeliefStateIndex> = history
+            .iter()
+            .map(|b| bsi(-b.value, b.last_updated))
+            .collect();
+        let flat = prior.estimate(&market, &falling, now).unwrap();
+        let grid = market.bucket_grid();
+        let first = flat.probability_between(grid[0].start, grid[0].end);
+        let last = flat.probability_between(grid[11].start, grid[11].end);
+        assert!((first - last).abs() < 1e-9);
+
+        let mut bot = MarketMaker::new(BsiStrategy::new(prior, history), limits());
+        let stakes = bot.plan(&market, &[], &[], &bsi(0.0, now + 1), now);
+        let peak = stakes.iter().max_by_key(|(_, amount)| *amount).unwrap().0;
+        assert!(peak.contains(now + 6 * HOUR) || peak.contains(now + 5 * HOUR));
+        assert_eq!(bot.strategy().history().len(), 5);
+    }
+}
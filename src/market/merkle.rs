@@ -0,0 +1,235 @@
+//! Merkle-root settlement with per-position claim proofs
+//!
+//! Payouts are computed off-chain and committed as a Merkle tree of
+//! `(position, owner, payout)` leaves. Only the root is published on-chain;
+//! each claim carries an inclusion proof. Leaves and inner nodes are hashed
+//! with distinct prefixes so a leaf can never be passed off as a node.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+
+use super::settlement::SettlementReport;
+
+/// 32-byte SHA-256 hash
+pub type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Payout committed for a single position
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PayoutLeaf {
+    /// Position address
+    pub position: Pubkey,
+
+    /// Position owner
+    pub owner: Pubkey,
+
+    /// Payout amount (lamports)
+    pub payout: u64,
+}
+
+/// Sibling hash on the path from a leaf to the root
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProofNode {
+    /// Sibling hash
+    pub hash: Hash,
+
+    /// Whether the sibling is the left child
+    pub is_left: bool,
+}
+
+/// Inclusion proof for a payout leaf
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PayoutProof {
+    /// Leaf being proven
+    pub leaf: PayoutLeaf,
+
+    /// Siblings from the leaf level upwards
+    pub path: Vec<ProofNode>,
+}
+
+/// Merkle tree over the payouts of a settled market
+#[derive(Debug, Clone)]
+pub struct SettlementTree {
+    leaves: Vec<PayoutLeaf>,
+    levels: Vec<Vec<Hash>>,
+}
+
+impl PayoutLeaf {
+    /// Hash of the leaf
+    pub fn hash(&self) -> Hash {
+        let mut hasher = Sha256::new();
+        hasher.update([LEAF_PREFIX]);
+        hasher.update(self.position.as_ref());
+        hasher.update(self.owner.as_ref());
+        hasher.update(self.payout.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+impl PayoutProof {
+    /// Root implied by this proof
+    pub fn compute_root(&self) -> Hash {
+        self.path.iter().fold(self.leaf.hash(), |acc, node| {
+            if node.is_left {
+                hash_node(&node.hash, &acc)
+            } else {
+                hash_node(&acc, &node.hash)
+            }
+        })
+    }
+
+    /// Check that the proof's leaf is included under `root`
+    pub fn verify(&self, root: &Hash) -> bool {
+        self.compute_root() == *root
+    }
+}
+
+impl SettlementTree {
+    /// Build a tree over payout leaves
+    ///
+    /// An unpaired node at the end of a level is carried up unchanged.
+    pub fn new(leaves: Vec<PayoutLeaf>) -> Self {
+        let mut levels = vec![leaves.iter().map(PayoutLeaf::hash).collect::<Vec<_>>()];
+
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        Self { leaves, levels }
+    }
+
+    /// Build a tree over the settled positions of a report
+    pub fn from_report(report: &SettlementReport) -> Self {
+        Self::new(
+            report
+                .positions
+                .iter()
+                .map(|position| PayoutLeaf {
+                    position: position.address,
+                    owner: position.owner,
+                    payout: position.payout.unwrap_or(0),
+                })
+                .collect(),
+        )
+    }
+
+    /// Merkle root, all zeros for an empty tree
+    pub fn root(&self) -> Hash {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or([0; 32])
+    }
+
+    /// Committed leaves
+    pub fn leaves(&self) -> &[PayoutLeaf] {
+        &self.leaves
+    }
+
+    /// Inclusion proof for a position
+    pub fn proof(&self, position: &Pubkey) -> Option<PayoutProof> {
+        let leaf_index = self
+            .leaves
+            .iter()
+            .position(|leaf| leaf.position == *position)?;
+
+        let mut index = leaf_index;
+        let mut path = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = index ^ 1;
+            if let Some(hash) = level.get(sibling) {
+                path.push(ProofNode {
+                    hash: *hash,
+                    is_left: sibling < index,
+                });
+            }
+            index /= 2;
+        }
+
+        Some(PayoutProof {
+            leaf: self.leaves[leaf_index],
+            path,
+        })
+    }
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<PayoutLeaf> {
+        (0..count)
+            .map(|i| PayoutLeaf {
+                position: Pubkey::new_unique(),
+                owner: Pubkey::new_unique(),
+                payout: i as u64 * 1_000,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_all_proofs_verify() {
+        for count in [1, 2, 3, 7, 8, 33] {
+            let tree = SettlementTree::new(leaves(count));
+            let root = tree.root();
+
+            for leaf in tree.leaves() {
+                let proof = tree.proof(&leaf.position).unwrap();
+                assert_eq!(proof.leaf, *leaf);
+                assert!(proof.verify(&root), "{} leaves", count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_proofs_fail() {
+        let tree = SettlementTree::new(leaves(5));
+        let root = tree.root();
+        let proof = tree.proof(&tree.leaves()[3].position).unwrap();
+
+        let mut inflated = proof.clone();
+        inflated.leaf.payout += 1;
+        assert!(!inflated.verify(&root));
+
+        let mut redirected = proof.clone();
+        redirected.leaf.owner = Pubkey::new_unique();
+        assert!(!redirected.verify(&root));
+
+        let mut reordered = proof.clone();
+        reordered.path[0].is_left = !reordered.path[0].is_left;
+        assert!(!reordered.verify(&root));
+
+        let mut truncated = proof;
+        truncated.path.pop();
+        assert!(!truncated.verify(&root));
+    }
+
+    #[test]
+    fn test_empty_and_unknown() {
+        let tree = SettlementTree::new(vec![]);
+        assert_eq!(tree.root(), [0; 32]);
+        assert!(tree.proof(&Pubkey::new_unique()).is_none());
+    }
+}
@@ -1,19 +1,38 @@
 //! Market operations module
 
+pub mod distribution;
+#[cfg(test)]
+mod fixtures;
 pub mod lifecycle;
+pub mod lmsr;
+pub mod maker;
+pub mod merkle;
+pub mod optimizer;
+pub mod orders;
+pub mod range;
+pub mod risk;
+pub mod scoring;
+pub mod secondary;
+pub mod simulation;
 pub mod settlement;
+pub mod valuation;
 
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use tokio::sync::RwLock;
 
 use crate::{
     bsi::BeliefStateIndex,
     error::{PredaError, Result},
     types::{
-        belief::BeliefCondition,
-        market::{Market, MarketConfig, MarketState, MarketType},
-        position::{Position, TimeBucket, TimeBucketAggregate},
+        belief::{BeliefCondition, BeliefInflection},
+        market::{Market, MarketConfig, MarketState, MarketType, PricingMode},
+        position::{
+            Listing, Position, PositionGroup, PositionStatus, RangeWeighting, TimeBucket,
+            TimeBucketAggregate,
+        },
     },
 };
 
@@ -21,6 +40,14 @@ use crate::{
 pub struct MarketManager {
     rpc_client: Arc<RpcClient>,
     program_id: Pubkey,
+    markets: RwLock<HashMap<Pubkey, Market>>,
+    collected_fees: RwLock<HashMap<Pubkey, u64>>,
+    makers: RwLock<HashMap<Pubkey, lmsr::LmsrMarketMaker>>,
+    shares: RwLock<HashMap<Pubkey, u64>>,
+    positions: RwLock<HashMap<Pubkey, Position>>,
+    listings: RwLock<HashMap<Pubkey, Listing>>,
+    settlement_roots: RwLock<HashMap<Pubkey, merkle::Hash>>,
+    claimed: RwLock<HashMap<Pubkey, HashSet<Pubkey>>>,
 }
 
 impl MarketManager {
@@ -29,10 +56,21 @@ impl MarketManager {
         Self {
             rpc_client,
             program_id,
+            markets: RwLock::new(HashMap::new()),
+            collected_fees: RwLock::new(HashMap::new()),
+            makers: RwLock::new(HashMap::new()),
+            shares: RwLock::new(HashMap::new()),
+            positions: RwLock::new(HashMap::new()),
+            listings: RwLock::new(HashMap::new()),
+            settlement_roots: RwLock::new(HashMap::new()),
+            claimed: RwLock::new(HashMap::new()),
         }
     }
 
-    /// Create a new market
+    /// Create a new market, charging the creator `FeeSchedule::creation_fee`
+    ///
+    /// `PricingMode::Lmsr` markets get a market maker over their bucket grid,
+    /// so they need an expiration.
     pub async fn create_market(
         &self,
         creator: &Keypair,
@@ -46,7 +84,7 @@ impl MarketManager {
         
         let market_address = Pubkey::new_unique();
         
-        Ok(Market {
+        let market = Market {
             address: market_address,
             creator: creator.pubkey(),
             market_type,
@@ -59,13 +97,81 @@ impl MarketManager {
             total_value_locked: 0,
             participant_count: 0,
             oracle_addresses: vec![],
-        })
+        };
+        let maker = match market.config.pricing_mode {
+            PricingMode::Lmsr { .. } => Some(lmsr::LmsrMarketMaker::for_market(
+                &market,
+                market.bucket_grid(),
+            )?),
+            PricingMode::Parimutuel => None,
+        };
+
+        // Lock order: markets, collected fees, then market makers
+        let mut markets = self.markets.write().await;
+        self.collected_fees
+            .write()
+            .await
+            .insert(market_address, market.config.fees.creation_fee);
+        if let Some(maker) = maker {
+            self.makers.write().await.insert(market_address, maker);
+        }
+        markets.insert(market_address, market.clone());
+        Ok(market)
     }
 
     /// Get market by address
     pub async fn get_market(&self, market_address: &Pubkey) -> Result<Market> {
-        // In production, fetch from blockchain
-        Err(PredaError::MarketNotFound(market_address.to_string()))
+        // In production, fetch from blockchain; markets created here are cached
+        self.markets
+            .read()
+            .await
+            .get(market_address)
+            .cloned()
+            .ok_or_else(|| PredaError::MarketNotFound(market_address.to_string()))
+    }
+
+    /// Fees collected by a market so far: its creation fee plus placement and
+    /// withdrawal fees (lamports)
+    ///
+    /// Settlement fees are taken from the pool when the market settles; see
+    /// [`settlement::SettlementReport::fee`].
+    pub async fn get_collected_fees(&self, market_address: &Pubkey) -> Result<u64> {
+        self.collected_fees
+            .read()
+            .await
+            .get(market_address)
+            .copied()
+            .ok_or_else(|| PredaError::MarketNotFound(market_address.to_string()))
+    }
+
+    /// LMSR market maker of a `PricingMode::Lmsr` market, for quotes
+    pub async fn get_market_maker(
+        &self,
+        market_address: &Pubkey,
+    ) -> Result<lmsr::LmsrMarketMaker> {
+        self.makers
+            .read()
+            .await
+            .get(market_address)
+            .cloned()
+            .ok_or_else(|| {
+                PredaError::Configuration(format!(
+                    "Market {} is not priced with LMSR",
+                    market_address
+                ))
+            })
+    }
+
+    /// Shares held by a position in an LMSR market; zero in pooled markets
+    pub async fn get_position_shares(&self, position_address: &Pubkey) -> Result<u64> {
+        // In production, read the position account
+        Ok(self
+            .shares
+            .read()
+            .await
+            .get(position_address)
+            .copied()
+            .unwrap_or(0))
     }
 
     /// Get all active markets
@@ -81,6 +187,10 @@ impl MarketManager {
     }
 
     /// Place a position in a market
+    ///
+    /// The placement fee is charged on `amount`; the net stake enters the
+    /// pool (`Market::total_value_locked`). In `PricingMode::Lmsr` markets the
+    /// net stake buys bucket shares from the market maker; see `buy_shares`.
     pub async fn place_position(
         &self,
         user: &Keypair,
@@ -90,20 +200,160 @@ impl MarketManager {
     ) -> Result<Position> {
         // In production, create on-chain transaction
         
-        let position_address = Pubkey::new_unique();
-        let time_bucket = TimeBucket::from_duration(time_bucket_start, 3600);
-        
-        Ok(Position {
-            address: position_address,
+        let market = self.get_market(market_address).await?;
+        let time_bucket =
+            TimeBucket::from_duration(time_bucket_start, market.config.time_bucket_size);
+        market.validate_bucket(&time_bucket)?;
+
+        let position = Self::new_position(user, market_address, time_bucket, amount);
+        self.buy_shares(&market, std::slice::from_ref(&position)).await?;
+        self.cache_positions(std::slice::from_ref(&position)).await;
+        Ok(position)
+    }
+
+    /// Place several positions in one batch
+    ///
+    /// Every bucket is checked against the market's grid before any position
+    /// is placed, so an invalid entry places nothing. Zero stakes are skipped.
+    pub async fn place_positions(
+        &self,
+        user: &Keypair,
+        market_address: &Pubkey,
+        stakes: &[(TimeBucket, u64)],
+    ) -> Result<Vec<Position>> {
+        let market = self.get_market(market_address).await?;
+        for (time_bucket, _) in stakes {
+            market.validate_bucket(time_bucket)?;
+        }
+
+        // In production, create one transaction placing every position
+        let positions: Vec<Position> = stakes
+            .iter()
+            .filter(|&&(_, amount)| amount > 0)
+            .map(|&(time_bucket, amount)| {
+                Self::new_position(user, market_address, time_bucket, amount)
+            })
+            .collect();
+        self.buy_shares(&market, &positions).await?;
+        self.cache_positions(&positions).await;
+        Ok(positions)
+    }
+
+    /// Place a group of positions splitting `amount` across `[start, end)`
+    ///
+    /// The range must lie on the market's bucket grid; see
+    /// [`range::split_range`] for how the stake is split.
+    pub async fn place_range_position(
+        &self,
+        user: &Keypair,
+        market_address: &Pubkey,
+        start: i64,
+        end: i64,
+        amount: u64,
+        weighting: RangeWeighting,
+    ) -> Result<PositionGroup> {
+        let market = self.get_market(market_address).await?;
+        let split = range::split_range(&market, start, end, amount, weighting)?;
+
+        // In production, create one transaction placing every position
+        let group = PositionGroup {
+            range: TimeBucket { start, end },
+            weighting,
+            positions: split
+                .into_iter()
+                .map(|(time_bucket, amount)| {
+                    Self::new_position(user, market_address, time_bucket, amount)
+                })
+                .collect(),
+        };
+        self.buy_shares(&market, &group.positions).await?;
+        self.cache_positions(&group.positions).await;
+        Ok(group)
+    }
+
+    fn new_position(
+        user: &Keypair,
+        market_address: &Pubkey,
+        time_bucket: TimeBucket,
+        amount: u64,
+    ) -> Position {
+        Position {
+            address: Pubkey::new_unique(),
             market: *market_address,
             owner: user.pubkey(),
             time_bucket,
             amount,
-            status: crate::types::position::PositionStatus::Active,
+            status: PositionStatus::Active,
             created_at: chrono::Utc::now().timestamp(),
             settled_at: None,
             payout: None,
-        })
+        }
+    }
+
+    /// Buy shares for new positions in a `PricingMode::Lmsr` market
+    ///
+    /// Each net stake buys as many shares in its bucket as it covers; the
+    /// remainder, under one share's cost, stays in the pool. The trades are
+    /// filled on a copy of the market maker, so if one fails none is made.
+    /// Positions in pooled markets buy nothing.
+    async fn buy_shares(&self, market: &Market, positions: &[Position]) -> Result<()> {
+        if market.config.pricing_mode == PricingMode::Parimutuel {
+            return Ok(());
+        }
+
+        // Lock order: market makers before shares
+        let mut makers = self.makers.write().await;
+        let mut holdings = self.shares.write().await;
+        let maker = makers.get_mut(&market.address).ok_or_else(|| {
+            PredaError::Configuration(format!("Market {} is not priced with LMSR", market.address))
+        })?;
+
+        let mut filled = maker.clone();
+        let mut bought = Vec::with_capacity(positions.len());
+        for position in positions {
+            let index = filled
+                .bucket_index(position.time_bucket.start)
+                .ok_or_else(|| {
+                    PredaError::InvalidTimeBucket(format!(
+                        "No LMSR bucket starts at {}",
+                        position.time_bucket.start
+                    ))
+                })?;
+            let budget = position.net_stake(&market.config.fees);
+            let shares = filled.shares_for_cost(index, budget);
+            if shares == 0 {
+                return Err(PredaError::InsufficientFunds {
+                    required: filled.cost_to_buy(index, 1),
+                    available: budget,
+                });
+            }
+            filled.buy(index, shares)?;
+            bought.push((position.address, shares));
+        }
+
+        *maker = filled;
+        holdings.extend(bought);
+        Ok(())
+    }
+
+    /// Cache new positions, adding each net stake to its market's pool and
+    /// each placement fee to the market's collected fees
+    async fn cache_positions(&self, positions: &[Position]) {
+        // Lock order: markets, collected fees, then positions
+        let mut markets = self.markets.write().await;
+        let mut fees = self.collected_fees.write().await;
+        let mut cache = self.positions.write().await;
+        for position in positions {
+            if let Some(market) = markets.get_mut(&position.market) {
+                let fee = market.config.fees.placement_fee(position.amount);
+                market.total_value_locked = market
+                    .total_value_locked
+                    .saturating_add(position.amount - fee);
+                let collected = fees.entry(position.market).or_default();
+                *collected = collected.saturating_add(fee);
+            }
+            cache.insert(position.address, position.clone());
+        }
     }
 
     /// Get user positions in a market
@@ -112,8 +362,221 @@ impl MarketManager {
         market_address: &Pubkey,
         user: &Pubkey,
     ) -> Result<Vec<Position>> {
-        // In production, query blockchain
-        Ok(vec![])
+        // In production, query blockchain; positions placed here are cached
+        Ok(self
+            .positions
+            .read()
+            .await
+            .values()
+            .filter(|pos| pos.market == *market_address && pos.owner == *user)
+            .cloned()
+            .collect())
+    }
+
+    /// Get every position placed in a market
+    pub async fn get_market_positions(&self, market_address: &Pubkey) -> Result<Vec<Position>> {
+        // In production, query position accounts by market
+        Ok(self
+            .positions
+            .read()
+            .await
+            .values()
+            .filter(|pos| pos.market == *market_address)
+            .cloned()
+            .collect())
+    }
+
+    /// Get a user's positions across all markets
+    pub async fn get_positions_by_owner(&self, user: &Pubkey) -> Result<Vec<Position>> {
+        // In production, query position accounts by owner
+        Ok(self
+            .positions
+            .read()
+            .await
+            .values()
+            .filter(|pos| pos.owner == *user)
+            .cloned()
+            .collect())
+    }
+
+    /// Transfer a position to a new owner
+    ///
+    /// Only active positions without an open listing can be transferred; an
+    /// expired listing is closed along the way. See
+    /// [`secondary::transfer_position`] for the instruction.
+    pub async fn transfer_position(
+        &self,
+        owner: &Keypair,
+        position_address: &Pubkey,
+        new_owner: &Pubkey,
+    ) -> Result<Position> {
+        let now = chrono::Utc::now().timestamp();
+        let (listing, _) = secondary::listing_address(&self.program_id, position_address);
+
+        // Lock order: listings before positions
+        let mut listings = self.listings.write().await;
+        if listings.get(&listing).is_some_and(|l| !l.is_expired(now)) {
+            return Err(PredaError::NotTransferable(format!(
+                "position {} is listed for sale",
+                position_address
+            )));
+        }
+
+        // In production, create transfer transaction
+        let mut positions = self.positions.write().await;
+        let position = Self::transferable(&mut positions, position_address, &owner.pubkey())?;
+        position.owner = *new_owner;
+        listings.remove(&listing);
+        Ok(position.clone())
+    }
+
+    /// List a position for sale at `price` lamports
+    ///
+    /// A position has at most one open listing; an expired one is replaced.
+    pub async fn list_position(
+        &self,
+        seller: &Keypair,
+        position_address: &Pubkey,
+        price: u64,
+        expires_at: Option<i64>,
+    ) -> Result<Listing> {
+        // Lock order: listings before positions
+        let mut listings = self.listings.write().await;
+        let mut positions = self.positions.write().await;
+        let position = Self::transferable(&mut positions, position_address, &seller.pubkey())?;
+        if price == 0 {
+            return Err(PredaError::NotTransferable(
+                "listing price must be greater than 0".to_string(),
+            ));
+        }
+
+        let (address, _) = secondary::listing_address(&self.program_id, position_address);
+        let listing = Listing {
+            address,
+            position: *position_address,
+            market: position.market,
+            seller: seller.pubkey(),
+            price,
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at,
+        };
+
+        // In production, create listing transaction
+        if listings
+            .get(&address)
+            .is_some_and(|l| !l.is_expired(listing.created_at))
+        {
+            return Err(PredaError::NotTransferable(format!(
+                "position {} is already listed",
+                position_address
+            )));
+        }
+        listings.insert(address, listing.clone());
+        Ok(listing)
+    }
+
+    /// Withdraw a listing
+    pub async fn cancel_listing(
+        &self,
+        seller: &Keypair,
+        listing_address: &Pubkey,
+    ) -> Result<solana_sdk::signature::Signature> {
+        let mut listings = self.listings.write().await;
+        match listings.get(listing_address) {
+            Some(listing) if listing.seller == seller.pubkey() => {}
+            Some(listing) => {
+                return Err(PredaError::NotTransferable(format!(
+                    "listing {} belongs to {}",
+                    listing_address, listing.seller
+                )))
+            }
+            None => return Err(PredaError::ListingNotFound(listing_address.to_string())),
+        }
+
+        // In production, create cancellation transaction
+        listings.remove(listing_address);
+        Ok(solana_sdk::signature::Signature::default())
+    }
+
+    /// Buy a listed position, paying at most `max_price` lamports
+    ///
+    /// Payment and transfer happen in one instruction (see
+    /// [`secondary::buy_listing`]), so the buyer never pays without receiving
+    /// the position. Returns the position under its new owner.
+    pub async fn buy_listing(
+        &self,
+        buyer: &Keypair,
+        listing_address: &Pubkey,
+        max_price: u64,
+    ) -> Result<Position> {
+        let mut listings = self.listings.write().await;
+        let listing = listings
+            .get(listing_address)
+            .ok_or_else(|| PredaError::ListingNotFound(listing_address.to_string()))?;
+
+        if listing.is_expired(chrono::Utc::now().timestamp()) {
+            return Err(PredaError::NotTransferable(format!(
+                "listing {} has expired",
+                listing_address
+            )));
+        }
+        if listing.price > max_price {
+            return Err(PredaError::InsufficientFunds {
+                required: listing.price,
+                available: max_price,
+            });
+        }
+        if listing.seller == buyer.pubkey() {
+            return Err(PredaError::NotTransferable(
+                "cannot buy your own listing".to_string(),
+            ));
+        }
+
+        // In production, create purchase transaction
+        let mut positions = self.positions.write().await;
+        let position = Self::transferable(&mut positions, &listing.position, &listing.seller)?;
+        position.owner = buyer.pubkey();
+        listings.remove(listing_address);
+        Ok(position.clone())
+    }
+
+    /// Get open listings in a market
+    pub async fn get_listings(&self, market_address: &Pubkey) -> Result<Vec<Listing>> {
+        // In production, query listing accounts
+        let now = chrono::Utc::now().timestamp();
+        Ok(self
+            .listings
+            .read()
+            .await
+            .values()
+            .filter(|listing| listing.market == *market_address && !listing.is_expired(now))
+            .cloned()
+            .collect())
+    }
+
+    fn transferable<'a>(
+        positions: &'a mut HashMap<Pubkey, Position>,
+        position_address: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<&'a mut Position> {
+        let position = positions.get_mut(position_address).ok_or_else(|| {
+            PredaError::NotTransferable(format!("position {} not found", position_address))
+        })?;
+
+        if position.owner != *owner {
+            return Err(PredaError::NotTransferable(format!(
+                "position {} is owned by {}",
+                position_address, position.owner
+            )));
+        }
+        if !position.is_active() {
+            return Err(PredaError::NotTransferable(format!(
+                "position {} is {:?}",
+                position_address, position.status
+            )));
+        }
+
+        Ok(position)
     }
 
     /// Get positions for a time bucket
@@ -131,27 +594,750 @@ impl MarketManager {
         &self,
         market_address: &Pubkey,
     ) -> Result<Vec<TimeBucketAggregate>> {
-        // In production, aggregate on-chain data
-        Ok(vec![])
+        // In production, aggregate on-chain data; positions placed here are cached
+        let mut buckets: Vec<TimeBucketAggregate> = Vec::new();
+        for position in self.positions.read().await.values() {
+            if position.market != *market_address || !position.is_active() {
+                continue;
+            }
+
+            match buckets
+                .iter_mut()
+                .find(|agg| agg.time_bucket == position.time_bucket)
+            {
+                Some(agg) => {
+                    agg.total_staked += position.amount;
+                    agg.position_count += 1;
+                }
+                None => buckets.push(TimeBucketAggregate {
+                    time_bucket: position.time_bucket,
+                    total_staked: position.amount,
+                    position_count: 1,
+                    implied_probability: 0.0,
+                    avg_position_size: 0,
+                }),
+            }
+        }
+
+        let total: u64 = buckets.iter().map(|agg| agg.total_staked).sum();
+        for agg in &mut buckets {
+            agg.implied_probability =
+                TimeBucketAggregate::calculate_implied_probability(agg.total_staked, total);
+            agg.avg_position_size = agg.total_staked / agg.position_count as u64;
+        }
+        buckets.sort_by_key(|agg| agg.time_bucket.start);
+        Ok(buckets)
     }
 
-    /// Withdraw position
+    /// Withdraw an active position before its market resolves
+    ///
+    /// The owner gets `Position::withdrawal_value`: the net stake leaves the
+    /// pool and the withdrawal fee goes to the market's collected fees. The
+    /// position is marked `Withdrawn` with the returned amount as its payout.
+    /// In `PricingMode::Lmsr` markets the shares are sold back to the market
+    /// maker instead, and the owner gets the proceeds less the withdrawal fee.
+    /// Listed positions must be delisted first.
     pub async fn withdraw_position(
         &self,
         user: &Keypair,
         position_address: &Pubkey,
     ) -> Result<solana_sdk::signature::Signature> {
+        let now = chrono::Utc::now().timestamp();
+
+        // Lock order: markets, collected fees, market makers, shares, listings,
+        // then positions
+        let mut markets = self.markets.write().await;
+        let mut fees = self.collected_fees.write().await;
+        let mut makers = self.makers.write().await;
+        let mut holdings = self.shares.write().await;
+        let listings = self.listings.read().await;
+        let mut positions = self.positions.write().await;
+
+        let position = positions.get_mut(position_address).ok_or_else(|| {
+            PredaError::NotWithdrawable(format!("position {} not found", position_address))
+        })?;
+        if position.owner != user.pubkey() {
+            return Err(PredaError::NotWithdrawable(format!(
+                "position {} is owned by {}",
+                position_address, position.owner
+            )));
+        }
+        if !position.is_active() {
+            return Err(PredaError::NotWithdrawable(format!(
+                "position {} is {:?}",
+                position_address, position.status
+            )));
+        }
+        let (listing, _) = secondary::listing_address(&self.program_id, position_address);
+        if listings.get(&listing).is_some_and(|l| !l.is_expired(now)) {
+            return Err(PredaError::NotWithdrawable(format!(
+                "position {} is listed for sale",
+                position_address
+            )));
+        }
+
+        let market = markets
+            .get_mut(&position.market)
+            .ok_or_else(|| PredaError::MarketNotFound(position.market.to_string()))?;
+        if !market.is_active() {
+            return Err(PredaError::InvalidMarketState {
+                expected: "Active".to_string(),
+                actual: format!("{:?}", market.state),
+            });
+        }
+
         // In production, create withdrawal transaction
+        let stake = position.net_stake(&market.config.fees);
+        let (value, fee) = match market.config.pricing_mode {
+            PricingMode::Parimutuel => {
+                let value = position.withdrawal_value(&market.config.fees);
+                (value, stake - value)
+            }
+            PricingMode::Lmsr { .. } => {
+                let maker = makers.get_mut(&market.address).ok_or_else(|| {
+                    PredaError::Configuration(format!(
+                        "Market {} is not priced with LMSR",
+                        market.address
+                    ))
+                })?;
+                let index = maker
+                    .bucket_index(position.time_bucket.start)
+                    .ok_or_else(|| {
+                        PredaError::NotWithdrawable(format!(
+                            "position {} is outside the LMSR buckets",
+                            position_address
+                        ))
+                    })?;
+                let shares = holdings.get(position_address).copied().unwrap_or(0);
+                let proceeds = maker.sell(index, shares)?;
+                holdings.remove(position_address);
+                let fee = market.config.fees.withdrawal_fee(proceeds);
+                (proceeds - fee, fee)
+            }
+        };
+        market.total_value_locked = market.total_value_locked.saturating_sub(stake);
+        let collected = fees.entry(market.address).or_default();
+        *collected = collected.saturating_add(fee);
+
+        position.status = PositionStatus::Withdrawn;
+        position.settled_at = Some(now);
+        position.payout = Some(value);
+        Ok(solana_sdk::signature::Signature::default())
+    }
+
+    /// Settle a market's positions against its validated inflections
+    ///
+    /// The market's `ResolutionMode` picks the inflections, and positions are
+    /// settled with [`settlement::SettlementCalculator::settle_inflections`],
+    /// or with `settle_shares` against the first inflection in LMSR markets.
+    /// The market is resolved now and every settled position gets its status,
+    /// payout and settlement time. Nothing changes if no inflection is
+    /// selected.
+    pub async fn settle_market(
+        &self,
+        market_address: &Pubkey,
+        inflections: &[BeliefInflection],
+    ) -> Result<settlement::SettlementReport> {
+        let now = chrono::Utc::now().timestamp();
+
+        // Lock order: markets, shares, then positions
+        let mut markets = self.markets.write().await;
+        let market = markets
+            .get_mut(market_address)
+            .ok_or_else(|| PredaError::MarketNotFound(market_address.to_string()))?;
+        if let Some(resolved_at) = market.resolved_at {
+            return Err(PredaError::MarketAlreadyResolved(resolved_at));
+        }
+        if !market.is_active() {
+            return Err(PredaError::InvalidMarketState {
+                expected: "Active".to_string(),
+                actual: format!("{:?}", market.state),
+            });
+        }
+
+        let shares = self.shares.read().await;
+        let mut positions = self.positions.write().await;
+        let placed: Vec<Position> = positions
+            .values()
+            .filter(|pos| pos.market == *market_address)
+            .cloned()
+            .collect();
+
+        let mut resolved = market.clone();
+        lifecycle::LifecycleManager::resolve_market(&mut resolved, now)?;
+        let no_inflection = || {
+            PredaError::Generic(format!(
+                "no validated inflection selected for market {}",
+                market_address
+            ))
+        };
+        let report = match resolved.config.pricing_mode {
+            PricingMode::Parimutuel => settlement::SettlementCalculator::settle_inflections(
+                &resolved,
+                &placed,
+                inflections,
+            )
+            .ok_or_else(no_inflection)?,
+            PricingMode::Lmsr { .. } => {
+                let mut validated: Vec<BeliefInflection> = inflections
+                    .iter()
+                    .filter(|inflection| inflection.validated)
+                    .cloned()
+                    .collect();
+                validated.sort_by_key(|inflection| inflection.timestamp);
+                let inflection = resolved
+                    .config
+                    .resolution_mode
+                    .select(&validated)
+                    .first()
+                    .ok_or_else(no_inflection)?;
+                let holdings: Vec<(Position, u64)> = placed
+                    .into_iter()
+                    .map(|pos| {
+                        let held = shares.get(&pos.address).copied().unwrap_or(0);
+                        (pos, held)
+                    })
+                    .collect();
+                settlement::SettlementCalculator::settle_shares(&resolved, &holdings, inflection)
+            }
+        };
+
+        // In production, the settlement is published as a Merkle root
+        *market = resolved;
+        for position in &report.positions {
+            positions.insert(position.address, position.clone());
+        }
+        Ok(report)
+    }
+
+    /// Publish the settlement Merkle root for a market
+    ///
+    /// Claims in the market are checked against the latest published root.
+    pub async fn publish_settlement_root(
+        &self,
+        _authority: &Keypair,
+        market_address: &Pubkey,
+        root: merkle::Hash,
+    ) -> Result<solana_sdk::signature::Signature> {
+        self.get_market(market_address).await?;
+
+        // In production, create root publication transaction signed by the authority
+        self.settlement_roots
+            .write()
+            .await
+            .insert(*market_address, root);
         Ok(solana_sdk::signature::Signature::default())
     }
 
-    /// Claim payout
+    /// Claim payout, attaching the position's inclusion proof
+    ///
+    /// The proof must lead to the settlement root published for the
+    /// position's market, and each position's payout is claimed once.
     pub async fn claim_payout(
         &self,
         user: &Keypair,
         position_address: &Pubkey,
+        proof: &merkle::PayoutProof,
     ) -> Result<solana_sdk::signature::Signature> {
-        // In production, create claim transaction
+        if proof.leaf.position != *position_address {
+            return Err(PredaError::InvalidProof(format!(
+                "proof is for position {}",
+                proof.leaf.position
+            )));
+        }
+
+        if proof.leaf.owner != user.pubkey() {
+            return Err(PredaError::InvalidProof(format!(
+                "position {} is owned by {}",
+                position_address, proof.leaf.owner
+            )));
+        }
+
+        let market = self
+            .positions
+            .read()
+            .await
+            .get(position_address)
+            .map(|pos| pos.market)
+            .ok_or_else(|| {
+                PredaError::InvalidProof(format!("position {} not found", position_address))
+            })?;
+        // Lock order: settlement roots before claims
+        let roots = self.settlement_roots.read().await;
+        let mut claimed = self.claimed.write().await;
+        let root = roots.get(&market).ok_or_else(|| {
+            PredaError::InvalidProof(format!("no settlement root published for {}", market))
+        })?;
+        if !proof.verify(root) {
+            return Err(PredaError::InvalidProof(format!(
+                "proof does not lead to the settlement root of {}",
+                market
+            )));
+        }
+        if !claimed.entry(market).or_default().insert(*position_address) {
+            return Err(PredaError::AlreadyClaimed(position_address.to_string()));
+        }
+
+        // In production, create claim transaction carrying the proof
         Ok(solana_sdk::signature::Signature::default())
     }
+
+    /// Claim refund of a position in a cancelled or expired market
+    ///
+    /// The net stake leaves the pool and, under `RefundPolicy::RefundFees`,
+    /// the placement fee comes back out of the market's collected fees; see
+    /// [`settlement::SettlementCalculator::refund_split`]. The position is
+    /// marked `Withdrawn` when the market was cancelled and `Expired` when it
+    /// expired, so it can only be refunded once.
+    ///
+    /// Returns the refunded amount along with the transaction signature.
+    pub async fn claim_refund(
+        &self,
+        user: &Keypair,
+        position_address: &Pubkey,
+    ) -> Result<(u64, solana_sdk::signature::Signature)> {
+        let now = chrono::Utc::now().timestamp();
+
+        // Lock order: markets, collected fees, shares, then positions
+        let mut markets = self.markets.write().await;
+        let mut fees = self.collected_fees.write().await;
+        let mut holdings = self.shares.write().await;
+        let mut positions = self.positions.write().await;
+
+        let position = positions.get_mut(position_address).ok_or_else(|| {
+            PredaError::NotRefundable(format!("position {} not found", position_address))
+        })?;
+        if position.owner != user.pubkey() {
+            return Err(PredaError::NotRefundable(format!(
+                "position {} is owned by {}",
+                position_address, position.owner
+            )));
+        }
+        let market = markets
+            .get_mut(&position.market)
+            .ok_or_else(|| PredaError::MarketNotFound(position.market.to_string()))?;
+        let (stake, fee) = settlement::SettlementCalculator::refund_split(market, position)?;
+
+        // In production, create refund transaction
+        market.total_value_locked = market.total_value_locked.saturating_sub(stake);
+        let collected = fees.entry(market.address).or_default();
+        *collected = collected.saturating_sub(fee);
+        holdings.remove(position_address);
+
+        position.status = match market.state {
+            MarketState::Cancelled => PositionStatus::Withdrawn,
+            _ => PositionStatus::Expired,
+        };
+        position.settled_at = Some(now);
+        position.payout = Some(stake + fee);
+        Ok((stake + fee, solana_sdk::signature::Signature::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> MarketManager {
+        MarketManager::new(
+            Arc::new(RpcClient::new("http://localhost:8899".to_string())),
+            Pubkey::new_unique(),
+        )
+    }
+
+    async fn manager_with_position() -> (MarketManager, Keypair, Position) {
+        let manager = manager();
+        let creator = Keypair::new();
+        let market = manager
+            .create_market(
+                &creator,
+                MarketType::SentimentTransition,
+                BeliefCondition::SentimentShift {
+                    from_polarity: -0.2,
+                    to_polarity: 0.6,
+                    persistence_window: 3600,
+                },
+                "Test market".to_string(),
+                MarketConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let owner = Keypair::new();
+        let position = manager
+            .place_position(&owner, &market.address, market.created_at, 10_000_000)
+            .await
+            .unwrap();
+        (manager, owner, position)
+    }
+
+    #[tokio::test]
+    async fn test_aggregates_track_positions() {
+        let (manager, owner, position) = manager_with_position().await;
+        let next = position.time_bucket.end;
+        manager
+            .place_position(&owner, &position.market, next, 30_000_000)
+            .await
+            .unwrap();
+
+        let aggregates = manager
+            .get_time_bucket_aggregates(&position.market)
+            .await
+            .unwrap();
+        assert_eq!(aggregates.len(), 2);
+        assert_eq!(aggregates[0].time_bucket, position.time_bucket);
+        assert_eq!(aggregates[0].implied_probability, 0.25);
+        assert_eq!(aggregates[1].avg_position_size, 30_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_position() {
+        let (manager, owner, position) = manager_with_position().await;
+        let recipient = Keypair::new();
+
+        let moved = manager
+            .transfer_position(&owner, &position.address, &recipient.pubkey())
+            .await
+            .unwrap();
+        assert_eq!(moved.owner, recipient.pubkey());
+        assert!(manager
+            .get_user_positions(&position.market, &owner.pubkey())
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The previous owner can no longer move it
+        assert!(manager
+            .transfer_position(&owner, &position.address, &owner.pubkey())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_buy_listing() {
+        let (manager, seller, position) = manager_with_position().await;
+        let buyer = Keypair::new();
+
+        let listing = manager
+            .list_position(&seller, &position.address, 12_000_000, None)
+            .await
+            .unwrap();
+        assert_eq!(manager.get_listings(&position.market).await.unwrap(), vec![listing.clone()]);
+        assert!(manager
+            .transfer_position(&seller, &position.address, &buyer.pubkey())
+            .await
+            .is_err());
+
+        assert!(matches!(
+            manager.buy_listing(&buyer, &listing.address, 11_000_000).await,
+            Err(PredaError::InsufficientFunds { .. })
+        ));
+        let bought = manager
+            .buy_listing(&buyer, &listing.address, 12_000_000)
+            .await
+            .unwrap();
+
+        assert_eq!(bought.owner, buyer.pubkey());
+        assert!(manager.get_listings(&position.market).await.unwrap().is_empty());
+        assert!(matches!(
+            manager.buy_listing(&buyer, &listing.address, 12_000_000).await,
+            Err(PredaError::ListingNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_listing_does_not_block() {
+        let (manager, seller, position) = manager_with_position().await;
+        let now = chrono::Utc::now().timestamp();
+
+        let stale = manager
+            .list_position(&seller, &position.address, 12_000_000, Some(now - 1))
+            .await
+            .unwrap();
+        assert!(manager.get_listings(&position.market).await.unwrap().is_empty());
+
+        // An expired listing is replaced by a new one
+        let open = manager
+            .list_position(&seller, &position.address, 15_000_000, Some(now + 3600))
+            .await
+            .unwrap();
+        assert_eq!(open.address, stale.address);
+        assert_eq!(manager.get_listings(&position.market).await.unwrap(), vec![open.clone()]);
+        assert!(manager
+            .list_position(&seller, &position.address, 20_000_000, None)
+            .await
+            .is_err());
+
+        // Once the listing has expired the position can be given away
+        manager.listings.write().await.insert(
+            open.address,
+            Listing {
+                expires_at: Some(now - 1),
+                ..open.clone()
+            },
+        );
+        let recipient = Keypair::new();
+        let given = manager
+            .transfer_position(&seller, &position.address, &recipient.pubkey())
+            .await
+            .unwrap();
+        assert_eq!(given.owner, recipient.pubkey());
+        assert!(!manager.listings.read().await.contains_key(&open.address));
+    }
+
+    #[tokio::test]
+    async fn test_settled_losses_trip_daily_limit() {
+        let (manager, owner, position) = manager_with_position().await;
+        let other = Keypair::new();
+        let far = position.time_bucket.start + 100 * 3600;
+        manager
+            .place_position(&other, &position.market, far, 10_000_000)
+            .await
+            .unwrap();
+
+        let guard = risk::RiskGuard::new(risk::RiskLimits {
+            max_daily_loss: Some(position.amount / 2),
+            ..risk::RiskLimits::default()
+        });
+        let placements = [risk::Placement {
+            market: position.market,
+            time_bucket: position.time_bucket,
+            amount: 1_000_000,
+        }];
+        let check = |positions: &[Position]| {
+            guard.check(
+                positions,
+                &HashMap::new(),
+                &placements,
+                chrono::Utc::now().timestamp(),
+            )
+        };
+        let held = manager.get_positions_by_owner(&owner.pubkey()).await.unwrap();
+        assert!(check(&held).is_ok());
+
+        // The inflection lands in the other participant's bucket
+        let inflection = BeliefInflection {
+            inflection_type: crate::types::belief::InflectionType::ThresholdCrossing,
+            timestamp: far + 1800,
+            bsi_value: 0.0,
+            velocity: 0.0,
+            sharpness: 0.5,
+            persistence_duration: 0,
+            validated: true,
+        };
+        let report = manager
+            .settle_market(&position.market, std::slice::from_ref(&inflection))
+            .await
+            .unwrap();
+        assert_eq!(report.positions.len(), 2);
+        assert!(manager.get_market(&position.market).await.unwrap().is_resolved());
+        assert!(matches!(
+            manager.settle_market(&position.market, &[inflection]).await,
+            Err(PredaError::MarketAlreadyResolved(_))
+        ));
+
+        let held = manager.get_positions_by_owner(&owner.pubkey()).await.unwrap();
+        assert_eq!(held[0].status, PositionStatus::Lost);
+        assert!(matches!(
+            check(&held),
+            Err(PredaError::RiskLimitExceeded {
+                limit: crate::error::RiskLimit::DailyLoss,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_claim_checks_published_root() {
+        let (manager, owner, position) = manager_with_position().await;
+        let tree = merkle::SettlementTree::new(vec![
+            merkle::PayoutLeaf {
+                position: position.address,
+                owner: owner.pubkey(),
+                payout: 15_000_000,
+            },
+            merkle::PayoutLeaf {
+                position: Pubkey::new_unique(),
+                owner: Pubkey::new_unique(),
+                payout: 5_000_000,
+            },
+        ]);
+        let proof = tree.proof(&position.address).unwrap();
+
+        // Nothing to check against before the root is published
+        assert!(matches!(
+            manager.claim_payout(&owner, &position.address, &proof).await,
+            Err(PredaError::InvalidProof(_))
+        ));
+
+        manager
+            .publish_settlement_root(&owner, &position.market, tree.root())
+            .await
+            .unwrap();
+        assert!(manager
+            .claim_payout(&owner, &position.address, &proof)
+            .await
+            .is_ok());
+        assert!(matches!(
+            manager.claim_payout(&owner, &position.address, &proof).await,
+            Err(PredaError::AlreadyClaimed(_))
+        ));
+
+        let mut inflated = proof;
+        inflated.leaf.payout += 1;
+        assert!(matches!(
+            manager.claim_payout(&owner, &position.address, &inflated).await,
+            Err(PredaError::InvalidProof(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fees_charged_and_withdrawal() {
+        let (manager, owner, position) = manager_with_position().await;
+        let fees = manager.get_market(&position.market).await.unwrap().config.fees;
+        let placement = fees.placement_fee(position.amount);
+
+        let market = manager.get_market(&position.market).await.unwrap();
+        assert_eq!(market.total_value_locked, position.net_stake(&fees));
+        assert_eq!(
+            manager.get_collected_fees(&position.market).await.unwrap(),
+            fees.creation_fee + placement
+        );
+
+        // Only the owner can withdraw, and only once
+        assert!(matches!(
+            manager.withdraw_position(&Keypair::new(), &position.address).await,
+            Err(PredaError::NotWithdrawable(_))
+        ));
+        manager
+            .withdraw_position(&owner, &position.address)
+            .await
+            .unwrap();
+        assert!(manager
+            .withdraw_position(&owner, &position.address)
+            .await
+            .is_err());
+
+        let withdrawn = manager
+            .get_user_positions(&position.market, &owner.pubkey())
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(withdrawn.status, PositionStatus::Withdrawn);
+        assert_eq!(withdrawn.payout, Some(position.withdrawal_value(&fees)));
+
+        let market = manager.get_market(&position.market).await.unwrap();
+        assert_eq!(market.total_value_locked, 0);
+        assert_eq!(
+            manager.get_collected_fees(&position.market).await.unwrap(),
+            fees.creation_fee + position.amount - position.withdrawal_value(&fees)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refund_claimed_once() {
+        let (manager, owner, position) = manager_with_position().await;
+        let fees = manager.get_market(&position.market).await.unwrap().config.fees;
+
+        // Active markets do not refund
+        assert!(manager
+            .claim_refund(&owner, &position.address)
+            .await
+            .is_err());
+        manager
+            .markets
+            .write()
+            .await
+            .get_mut(&position.market)
+            .unwrap()
+            .state = MarketState::Cancelled;
+
+        assert!(matches!(
+            manager.claim_refund(&Keypair::new(), &position.address).await,
+            Err(PredaError::NotRefundable(_))
+        ));
+        let (refund, _) = manager
+            .claim_refund(&owner, &position.address)
+            .await
+            .unwrap();
+        assert_eq!(refund, position.amount);
+        assert!(matches!(
+            manager.claim_refund(&owner, &position.address).await,
+            Err(PredaError::NotRefundable(_))
+        ));
+
+        let refunded = manager
+            .get_user_positions(&position.market, &owner.pubkey())
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(refunded.status, PositionStatus::Withdrawn);
+        assert_eq!(refunded.payout, Some(refund));
+
+        // The pool is empty and only the creation fee is left
+        let market = manager.get_market(&position.market).await.unwrap();
+        assert_eq!(market.total_value_locked, 0);
+        assert_eq!(
+            manager.get_collected_fees(&position.market).await.unwrap(),
+            fees.creation_fee
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lmsr_positions_buy_shares() {
+        let manager = manager();
+        let config = MarketConfig {
+            expiration_time: chrono::Utc::now().timestamp() + 4 * 3600,
+            pricing_mode: PricingMode::Lmsr { liquidity: 1_000_000_000 },
+            ..MarketConfig::default()
+        };
+        let market = manager
+            .create_market(
+                &Keypair::new(),
+                MarketType::SentimentTransition,
+                BeliefCondition::SentimentShift {
+                    from_polarity: -0.2,
+                    to_polarity: 0.6,
+                    persistence_window: 3600,
+                },
+                "LMSR market".to_string(),
+                config,
+            )
+            .await
+            .unwrap();
+        let fees = market.config.fees;
+        let owner = Keypair::new();
+
+        let first = manager
+            .place_position(&owner, &market.address, market.created_at, 100_000_000)
+            .await
+            .unwrap();
+        let second = manager
+            .place_position(&owner, &market.address, market.created_at, 100_000_000)
+            .await
+            .unwrap();
+
+        // Buying pushes the bucket's price up, so the same stake buys fewer shares
+        let maker = manager.get_market_maker(&market.address).await.unwrap();
+        let bought = manager.get_position_shares(&first.address).await.unwrap();
+        let rebought = manager.get_position_shares(&second.address).await.unwrap();
+        assert!(bought > rebought && rebought > 0);
+        assert_eq!(maker.shares()[0], bought + rebought);
+        assert!(maker.price(0) > 0.25);
+        assert!(maker.collected() <= first.net_stake(&fees) + second.net_stake(&fees));
+
+        // Withdrawing sells the shares back
+        manager
+            .withdraw_position(&owner, &second.address)
+            .await
+            .unwrap();
+        let maker = manager.get_market_maker(&market.address).await.unwrap();
+        assert_eq!(maker.shares()[0], bought);
+        assert_eq!(manager.get_position_shares(&second.address).await.unwrap(), 0);
+
+        // Pooled markets have no market maker
+        let (manager, _, position) = manager_with_position().await;
+        assert!(manager.get_market_maker(&position.market).await.is_err());
+        assert_eq!(manager.get_position_shares(&position.address).await.unwrap(), 0);
+    }
 }
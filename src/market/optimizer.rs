@@ -0,0 +1,503 @@
+//! Kelly sizing of stakes across time buckets
+//!
+//! Given our own belief about the inflection time and the stakes already in
+//! the market, stakes are grown greedily, one step at a time, in whichever
+//! bucket most increases expected log wealth. Payouts are modelled the way
+//! `SettlementCalculator::settle_market` computes them: net stakes weighted by
+//! the market's curve multiplier, losing positions excluded, and the
+//! settlement fee taken from the pool. Our weight in each scenario is updated
+//! incrementally, so a step costs O(candidates × scenarios) rather than a
+//! full re-evaluation per candidate. The full-Kelly allocation is then
+//! scaled down by the Kelly fraction and clipped to the market's position
+//! size limits.
+
+use serde::{Deserialize, Serialize};
+
+use super::{distribution::InflectionTimeDistribution, settlement::SettlementCalculator};
+use crate::error::{PredaError, Result};
+use crate::types::{
+    market::Market,
+    position::{PositionStatus, TimeBucket, TimeBucketAggregate},
+};
+
+/// Sizing parameters
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SizingConfig {
+    /// Lamports available to stake
+    pub bankroll: u64,
+
+    /// Fraction of the full Kelly allocation to stake, in (0, 1]
+    pub kelly_fraction: f64,
+
+    /// Expected sharpness of the inflection, as used by the settlement curve
+    pub sharpness: f64,
+}
+
+/// Recommended stakes per bucket
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Allocation {
+    /// Stake (lamports, before fees) per grid bucket, in time order
+    pub stakes: Vec<(TimeBucket, u64)>,
+
+    /// Expected payout under our belief (lamports)
+    pub expected_payout: u64,
+
+    /// Expected log growth of the bankroll under our belief
+    pub expected_log_growth: f64,
+}
+
+impl SizingConfig {
+    /// Half-Kelly sizing of `bankroll` with a moderate inflection sharpness
+    pub fn new(bankroll: u64) -> Self {
+        Self {
+            bankroll,
+            kelly_fraction: 0.5,
+            sharpness: 0.5,
+        }
+    }
+}
+
+impl Allocation {
+    /// Total lamports staked
+    pub fn total(&self) -> u64 {
+        self.stakes.iter().map(|(_, stake)| stake).sum()
+    }
+
+    /// Whether no stake is recommended
+    pub fn is_empty(&self) -> bool {
+        self.stakes.is_empty()
+    }
+}
+
+/// Recommend stakes per bucket for our `belief` about the inflection time
+///
+/// Candidate buckets are the market's grid buckets overlapping the belief;
+/// belief mass outside the market's lifetime counts as a total loss. Other
+/// participants' stake comes from `aggregates`, and `total_value_locked` is
+/// taken as the pool before our stakes. Scoring-rule curves are not supported
+/// because their payouts depend on how every owner spreads their stake.
+pub fn optimize_allocation(
+    market: &Market,
+    aggregates: &[TimeBucketAggregate],
+    belief: &InflectionTimeDistribution,
+    sizing: &SizingConfig,
+) -> Result<Allocation> {
+    let config = &market.config;
+    if config.settlement_curve.is_scoring_rule() {
+        return Err(PredaError::Configuration(
+            "Kelly sizing does not support scoring-rule curves".to_string(),
+        ));
+    }
+    if !(sizing.kelly_fraction > 0.0 && sizing.kelly_fraction <= 1.0) {
+        return Err(PredaError::Configuration(
+            "Kelly fraction must be in (0, 1]".to_string(),
+        ));
+    }
+
+    let candidates = candidate_buckets(market, belief);
+    let model = PayoutModel::new(market, aggregates, belief, &candidates, sizing);
+
+    // Grow the full-Kelly allocation greedily
+    let step = config.min_position_size.max(sizing.bankroll / 100).max(1);
+    let mut stakes = vec![0u64; candidates.len()];
+    let (mut net, mut weights) = model.weights(&stakes);
+    let mut trial = weights.clone();
+    let mut utility = model.log_wealth(&stakes);
+    loop {
+        let total: u64 = stakes.iter().sum();
+        if total + step > sizing.bankroll {
+            break;
+        }
+
+        let mut best: Option<(usize, f64)> = None;
+        for (c, &stake) in stakes.iter().enumerate() {
+            if stake + step > config.max_position_size {
+                continue;
+            }
+            let added = model.added_net(stake, step);
+            model.add_weight(&weights, c, added, &mut trial);
+            let gain = model.log_wealth_from(total + step, net + added, &trial);
+            if best.is_none_or(|(_, best_gain)| gain > best_gain) {
+                best = Some((c, gain));
+            }
+        }
+
+        match best {
+            Some((c, gain)) if gain > utility + 1e-12 => {
+                let added = model.added_net(stakes[c], step);
+                model.add_weight(&weights, c, added, &mut trial);
+                std::mem::swap(&mut weights, &mut trial);
+                stakes[c] += step;
+                net += added;
+                utility = gain;
+            }
+            _ => break,
+        }
+    }
+
+    // Fractional Kelly, dropping stakes that fall below the minimum
+    for stake in stakes.iter_mut() {
+        *stake = (*stake as f64 * sizing.kelly_fraction).floor() as u64;
+        if *stake < config.min_position_size {
+            *stake = 0;
+        }
+    }
+
+    let expected_log_growth = if sizing.bankroll > 0 {
+        model.log_wealth(&stakes) - (sizing.bankroll as f64).ln()
+    } else {
+        0.0
+    };
+    Ok(Allocation {
+        expected_payout: model.expected_payout(&stakes) as u64,
+        expected_log_growth,
+        stakes: candidates
+            .into_iter()
+            .zip(stakes)
+            .filter(|&(_, stake)| stake > 0)
+            .collect(),
+    })
+}
+
+/// Grid buckets overlapping the belief's support
+pub(crate) fn candidate_buckets(
+    market: &Market,
+    belief: &InflectionTimeDistribution,
+) -> Vec<TimeBucket> {
+    let mut buckets = Vec::new();
+    let mut next = market.bucket_for(belief.start().max(market.created_at));
+    while let Some(bucket) = next {
+        if bucket.start >= belief.end() {
+            break;
+        }
+        if belief.probability_between(bucket.start, bucket.end) > 0.0 {
+            buckets.push(bucket);
+        }
+        next = market.bucket_for(bucket.end);
+    }
+    buckets
+}
+
+/// Settlement payouts of candidate stakes across belief scenarios
+struct PayoutModel<'a> {
+    market: &'a Market,
+    bankroll: f64,
+
+    /// Probability of each scenario
+    probabilities: Vec<f64>,
+
+    /// Probability that the inflection falls outside every candidate bucket
+    miss_probability: f64,
+
+    /// Curve multiplier of each candidate bucket in each scenario, 0 if it loses
+    multipliers: Vec<Vec<f64>>,
+
+    /// Weight of other participants' net stake in each scenario
+    other_weights: Vec<f64>,
+}
+
+impl<'a> PayoutModel<'a> {
+    fn new(
+        market: &'a Market,
+        aggregates: &[TimeBucketAggregate],
+        belief: &InflectionTimeDistribution,
+        candidates: &[TimeBucket],
+        sizing: &SizingConfig,
+    ) -> Self {
+        let config = &market.config;
+        let multiplier = |bucket: &TimeBucket, timestamp: i64| {
+            let distance = bucket.distance_from(timestamp);
+            let m = SettlementCalculator::multiplier(config, distance, sizing.sharpness);
+            match SettlementCalculator::classify(config, m) {
+                PositionStatus::Lost => 0.0,
+                _ => m,
+            }
+        };
+
+        let probabilities: Vec<f64> = candidates
+            .iter()
+            .map(|bucket| belief.probability_between(bucket.start, bucket.end))
+            .collect();
+        let times: Vec<i64> = candidates.iter().map(TimeBucket::midpoint).collect();
+
+        Self {
+            market,
+            bankroll: sizing.bankroll as f64,
+            miss_probability: (1.0 - probabilities.iter().sum::<f64>()).max(0.0),
+            probabilities,
+            multipliers: times
+                .iter()
+                .map(|&t| {
+                    candidates
+                        .iter()
+                        .map(|bucket| multiplier(bucket, t))
+                        .collect()
+                })
+                .collect(),
+            other_weights: times
+                .iter()
+                .map(|&t| {
+                    aggregates
+                        .iter()
+                        .map(|agg| {
+                            let net =
+                                agg.total_staked - config.fees.placement_fee(agg.total_staked);
+                            net as f64 * multiplier(&agg.time_bucket, t)
+                        })
+                        .sum()
+                })
+                .collect(),
+        }
+    }
+
+    /// Total net stake and our weight in each scenario for gross `stakes`
+    /// per candidate bucket
+    fn weights(&self, stakes: &[u64]) -> (u64, Vec<f64>) {
+        let fees = &self.market.config.fees;
+        let net: Vec<u64> = stakes
+            .iter()
+            .map(|&stake| stake - fees.placement_fee(stake))
+            .collect();
+
+        let weights = self
+            .multipliers
+            .iter()
+            .map(|multipliers| {
+                net.iter()
+                    .zip(multipliers)
+                    .map(|(&n, m)| n as f64 * m)
+                    .sum()
+            })
+            .collect();
+        (net.iter().sum(), weights)
+    }
+
+    /// Net stake added by growing a gross stake by `step`
+    fn added_net(&self, stake: u64, step: u64) -> u64 {
+        let fees = &self.market.config.fees;
+        let grown = stake + step;
+        (grown - fees.placement_fee(grown)) - (stake - fees.placement_fee(stake))
+    }
+
+    /// `weights` with `added` net stake in candidate `c`, written to `out`
+    fn add_weight(&self, weights: &[f64], c: usize, added: u64, out: &mut [f64]) {
+        for ((out, weight), multipliers) in out.iter_mut().zip(weights).zip(&self.multipliers) {
+            *out = weight + added as f64 * multipliers[c];
+        }
+    }
+
+    /// Payout in each scenario for a total net stake and our weight per scenario
+    fn payouts_from<'b>(&'b self, net: u64, weights: &'b [f64]) -> impl Iterator<Item = f64> + 'b {
+        let fees = &self.market.config.fees;
+        let pool = self.market.total_value_locked + net;
+        let distributable = (pool - fees.settlement_fee(pool)) as f64;
+
+        weights
+            .iter()
+            .zip(&self.other_weights)
+            .map(move |(&ours, other)| {
+                if ours > 0.0 {
+                    distributable * ours / (other + ours)
+                } else {
+                    0.0
+                }
+            })
+    }
+
+    /// Payout in each scenario for gross `stakes` per candidate bucket
+    fn payouts(&self, stakes: &[u64]) -> Vec<f64> {
+        let (net, weights) = self.weights(stakes);
+        self.payouts_from(net, &weights).collect()
+    }
+
+    /// Expected log of final wealth
+    fn log_wealth(&self, stakes: &[u64]) -> f64 {
+        let (net, weights) = self.weights(stakes);
+        self.log_wealth_from(stakes.iter().sum(), net, &weights)
+    }
+
+    /// Expected log of final wealth after spending `spent` on stakes with
+    /// total net stake `net` and our weight per scenario
+    fn log_wealth_from(&self, spent: u64, net: u64, weights: &[f64]) -> f64 {
+        let remaining = self.bankroll - spent as f64;
+        let expected: f64 = self
+            .payouts_from(net, weights)
+            .zip(&self.probabilities)
+            .map(|(payout, p)| p * (remaining + payout).ln())
+            .sum();
+
+        if self.miss_probability > 0.0 {
+            expected + self.miss_probability * remaining.ln()
+        } else {
+            expected
+        }
+    }
+
+    /// Expected payout
+    fn expected_payout(&self, stakes: &[u64]) -> f64 {
+        self.payouts(stakes)
+            .iter()
+            .zip(&self.probabilities)
+            .map(|(payout, p)| p * payout)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::fixtures;
+
+    const HOUR: i64 = 3600;
+
+    fn market(total_value_locked: u64) -> Market {
+        let mut market = Market {
+            total_value_locked,
+            ..fixtures::market()
+        };
+        market.config.expiration_time = 24 * HOUR;
+        market
+    }
+
+    fn aggregates(stakes: &[(i64, u64)]) -> Vec<TimeBucketAggregate> {
+        stakes
+            .iter()
+            .map(|&(hour, total_staked)| fixtures::aggregate(hour * HOUR, total_staked))
+            .collect()
+    }
+
+    fn belief(weights: &[(i64, f64)]) -> InflectionTimeDistribution {
+        let weights: Vec<(TimeBucket, f64)> = weights
+            .iter()
+            .map(|&(hour, weight)| (TimeBucket::from_duration(hour * HOUR, 3600), weight))
+            .collect();
+        InflectionTimeDistribution::from_weights(&weights).unwrap()
+    }
+
+    #[test]
+    fn test_stakes_where_market_underprices() {
+        // The market crowds hour 2; we believe hour 8 is likely
+        let stakes = [(2, 5_000_000_000), (8, 200_000_000)];
+        let market = market(5_200_000_000);
+        let belief = belief(&[(2, 0.4), (8, 0.6)]);
+
+        let allocation = optimize_allocation(
+            &market,
+            &aggregates(&stakes),
+            &belief,
+            &SizingConfig::new(2_000_000_000),
+        )
+        .unwrap();
+
+        // Half Kelly, mostly on hour 8 with a small log-utility hedge on hour 2
+        assert!(allocation.total() <= 1_000_000_000);
+        let stake_at = |hour: i64| {
+            allocation
+                .stakes
+                .iter()
+                .find(|(bucket, _)| bucket.start == hour * HOUR)
+                .map_or(0, |(_, stake)| *stake)
+        };
+        assert!(stake_at(8) > 5 * stake_at(2));
+        assert!(allocation.expected_log_growth > 0.0);
+        assert!(allocation.expected_payout > allocation.total());
+    }
+
+    #[test]
+    fn test_respects_position_limits_and_kelly_fraction() {
+        let stakes = [(2, 5_000_000_000), (8, 200_000_000)];
+        let market = market(5_200_000_000);
+        let belief = belief(&[(2, 0.4), (8, 0.6)]);
+        let full = SizingConfig {
+            kelly_fraction: 1.0,
+            ..SizingConfig::new(10_000_000_000)
+        };
+
+        let full_kelly =
+            optimize_allocation(&market, &aggregates(&stakes), &belief, &full).unwrap();
+        let quarter = optimize_allocation(
+            &market,
+            &aggregates(&stakes),
+            &belief,
+            &SizingConfig {
+                kelly_fraction: 0.25,
+                ..full
+            },
+        )
+        .unwrap();
+
+        let limits = &market.config;
+        for (_, stake) in full_kelly.stakes.iter().chain(&quarter.stakes) {
+            assert!(*stake >= limits.min_position_size && *stake <= limits.max_position_size);
+        }
+        assert_eq!(quarter.total(), full_kelly.total() / 4);
+    }
+
+    #[test]
+    fn test_no_edge_no_stake() {
+        // Our belief matches the market's, so fees make every stake a loss
+        let stakes = [(2, 1_000_000_000), (8, 1_000_000_000)];
+        let market = market(2_000_000_000);
+
+        let allocation = optimize_allocation(
+            &market,
+            &aggregates(&stakes),
+            &belief(&[(2, 0.5), (8, 0.5)]),
+            &SizingConfig::new(1_000_000_000),
+        )
+        .unwrap();
+
+        assert!(allocation.is_empty());
+    }
+
+    #[test]
+    fn test_incremental_weights_match_full_evaluation() {
+        let market = market(5_200_000_000);
+        let belief = belief(&[(2, 0.4), (5, 0.2), (8, 0.4)]);
+        let candidates = candidate_buckets(&market, &belief);
+        let sizing = SizingConfig::new(2_000_000_000);
+        let model = PayoutModel::new(
+            &market,
+            &aggregates(&[(2, 5_000_000_000), (8, 200_000_000)]),
+            &belief,
+            &candidates,
+            &sizing,
+        );
+
+        let mut stakes = vec![0u64; candidates.len()];
+        let (mut net, mut weights) = model.weights(&stakes);
+        let mut trial = weights.clone();
+        for (c, step) in [(0, 30_000_001), (2, 70_000_003), (0, 12_345_678)] {
+            let added = model.added_net(stakes[c], step);
+            model.add_weight(&weights, c, added, &mut trial);
+            std::mem::swap(&mut weights, &mut trial);
+            stakes[c] += step;
+            net += added;
+        }
+
+        let (full_net, full_weights) = model.weights(&stakes);
+        assert_eq!(net, full_net);
+        for (incremental, full) in weights.iter().zip(&full_weights) {
+            assert!((incremental - full).abs() <= 1e-6 * full.max(1.0));
+        }
+        let spent = stakes.iter().sum();
+        assert!(
+            (model.log_wealth_from(spent, net, &weights) - model.log_wealth(&stakes)).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_config() {
+        let mut market = market(0);
+        let belief = belief(&[(1, 1.0)]);
+        let sizing = SizingConfig {
+            kelly_fraction: 0.0,
+            ..SizingConfig::new(1_000_000_000)
+        };
+        assert!(optimize_allocation(&market, &[], &belief, &sizing).is_err());
+
+        market.config.settlement_curve = crate::types::market::SettlementCurve::LogScore;
+        let sizing = SizingConfig::new(1_000_000_000);
+        assert!(optimize_allocation(&market, &[], &belief, &sizing).is_err());
+    }
+}
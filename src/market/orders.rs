@@ -0,0 +1,665 @@
+//! Client-side conditional position orders
+//!
+//! Orders wait until their conditions hold on the latest time bucket
+//! aggregates and BSI, then are placed with `place_position`. The engine only
+//! decides which orders fire; `PredaClient::process_orders` fetches market
+//! data, places the positions and records the outcome. With a store path every
+//! change is written to a JSON file so pending orders survive restarts; a
+//! change that cannot be saved is undone. A firing order is saved as
+//! `Placing` before its position is placed, so an order interrupted by a
+//! restart is never placed twice.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{PredaError, Result};
+use crate::types::{
+    belief::BeliefStateIndex,
+    market::Market,
+    position::{Position, TimeBucket, TimeBucketAggregate},
+};
+
+/// Condition that must hold for an order to fire
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OrderCondition {
+    /// Implied probability of the order's bucket is below the threshold
+    ImpliedProbabilityBelow(f64),
+
+    /// Implied probability of the order's bucket is above the threshold
+    ImpliedProbabilityAbove(f64),
+
+    /// BSI velocity is above the threshold
+    VelocityAbove(f64),
+
+    /// BSI velocity is below the threshold
+    VelocityBelow(f64),
+
+    /// BSI value is above the threshold
+    BsiAbove(f64),
+
+    /// BSI value is below the threshold
+    BsiBelow(f64),
+}
+
+/// Lifecycle of a conditional order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum OrderStatus {
+    /// Waiting for its conditions
+    Pending,
+
+    /// Conditions held and its position is being placed
+    Placing { since: i64 },
+
+    /// The engine restarted while the order was `Placing`; the position may
+    /// or may not have been placed, so the order is not retried
+    Interrupted { since: i64 },
+
+    /// Position placed
+    Filled { position: Pubkey, filled_at: i64 },
+
+    /// Cancelled by the user
+    Cancelled,
+
+    /// Expired before its conditions held
+    Expired,
+
+    /// Conditions held but the position could not be placed
+    Failed(String),
+}
+
+/// Parameters of a new conditional order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderRequest {
+    /// Market to place the position in
+    pub market: Pubkey,
+
+    /// Start of the target time bucket
+    pub time_bucket_start: i64,
+
+    /// Amount to stake (lamports)
+    pub amount: u64,
+
+    /// Conditions that must all hold
+    pub conditions: Vec<OrderCondition>,
+
+    /// Timestamp after which the order no longer fires
+    pub expires_at: Option<i64>,
+}
+
+/// Conditional order tracked by the engine
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConditionalOrder {
+    /// Order id, unique within an engine
+    pub id: u64,
+
+    /// Order parameters
+    pub request: OrderRequest,
+
+    /// Submission timestamp
+    pub created_at: i64,
+
+    /// Current status
+    pub status: OrderStatus,
+}
+
+/// Holds conditional orders and decides when they fire
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OrderEngine {
+    next_id: u64,
+    orders: Vec<ConditionalOrder>,
+
+    #[serde(skip)]
+    store: Option<PathBuf>,
+}
+
+impl OrderCondition {
+    /// Check the condition against market data for `bucket_start`
+    pub fn holds(
+        &self,
+        bucket_start: i64,
+        aggregates: &[TimeBucketAggregate],
+        bsi: &BeliefStateIndex,
+    ) -> bool {
+        // An empty pool implies no probability, so neither condition holds
+        match *self {
+            OrderCondition::ImpliedProbabilityBelow(threshold) => {
+                implied_probability(bucket_start, aggregates).is_some_and(|p| p < threshold)
+            }
+            OrderCondition::ImpliedProbabilityAbove(threshold) => {
+                implied_probability(bucket_start, aggregates).is_some_and(|p| p > threshold)
+            }
+            OrderCondition::VelocityAbove(threshold) => bsi.velocity > threshold,
+            OrderCondition::VelocityBelow(threshold) => bsi.velocity < threshold,
+            OrderCondition::BsiAbove(threshold) => bsi.value > threshold,
+            OrderCondition::BsiBelow(threshold) => bsi.value < threshold,
+        }
+    }
+}
+
+impl ConditionalOrder {
+    /// Check if the order is still waiting
+    pub fn is_pending(&self) -> bool {
+        self.status == OrderStatus::Pending
+    }
+
+    /// Check if the order's position is being placed
+    pub fn is_placing(&self) -> bool {
+        matches!(self.status, OrderStatus::Placing { .. })
+    }
+}
+
+impl OrderEngine {
+    /// Create an engine that keeps orders in memory only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open an engine persisted at `path`, loading any saved orders
+    ///
+    /// Orders saved as `Placing` were interrupted mid-placement and become
+    /// `Interrupted`, so they are not placed again.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut engine = if path.exists() {
+            let data = fs::read(&path).map_err(|e| storage_error(&path, e))?;
+            serde_json::from_slice::<OrderEngine>(&data)?
+        } else {
+            Self::default()
+        };
+        engine.store = Some(path);
+
+        let mut interrupted = false;
+        for order in engine.orders.iter_mut() {
+            if let OrderStatus::Placing { since } = order.status {
+                order.status = OrderStatus::Interrupted { since };
+                interrupted = true;
+            }
+        }
+        if interrupted {
+            engine.save()?;
+        }
+        Ok(engine)
+    }
+
+    /// Submit an order in `market`, returning its id
+    ///
+    /// The amount must be positive and the target bucket on the market's
+    /// grid, so an order never fires into a placement bound to fail.
+    pub fn submit(&mut self, market: &Market, request: OrderRequest, now: i64) -> Result<u64> {
+        if request.market != market.address {
+            return Err(PredaError::Generic(format!(
+                "Order is for market {}, not {}",
+                request.market, market.address
+            )));
+        }
+        if request.amount == 0 {
+            return Err(PredaError::Generic(
+                "Order amount must be greater than 0".to_string(),
+            ));
+        }
+        market.validate_bucket(&TimeBucket::from_duration(
+            request.time_bucket_start,
+            market.config.time_bucket_size,
+        ))?;
+
+        let snapshot = self.snapshot();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.orders.push(ConditionalOrder {
+            id,
+            request,
+            created_at: now,
+            status: OrderStatus::Pending,
+        });
+
+        self.save_or_restore(snapshot)?;
+        Ok(id)
+    }
+
+    /// Cancel a pending order
+    pub fn cancel(&mut self, id: u64) -> Result<()> {
+        let snapshot = self.snapshot();
+        let order = self.pending_mut(id)?;
+        order.status = OrderStatus::Cancelled;
+        self.save_or_restore(snapshot)
+    }
+
+    /// Order by id
+    pub fn get(&self, id: u64) -> Option<&ConditionalOrder> {
+        self.orders.iter().find(|order| order.id == id)
+    }
+
+    /// All orders, in submission order
+    pub fn orders(&self) -> &[ConditionalOrder] {
+        &self.orders
+    }
+
+    /// Pending orders, in submission order
+    pub fn pending(&self) -> impl Iterator<Item = &ConditionalOrder> {
+        self.orders.iter().filter(|order| order.is_pending())
+    }
+
+    /// Markets with pending orders
+    pub fn markets(&self) -> Vec<Pubkey> {
+        let mut markets: Vec<Pubkey> = self.pending().map(|order| order.request.market).collect();
+        markets.sort();
+        markets.dedup();
+        markets
+    }
+
+    /// Expire stale orders and return the orders of `market` ready to fire
+    ///
+    /// An order expires once `now` reaches its `expires_at`. Returned orders
+    /// are saved as `Placing` before this returns and stay so until
+    /// `mark_filled` or `mark_failed` records the outcome. If the save fails,
+    /// no order changes and none is returned.
+    pub fn evaluate(
+        &mut self,
+        market: &Pubkey,
+        aggregates: &[TimeBucketAggregate],
+        bsi: &BeliefStateIndex,
+        now: i64,
+    ) -> Result<Vec<ConditionalOrder>> {
+        let snapshot = self.snapshot();
+        let mut changed = false;
+        let mut fired = Vec::new();
+        for order in self.orders.iter_mut().filter(|order| order.is_pending()) {
+            if order
+                .request
+                .expires_at
+                .is_some_and(|expires_at| now >= expires_at)
+            {
+                order.status = OrderStatus::Expired;
+                changed = true;
+            } else if order.request.market == *market
+                && order.request.conditions.iter().all(|condition| {
+                    condition.holds(order.request.time_bucket_start, aggregates, bsi)
+                })
+            {
+                order.status = OrderStatus::Placing { since: now };
+                fired.push(order.clone());
+                changed = true;
+            }
+        }
+        if changed {
+            self.save_or_restore(snapshot)?;
+        }
+
+        Ok(fired)
+    }
+
+    /// Record that an order's position was placed
+    pub fn mark_filled(&mut self, id: u64, position: &Position) -> Result<()> {
+        let snapshot = self.snapshot();
+        let order = self.placing_mut(id)?;
+        order.status = OrderStatus::Filled {
+            position: position.address,
+            filled_at: position.created_at,
+        };
+        self.save_or_restore(snapshot)
+    }
+
+    /// Record that an order's position could not be placed
+    pub fn mark_failed(&mut self, id: u64, reason: String) -> Result<()> {
+        let snapshot = self.snapshot();
+        let order = self.placing_mut(id)?;
+        order.status = OrderStatus::Failed(reason);
+        self.save_or_restore(snapshot)
+    }
+
+    fn pending_mut(&mut self, id: u64) -> Result<&mut ConditionalOrder> {
+        self.orders
+            .iter_mut()
+            .find(|order| order.id == id && order.is_pending())
+            .ok_or(PredaError::OrderNotFound(id))
+    }
+
+    fn placing_mut(&mut self, id: u64) -> Result<&mut ConditionalOrder> {
+        self.orders
+            .iter_mut()
+            .find(|order| order.id == id && order.is_placing())
+            .ok_or(PredaError::OrderNotFound(id))
+    }
+
+    /// Orders and next id as they are now, to restore if a save fails
+    fn snapshot(&self) -> Option<(Vec<ConditionalOrder>, u64)> {
+        self.store
+            .as_ref()
+            .map(|_| (self.orders.clone(), self.next_id))
+    }
+
+    /// Save, restoring `snapshot` if the save fails so memory matches the store
+    fn save_or_restore(&mut self, snapshot: Option<(Vec<ConditionalOrder>, u64)>) -> Result<()> {
+        let result = self.save();
+        if result.is_err() {
+            if let Some((orders, next_id)) = snapshot {
+                self.orders = orders;
+                self.next_id = next_id;
+            }
+        }
+        result
+    }
+
+    /// Write the orders to the store, replacing the file atomically
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.store else {
+            return Ok(());
+        };
+
+        let data = serde_json::to_vec_pretty(self)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data).map_err(|e| storage_error(&tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| storage_error(path, e))
+    }
+}
+
+/// Share of the market's stake in the bucket starting at `bucket_start`
+///
+/// `None` while the market holds no stake.
+fn implied_probability(bucket_start: i64, aggregates: &[TimeBucketAggregate]) -> Option<f64> {
+    let total: u64 = aggregates.iter().map(|agg| agg.total_staked).sum();
+    if total == 0 {
+        return None;
+    }
+    let stake: u64 = aggregates
+        .iter()
+        .filter(|agg| agg.time_bucket.start == bucket_start)
+        .map(|agg| agg.total_staked)
+        .sum();
+    Some(TimeBucketAggregate::calculate_implied_probability(
+        stake, total,
+    ))
+}
+
+fn storage_error(path: &Path, error: std::io::Error) -> PredaError {
+    PredaError::Storage(format!("{}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::fixtures;
+
+    fn aggregates(stakes: &[(i64, u64)]) -> Vec<TimeBucketAggregate> {
+        stakes
+            .iter()
+            .map(|&(start, total_staked)| fixtures::aggregate(start, total_staked))
+            .collect()
+    }
+
+    fn bsi(value: f64, velocity: f64) -> BeliefStateIndex {
+        BeliefStateIndex {
+            value,
+            velocity,
+            ..BeliefStateIndex::new("test".to_string())
+        }
+    }
+
+    fn request(market: Pubkey, conditions: Vec<OrderCondition>) -> OrderRequest {
+        OrderRequest {
+            market,
+            time_bucket_start: 3600,
+            amount: 500_000_000,
+            conditions,
+            expires_at: Some(1_000),
+        }
+    }
+
+    fn position(created_at: i64) -> Position {
+        Position {
+            created_at,
+            ..fixtures::position(3600, 500_000_000)
+        }
+    }
+
+    #[test]
+    fn test_orders_fire_when_conditions_hold() {
+        let market = fixtures::market();
+        let mut engine = OrderEngine::new();
+        let cheap = engine
+            .submit(
+                &market,
+                request(
+                    market.address,
+                    vec![OrderCondition::ImpliedProbabilityBelow(0.1)],
+                ),
+                0,
+            )
+            .unwrap();
+        let momentum = engine
+            .submit(
+                &market,
+                request(market.address, vec![OrderCondition::VelocityAbove(0.05)]),
+                0,
+            )
+            .unwrap();
+
+        // 20% of the stake sits in the order's bucket and belief is flat
+        let crowded = aggregates(&[(0, 800), (3600, 200)]);
+        assert!(engine
+            .evaluate(&market.address, &crowded, &bsi(0.0, 0.0), 10)
+            .unwrap()
+            .is_empty());
+
+        let cheap_now = aggregates(&[(0, 950), (3600, 50)]);
+        let fired = engine
+            .evaluate(&market.address, &cheap_now, &bsi(0.0, 0.1), 20)
+            .unwrap();
+        assert_eq!(
+            fired.iter().map(|o| o.id).collect::<Vec<_>>(),
+            vec![cheap, momentum]
+        );
+
+        // Fired orders are placing and do not fire again
+        engine.mark_filled(cheap, &position(20)).unwrap();
+        assert!(engine
+            .evaluate(&market.address, &cheap_now, &bsi(0.0, 0.1), 30)
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            engine.get(cheap).unwrap().status,
+            OrderStatus::Filled { .. }
+        ));
+        assert_eq!(
+            engine.get(momentum).unwrap().status,
+            OrderStatus::Placing { since: 20 }
+        );
+        assert!(engine.mark_filled(cheap, &position(30)).is_err());
+    }
+
+    #[test]
+    fn test_empty_pool_gives_no_probability_signal() {
+        let market = fixtures::market();
+        let mut engine = OrderEngine::new();
+        engine
+            .submit(
+                &market,
+                request(
+                    market.address,
+                    vec![OrderCondition::ImpliedProbabilityBelow(0.1)],
+                ),
+                0,
+            )
+            .unwrap();
+        engine
+            .submit(
+                &market,
+                request(
+                    market.address,
+                    vec![OrderCondition::ImpliedProbabilityAbove(-1.0)],
+                ),
+                0,
+            )
+            .unwrap();
+
+        assert!(engine
+            .evaluate(&market.address, &[], &bsi(0.0, 0.0), 10)
+            .unwrap()
+            .is_empty());
+        assert!(engine
+            .evaluate(
+                &market.address,
+                &aggregates(&[(3600, 0)]),
+                &bsi(0.0, 0.0),
+                10
+            )
+            .unwrap()
+            .is_empty());
+        assert_eq!(engine.pending().count(), 2);
+    }
+
+    #[test]
+    fn test_expiry_and_cancellation() {
+        let market = fixtures::market();
+        let mut engine = OrderEngine::new();
+        let first = engine
+            .submit(&market, request(market.address, vec![]), 0)
+            .unwrap();
+        let second = engine
+            .submit(&market, request(market.address, vec![]), 0)
+            .unwrap();
+
+        engine.cancel(first).unwrap();
+        assert!(engine.cancel(first).is_err());
+
+        assert!(engine
+            .evaluate(&market.address, &[], &bsi(0.0, 0.0), 1_000)
+            .unwrap()
+            .is_empty());
+        assert_eq!(engine.get(second).unwrap().status, OrderStatus::Expired);
+        assert!(engine.markets().is_empty());
+    }
+
+    #[test]
+    fn test_orders_survive_restart() {
+        let path = std::env::temp_dir().join(format!("preda-orders-{}.json", Pubkey::new_unique()));
+        let market = fixtures::market();
+
+        let mut engine = OrderEngine::open(&path).unwrap();
+        let kept = engine
+            .submit(
+                &market,
+                request(market.address, vec![OrderCondition::BsiAbove(0.5)]),
+                0,
+            )
+            .unwrap();
+        let cancelled = engine
+            .submit(&market, request(market.address, vec![]), 0)
+            .unwrap();
+        engine.cancel(cancelled).unwrap();
+        drop(engine);
+
+        let mut reopened = OrderEngine::open(&path).unwrap();
+        assert_eq!(
+            reopened.pending().map(|o| o.id).collect::<Vec<_>>(),
+            vec![kept]
+        );
+        assert_eq!(
+            reopened.get(cancelled).unwrap().status,
+            OrderStatus::Cancelled
+        );
+
+        let next = reopened
+            .submit(&market, request(market.address, vec![]), 0)
+            .unwrap();
+        assert_eq!(next, 2);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_interrupted_placement_is_not_retried() {
+        let path = std::env::temp_dir().join(format!("preda-orders-{}.json", Pubkey::new_unique()));
+        let market = fixtures::market();
+
+        // Crash after the order fired but before its outcome was recorded
+        let mut engine = OrderEngine::open(&path).unwrap();
+        let id = engine
+            .submit(&market, request(market.address, vec![]), 0)
+            .unwrap();
+        assert_eq!(
+            engine
+                .evaluate(&market.address, &[], &bsi(0.0, 0.0), 10)
+                .unwrap()
+                .len(),
+            1
+        );
+        drop(engine);
+
+        let mut reopened = OrderEngine::open(&path).unwrap();
+        assert_eq!(
+            reopened.get(id).unwrap().status,
+            OrderStatus::Interrupted { since: 10 }
+        );
+        assert!(reopened
+            .evaluate(&market.address, &[], &bsi(0.0, 0.0), 20)
+            .unwrap()
+            .is_empty());
+        assert!(reopened.mark_filled(id, &position(20)).is_err());
+        drop(reopened);
+
+        // The reconciled status is saved
+        let reopened = OrderEngine::open(&path).unwrap();
+        assert_eq!(
+            reopened.get(id).unwrap().status,
+            OrderStatus::Interrupted { since: 10 }
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_submit_validates_order() {
+        let market = fixtures::market();
+        let mut engine = OrderEngine::new();
+
+        let empty = OrderRequest {
+            amount: 0,
+            ..request(market.address, vec![])
+        };
+        assert!(engine.submit(&market, empty, 0).is_err());
+
+        let off_grid = OrderRequest {
+            time_bucket_start: 1_800,
+            ..request(market.address, vec![])
+        };
+        assert!(matches!(
+            engine.submit(&market, off_grid, 0),
+            Err(PredaError::InvalidTimeBucket(_))
+        ));
+
+        let elsewhere = request(Pubkey::new_unique(), vec![]);
+        assert!(engine.submit(&market, elsewhere, 0).is_err());
+        assert!(engine.orders().is_empty());
+    }
+
+    #[test]
+    fn test_failed_save_restores_orders() {
+        let dir = std::env::temp_dir().join(format!("preda-orders-{}", Pubkey::new_unique()));
+        fs::create_dir(&dir).unwrap();
+        let market = fixtures::market();
+
+        let mut engine = OrderEngine::open(dir.join("orders.json")).unwrap();
+        let id = engine
+            .submit(&market, request(market.address, vec![]), 0)
+            .unwrap();
+
+        // Nothing can be saved once the store's directory is gone
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            engine.evaluate(&market.address, &[], &bsi(0.0, 0.0), 10),
+            Err(PredaError::Storage(_))
+        ));
+        assert_eq!(engine.get(id).unwrap().status, OrderStatus::Pending);
+
+        assert!(engine
+            .submit(&market, request(market.address, vec![]), 0)
+            .is_err());
+        assert_eq!(engine.orders().len(), 1);
+        assert!(engine.cancel(id).is_err());
+        assert!(engine.get(id).unwrap().is_pending());
+    }
+}
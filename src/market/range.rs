@@ -0,0 +1,186 @@
+//! Splitting range positions across a market's time buckets
+
+use super::settlement::{SettlementCalculator, WEIGHT_SCALE};
+use crate::error::{PredaError, Result};
+use crate::types::{
+    market::Market,
+    position::{RangeWeighting, TimeBucket},
+};
+
+/// Split `amount` across the market's buckets in `[start, end)`
+///
+/// Every bucket in the range must be on the market's bucket grid (see
+/// `Market::bucket_grid`), and a weighting's center must lie in the range.
+/// Lamports are allocated exactly by largest remainder. Buckets whose share
+/// would fall below `min_position_size` are dropped and their stake is spread
+/// over the rest, smallest share first. Returns the funded buckets in time
+/// order.
+pub fn split_range(
+    market: &Market,
+    start: i64,
+    end: i64,
+    amount: u64,
+    weighting: RangeWeighting,
+) -> Result<Vec<(TimeBucket, u64)>> {
+    let config = &market.config;
+    let size = config.time_bucket_size as i64;
+
+    if end <= start {
+        return Err(PredaError::InvalidTimeBucket(format!(
+            "range end {} must be after start {}",
+            end, start
+        )));
+    }
+    if (end - start) % size != 0 {
+        return Err(PredaError::InvalidTimeBucket(format!(
+            "range {}..{} is not a whole number of {}s buckets",
+            start, end, size
+        )));
+    }
+    if let Some(center) = weighting.center() {
+        if center < start || center > end {
+            return Err(PredaError::InvalidTimeBucket(format!(
+                "weighting center {} is outside the range {}..{}",
+                center, start, end
+            )));
+        }
+    }
+    if let RangeWeighting::Gaussian { sigma, .. } = weighting {
+        if !sigma.is_finite() || sigma <= 0.0 {
+            return Err(PredaError::Configuration(
+                "Gaussian range sigma must be positive".to_string(),
+            ));
+        }
+    }
+    if amount < config.min_position_size {
+        return Err(PredaError::InsufficientFunds {
+            required: config.min_position_size,
+            available: amount,
+        });
+    }
+
+    let range = TimeBucket { start, end };
+    let buckets: Vec<TimeBucket> = (start..end)
+        .step_by(size as usize)
+        .map(|bucket_start| TimeBucket::from_duration(bucket_start, config.time_bucket_size))
+        .collect();
+    for bucket in &buckets {
+        market.validate_bucket(bucket)?;
+    }
+
+    let mut weights: Vec<u128> = buckets
+        .iter()
+        .map(|bucket| {
+            let weight = weighting.weight(bucket, &range, config.time_bucket_size);
+            (weight * WEIGHT_SCALE as f64).round() as u128
+        })
+        .collect();
+
+    loop {
+        let shares = SettlementCalculator::allocate(amount as u128, &weights);
+        let dust = shares
+            .iter()
+            .enumerate()
+            .filter(|&(index, &share)| {
+                weights[index] > 0 && (share as u64) < config.min_position_size
+            })
+            .min_by_key(|&(index, &share)| (share, weights[index]))
+            .map(|(index, _)| index);
+
+        match dust {
+            Some(index) => weights[index] = 0,
+            None => {
+                return Ok(buckets
+                    .into_iter()
+                    .zip(shares)
+                    .filter(|&(_, share)| share > 0)
+                    .map(|(bucket, share)| (bucket, share as u64))
+                    .collect());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::fixtures;
+
+    fn market() -> Market {
+        let mut market = Market {
+            created_at: 100,
+            ..fixtures::market()
+        };
+        market.config.time_bucket_size = 900;
+        market
+    }
+
+    fn amounts(split: &[(TimeBucket, u64)]) -> Vec<u64> {
+        split.iter().map(|(_, amount)| *amount).collect()
+    }
+
+    #[test]
+    fn test_uniform_split_uses_market_buckets() {
+        let split =
+            split_range(&market(), 100, 3700, 100_000_001, RangeWeighting::Uniform).unwrap();
+
+        assert_eq!(split.len(), 4);
+        assert!(split.iter().all(|(bucket, _)| bucket.duration() == 900));
+        assert_eq!(split[3].0.end, 3700);
+        assert_eq!(
+            amounts(&split),
+            vec![25_000_001, 25_000_000, 25_000_000, 25_000_000]
+        );
+    }
+
+    #[test]
+    fn test_centered_weightings_peak_at_center() {
+        let market = market();
+        for weighting in [
+            RangeWeighting::Triangular { center: 2350 },
+            RangeWeighting::Gaussian {
+                center: 2350,
+                sigma: 1.0,
+            },
+        ] {
+            let split = split_range(&market, 100, 4600, 100_000_000, weighting).unwrap();
+            let amounts = amounts(&split);
+
+            assert_eq!(amounts.iter().sum::<u64>(), 100_000_000);
+            // Symmetric buckets differ by at most the remainder lamport
+            assert!(amounts[0].abs_diff(amounts[4]) <= 1);
+            assert!(amounts[2] > amounts[1] && amounts[1] > amounts[0]);
+        }
+    }
+
+    #[test]
+    fn test_dust_buckets_are_dropped() {
+        let weighting = RangeWeighting::Gaussian {
+            center: 2350,
+            sigma: 0.3,
+        };
+        let split = split_range(&market(), 100, 4600, 10_000_000, weighting).unwrap();
+
+        assert!(split.len() < 5);
+        assert!(split.iter().all(|&(_, amount)| amount >= 1_000_000));
+        assert_eq!(amounts(&split).iter().sum::<u64>(), 10_000_000);
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        let market = market();
+        let uniform = RangeWeighting::Uniform;
+
+        assert!(split_range(&market, 0, 3600, 10_000_000, uniform).is_err());
+        assert!(split_range(&market, 1000, 1000, 10_000_000, uniform).is_err());
+        assert!(split_range(&market, 100, 1900, 10, uniform).is_err());
+        assert!(split_range(
+            &market,
+            100,
+            1900,
+            10_000_000,
+            RangeWeighting::Triangular { center: 5000 }
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! Client-side exposure limits
+//!
+//! A `RiskGuard` checks new stakes against the user's current positions
+//! before any transaction is built, so a misbehaving strategy is stopped in
+//! the SDK rather than on chain. Every limit is optional;
+//! `RiskLimits::default()` allows everything.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{PredaError, Result, RiskLimit};
+use crate::types::position::{Position, TimeBucket};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Configurable exposure limits (lamports)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RiskLimits {
+    /// Maximum active stake in one market
+    pub max_market_stake: Option<u64>,
+
+    /// Maximum active stake across markets of one BSI domain
+    pub max_domain_stake: Option<u64>,
+
+    /// Maximum active stake across all markets
+    pub max_total_stake: Option<u64>,
+
+    /// Maximum active stake in one time bucket of a market
+    pub max_bucket_stake: Option<u64>,
+
+    /// Net realized loss today (UTC) at which placements stop
+    pub max_daily_loss: Option<u64>,
+}
+
+/// Stake about to be placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// Market to place in
+    pub market: Pubkey,
+
+    /// Target time bucket
+    pub time_bucket: TimeBucket,
+
+    /// Amount to stake
+    pub amount: u64,
+}
+
+/// Enforces `RiskLimits` on new placements
+#[derive(Debug, Clone, Default)]
+pub struct RiskGuard {
+    limits: RiskLimits,
+}
+
+impl RiskGuard {
+    /// Create a guard enforcing `limits`
+    pub fn new(limits: RiskLimits) -> Self {
+        Self { limits }
+    }
+
+    /// Limits being enforced
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    /// Check placements against the user's positions
+    ///
+    /// `positions` are all of the user's positions: active ones count toward
+    /// stake limits and ones settled today toward the daily loss. `domains`
+    /// maps markets to their BSI domain; markets missing from it are not
+    /// counted toward any domain. Placements are checked cumulatively, so a
+    /// batch fails if any prefix of it would exceed a limit.
+    pub fn check(
+        &self,
+        positions: &[Position],
+        domains: &HashMap<Pubkey, String>,
+        placements: &[Placement],
+        now: i64,
+    ) -> Result<()> {
+        if let Some(max) = self.limits.max_daily_loss {
+            let loss = Self::daily_loss(positions, now);
+            if loss >= max {
+                return Err(PredaError::RiskLimitExceeded {
+                    limit: RiskLimit::DailyLoss,
+                    value: loss,
+                    max,
+                });
+            }
+        }
+
+        let mut exposure = Exposure::default();
+        for position in positions.iter().filter(|pos| pos.is_active()) {
+            exposure.add(
+                position.market,
+                domains.get(&position.market),
+                position.time_bucket.start,
+                position.amount,
+            );
+        }
+
+        for placement in placements.iter().filter(|p| p.amount > 0) {
+            let domain = domains.get(&placement.market);
+            exposure.add(
+                placement.market,
+                domain,
+                placement.time_bucket.start,
+                placement.amount,
+            );
+
+            let market = placement.market;
+            Self::limit(
+                self.limits.max_market_stake,
+                exposure.markets[&market],
+                || RiskLimit::MarketStake(market.to_string()),
+            )?;
+            if let Some(domain) = domain {
+                Self::limit(
+                    self.limits.max_domain_stake,
+                    exposure.domains[domain],
+                    || RiskLimit::DomainStake(domain.clone()),
+                )?;
+            }
+            Self::limit(self.limits.max_total_stake, exposure.total, || {
+                RiskLimit::TotalStake
+            })?;
+            Self::limit(
+                self.limits.max_bucket_stake,
+                exposure.buckets[&(market, placement.time_bucket.start)],
+                || RiskLimit::BucketStake(placement.time_bucket.start),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Net loss of positions settled on the UTC day containing `now`
+    pub fn daily_loss(positions: &[Position], now: i64) -> u64 {
+        let day_start = now - now.rem_euclid(SECONDS_PER_DAY);
+        let pnl: i128 = positions
+            .iter()
+            .filter(|pos| {
+                pos.settled_at
+                    .is_some_and(|t| t >= day_start && t < day_start + SECONDS_PER_DAY)
+            })
+            .filter_map(|pos| pos.payout.map(|payout| payout as i128 - pos.amount as i128))
+            .sum();
+        (-pnl).clamp(0, u64::MAX as i128) as u64
+    }
+
+    fn limit(max: Option<u64>, value: u64, limit: impl FnOnce() -> RiskLimit) -> Result<()> {
+        match max {
+            Some(max) if value > max => Err(PredaError::RiskLimitExceeded {
+                limit: limit(),
+                value,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Active stake grouped by each limit's key
+#[derive(Default)]
+struct Exposure {
+    markets: HashMap<Pubkey, u64>,
+    domains: HashMap<String, u64>,
+    buckets: HashMap<(Pubkey, i64), u64>,
+    total: u64,
+}
+
+impl Exposure {
+    fn add(&mut self, market: Pubkey, domain: Option<&String>, bucket_start: i64, amount: u64) {
+        let market_stake = self.markets.entry(market).or_default();
+        *market_stake = market_stake.saturating_add(amount);
+        if let Some(domain) = domain {
+            let domain_stake = self.domains.entry(domain.clone()).or_default();
+            *domain_stake = domain_stake.saturating_add(amount);
+        }
+        let bucket_stake = self.buckets.entry((market, bucket_start)).or_default();
+        *bucket_stake = bucket_stake.saturating_add(amount);
+        self.total = self.total.saturating_add(amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::fixtures;
+    use crate::types::position::PositionStatus;
+
+    const NOW: i64 = 10 * SECONDS_PER_DAY + 3600;
+
+    fn position(market: Pubkey, bucket_start: i64, amount: u64) -> Position {
+        Position {
+            market,
+            ..fixtures::position(bucket_start, amount)
+        }
+    }
+
+    fn placement(market: Pubkey, bucket_start: i64, amount: u64) -> Placement {
+        Placement {
+            market,
+            time_bucket: TimeBucket::from_duration(bucket_start, 3600),
+            amount,
+        }
+    }
+
+    fn exceeded(result: Result<()>) -> RiskLimit {
+        match result {
+            Err(PredaError::RiskLimitExceeded { limit, .. }) => limit,
+            other => panic!("expected risk limit error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stake_limits() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let domains = HashMap::from([(a, "BTC".to_string()), (b, "BTC".to_string())]);
+        let positions = vec![position(a, 0, 600), position(b, 0, 300)];
+        let guard = RiskGuard::new(RiskLimits {
+            max_market_stake: Some(1_000),
+            max_domain_stake: Some(1_400),
+            max_total_stake: Some(1_500),
+            max_bucket_stake: Some(700),
+            max_daily_loss: None,
+        });
+
+        let check = |placements: &[Placement]| guard.check(&positions, &domains, placements, NOW);
+        assert!(check(&[placement(a, 3600, 400)]).is_ok());
+        assert_eq!(
+            exceeded(check(&[placement(a, 0, 200)])),
+            RiskLimit::BucketStake(0)
+        );
+        assert_eq!(
+            exceeded(check(&[placement(a, 3600, 401)])),
+            RiskLimit::MarketStake(a.to_string())
+        );
+        assert_eq!(
+            exceeded(check(&[placement(b, 3600, 300), placement(b, 7200, 300)])),
+            RiskLimit::DomainStake("BTC".to_string())
+        );
+
+        let other = Pubkey::new_unique();
+        assert_eq!(
+            exceeded(check(&[placement(other, 0, 601)])),
+            RiskLimit::TotalStake
+        );
+    }
+
+    #[test]
+    fn test_huge_stakes_saturate() {
+        let market = Pubkey::new_unique();
+        let positions = vec![position(market, 0, u64::MAX), position(market, 0, u64::MAX)];
+        let guard = RiskGuard::new(RiskLimits {
+            max_total_stake: Some(u64::MAX - 1),
+            ..RiskLimits::default()
+        });
+
+        let result = guard.check(&positions, &HashMap::new(), &[placement(market, 0, 1)], NOW);
+        assert_eq!(exceeded(result), RiskLimit::TotalStake);
+    }
+
+    #[test]
+    fn test_daily_loss_limit() {
+        let market = Pubkey::new_unique();
+        let settled = |settled_at: i64, amount: u64, payout: u64| Position {
+            status: PositionStatus::Lost,
+            settled_at: Some(settled_at),
+            payout: Some(payout),
+            ..position(market, 0, amount)
+        };
+        let positions = vec![
+            settled(NOW - 60, 1_000, 0),
+            settled(NOW - 30, 500, 800),
+            // Settled yesterday
+            settled(NOW - SECONDS_PER_DAY, 5_000, 0),
+        ];
+        assert_eq!(RiskGuard::daily_loss(&positions, NOW), 700);
+
+        let guard = RiskGuard::new(RiskLimits {
+            max_daily_loss: Some(700),
+            ..RiskLimits::default()
+        });
+        let placements = [placement(market, 3600, 1)];
+        let result = guard.check(&positions, &HashMap::new(), &placements, NOW);
+        assert_eq!(exceeded(result), RiskLimit::DailyLoss);
+
+        // A new UTC day starts from zero
+        let tomorrow = NOW + SECONDS_PER_DAY;
+        assert!(guard
+            .check(&positions, &HashMap::new(), &placements, tomorrow)
+            .is_ok());
+    }
+}
@@ -0,0 +1,202 @@
+//! Proper scoring rules over time-bucket stake distributions
+//!
+//! Each owner's stake across time buckets is read as a probability
+//! distribution over the inflection time and scored against the bucket that
+//! contains the inflection. Scores are normalized to [0, 1] and turned into
+//! payouts with a weighted-score wagering mechanism: an owner with stake `m`
+//! and score `s` is weighted `m * (1 + s - s̄)`, where `s̄` is the
+//! stake-weighted mean score. Weights sum to the total stake, so the pool is
+//! budget-balanced and truthful reporting maximizes expected payout.
+
+use std::collections::BTreeMap;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::types::{
+    market::SettlementCurve,
+    position::{Position, TimeBucket},
+};
+
+/// Smallest probability the logarithmic rule distinguishes
+///
+/// Bounds the log score so it can be normalized to [0, 1].
+pub const LOG_SCORE_FLOOR: f64 = 1e-4;
+
+/// Proper scoring rule applied to a stake distribution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoringRule {
+    /// Logarithmic score `ln p(b*)`
+    Logarithmic,
+
+    /// Quadratic (Brier) score `2 p(b*) - Σ p(b)²`
+    Quadratic,
+}
+
+impl ScoringRule {
+    /// Scoring rule used by a settlement curve, if it is a scoring curve
+    pub fn for_curve(curve: SettlementCurve) -> Option<Self> {
+        match curve {
+            SettlementCurve::LogScore => Some(ScoringRule::Logarithmic),
+            SettlementCurve::QuadraticScore => Some(ScoringRule::Quadratic),
+            _ => None,
+        }
+    }
+
+    /// Normalized score in [0, 1] of a report against the inflection time
+    ///
+    /// `report` holds the probability assigned to each bucket and should sum to 1.
+    pub fn score(&self, report: &[(TimeBucket, f64)], inflection_time: i64) -> f64 {
+        let realized: f64 = report
+            .iter()
+            .filter(|(bucket, _)| bucket.contains(inflection_time))
+            .map(|(_, probability)| probability)
+            .sum();
+
+        match self {
+            ScoringRule::Logarithmic => {
+                let floor = LOG_SCORE_FLOOR.ln();
+                1.0 - realized.max(LOG_SCORE_FLOOR).ln() / floor
+            }
+            ScoringRule::Quadratic => {
+                let sum_squares: f64 = report.iter().map(|(_, p)| p * p).sum();
+                ((1.0 + 2.0 * realized - sum_squares) / 2.0).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+/// Probability distribution implied by stakes across buckets
+///
+/// Stakes in the same bucket are combined; buckets appear in first-seen order.
+pub fn stake_distribution<'a>(
+    positions: impl IntoIterator<Item = &'a Position>,
+) -> Vec<(TimeBucket, f64)> {
+    let mut stakes: Vec<(TimeBucket, u64)> = Vec::new();
+    for position in positions {
+        match stakes
+            .iter_mut()
+            .find(|(bucket, _)| *bucket == position.time_bucket)
+        {
+            Some((_, stake)) => *stake += position.amount,
+            None => stakes.push((position.time_bucket, position.amount)),
+        }
+    }
+
+    let total: u128 = stakes.iter().map(|(_, stake)| *stake as u128).sum();
+    if total == 0 {
+        return vec![];
+    }
+
+    stakes
+        .into_iter()
+        .map(|(bucket, stake)| (bucket, stake as f64 / total as f64))
+        .collect()
+}
+
+/// Payout multiplier of each position, in input order
+///
+/// Positions are grouped by owner; every position of an owner gets
+/// `1 + s - s̄`, which lies in [0, 2].
+pub fn multipliers(rule: ScoringRule, positions: &[Position], inflection_time: i64) -> Vec<f64> {
+    let mut owners: BTreeMap<Pubkey, Vec<&Position>> = BTreeMap::new();
+    for position in positions {
+        owners.entry(position.owner).or_default().push(position);
+    }
+
+    let scores: BTreeMap<Pubkey, (f64, f64)> = owners
+        .iter()
+        .map(|(owner, positions)| {
+            let stake: f64 = positions.iter().map(|pos| pos.amount as f64).sum();
+            let report = stake_distribution(positions.iter().copied());
+            (*owner, (stake, rule.score(&report, inflection_time)))
+        })
+        .collect();
+
+    let total_stake: f64 = scores.values().map(|(stake, _)| stake).sum();
+    let mean_score = if total_stake > 0.0 {
+        scores
+            .values()
+            .map(|(stake, score)| stake * score)
+            .sum::<f64>()
+            / total_stake
+    } else {
+        0.0
+    };
+
+    positions
+        .iter()
+        .map(|pos| (1.0 + scores[&pos.owner].1 - mean_score).max(0.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::fixtures;
+
+    fn position(owner: Pubkey, start: i64, amount: u64) -> Position {
+        Position {
+            owner,
+            ..fixtures::position(start, amount)
+        }
+    }
+
+    #[test]
+    fn test_scores_are_proper() {
+        // Belief: 70% the inflection lands in the first bucket, 30% in the second
+        let (first, second) = (
+            TimeBucket::from_duration(0, 3600),
+            TimeBucket::from_duration(3600, 3600),
+        );
+        let belief = 0.7;
+
+        for rule in [ScoringRule::Logarithmic, ScoringRule::Quadratic] {
+            let expected = |reported: f64| {
+                let report = [(first, reported), (second, 1.0 - reported)];
+                belief * rule.score(&report, 100) + (1.0 - belief) * rule.score(&report, 4000)
+            };
+
+            let truthful = expected(belief);
+            for step in 1..20 {
+                let reported = step as f64 / 20.0;
+                assert!(
+                    expected(reported) <= truthful + 1e-12,
+                    "{:?} rewards reporting {} over {}",
+                    rule,
+                    reported,
+                    belief
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_score_bounds() {
+        let bucket = TimeBucket::from_duration(0, 3600);
+        for rule in [ScoringRule::Logarithmic, ScoringRule::Quadratic] {
+            assert_eq!(rule.score(&[(bucket, 1.0)], 100), 1.0);
+            assert_eq!(rule.score(&[(bucket, 1.0)], 4000), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_multipliers_are_budget_balanced() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let positions = vec![
+            position(alice, 0, 600),
+            position(alice, 3600, 400),
+            position(bob, 3600, 2000),
+        ];
+
+        let multipliers = multipliers(ScoringRule::Quadratic, &positions, 100);
+        let weighted: f64 = positions
+            .iter()
+            .zip(&multipliers)
+            .map(|(pos, m)| pos.amount as f64 * m)
+            .sum();
+
+        assert!((weighted - 3000.0).abs() < 1e-6);
+        assert_eq!(multipliers[0], multipliers[1]);
+        assert!(multipliers[0] > 1.0 && multipliers[2] < 1.0);
+    }
+}
@@ -0,0 +1,203 @@
+//! Position transfers and secondary-market listings
+//!
+//! Builders for the program instructions that move a position to a new
+//! owner. A listing offers a position at a fixed price; buying it pays the
+//! seller and transfers the position in the same instruction, so either both
+//! happen or neither does.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::error::Result;
+
+/// Seed prefix of listing PDAs
+pub const LISTING_SEED: &[u8] = b"listing";
+
+/// Program instructions for transferring and trading positions
+#[derive(Debug, Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub enum PositionInstruction {
+    /// Give a position to `new_owner`
+    TransferPosition { new_owner: Pubkey },
+
+    /// Offer a position for sale at `price` lamports
+    ListPosition { price: u64, expires_at: Option<i64> },
+
+    /// Withdraw a listing
+    CancelListing,
+
+    /// Buy a listed position, failing if its price exceeds `max_price`
+    BuyListing { max_price: u64 },
+}
+
+/// Address of the listing for a position
+pub fn listing_address(program_id: &Pubkey, position: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LISTING_SEED, position.as_ref()], program_id)
+}
+
+/// Build a `TransferPosition` instruction
+///
+/// Accounts: `[signer]` owner, `[writable]` position, `[]` new owner, `[]`
+/// listing. The listing PDA lets the program refuse positions that are
+/// listed for sale.
+pub fn transfer_position(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    position: &Pubkey,
+    new_owner: &Pubkey,
+) -> Result<Instruction> {
+    let (listing, _) = listing_address(program_id, position);
+    instruction(
+        program_id,
+        PositionInstruction::TransferPosition {
+            new_owner: *new_owner,
+        },
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*position, false),
+            AccountMeta::new_readonly(*new_owner, false),
+            AccountMeta::new_readonly(listing, false),
+        ],
+    )
+}
+
+/// Build a `ListPosition` instruction
+///
+/// Accounts: `[signer, writable]` seller, `[]` position, `[writable]` listing,
+/// `[]` system program.
+pub fn list_position(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    position: &Pubkey,
+    price: u64,
+    expires_at: Option<i64>,
+) -> Result<Instruction> {
+    let (listing, _) = listing_address(program_id, position);
+    instruction(
+        program_id,
+        PositionInstruction::ListPosition { price, expires_at },
+        vec![
+            AccountMeta::new(*seller, true),
+            AccountMeta::new_readonly(*position, false),
+            AccountMeta::new(listing, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Build a `CancelListing` instruction
+///
+/// Accounts: `[signer, writable]` seller, `[writable]` listing.
+pub fn cancel_listing(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    position: &Pubkey,
+) -> Result<Instruction> {
+    let (listing, _) = listing_address(program_id, position);
+    instruction(
+        program_id,
+        PositionInstruction::CancelListing,
+        vec![
+            AccountMeta::new(*seller, true),
+            AccountMeta::new(listing, false),
+        ],
+    )
+}
+
+/// Build a `BuyListing` instruction
+///
+/// Accounts: `[signer, writable]` buyer, `[writable]` seller, `[writable]`
+/// position, `[writable]` listing, `[]` system program.
+pub fn buy_listing(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    seller: &Pubkey,
+    position: &Pubkey,
+    max_price: u64,
+) -> Result<Instruction> {
+    let (listing, _) = listing_address(program_id, position);
+    instruction(
+        program_id,
+        PositionInstruction::BuyListing { max_price },
+        vec![
+            AccountMeta::new(*buyer, true),
+            AccountMeta::new(*seller, false),
+            AccountMeta::new(*position, false),
+            AccountMeta::new(listing, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+fn instruction(
+    program_id: &Pubkey,
+    data: PositionInstruction,
+    accounts: Vec<AccountMeta>,
+) -> Result<Instruction> {
+    Ok(Instruction::new_with_bytes(
+        *program_id,
+        &data.try_to_vec()?,
+        accounts,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_data_round_trips() {
+        let program_id = Pubkey::new_unique();
+        let buyer = Pubkey::new_unique();
+        let seller = Pubkey::new_unique();
+        let position = Pubkey::new_unique();
+
+        let ix = buy_listing(&program_id, &buyer, &seller, &position, 42).unwrap();
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(
+            PositionInstruction::try_from_slice(&ix.data).unwrap(),
+            PositionInstruction::BuyListing { max_price: 42 }
+        );
+        assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_signer);
+        assert_eq!(
+            ix.accounts[3].pubkey,
+            listing_address(&program_id, &position).0
+        );
+    }
+
+    #[test]
+    fn test_transfer_passes_listing() {
+        let program_id = Pubkey::new_unique();
+        let position = Pubkey::new_unique();
+
+        let ix = transfer_position(
+            &program_id,
+            &Pubkey::new_unique(),
+            &position,
+            &Pubkey::new_unique(),
+        )
+        .unwrap();
+        let listing = &ix.accounts[3];
+        assert_eq!(listing.pubkey, listing_address(&program_id, &position).0);
+        assert!(!listing.is_writable && !listing.is_signer);
+    }
+
+    #[test]
+    fn test_listing_address_is_per_position() {
+        let program_id = Pubkey::new_unique();
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+
+        assert_eq!(
+            listing_address(&program_id, &a),
+            listing_address(&program_id, &a)
+        );
+        assert_ne!(
+            listing_address(&program_id, &a).0,
+            listing_address(&program_id, &b).0
+        );
+    }
+}
@@ -1,8 +1,10 @@
 //! Volatility-aware settlement logic
 
+use super::scoring::{self, ScoringRule};
+use crate::error::{PredaError, Result};
 use crate::types::{
-    market::{Market, SettlementCurve},
-    position::{Position, TimeBucket},
+    market::{CurveParameters, Market, MarketConfig, MarketState, RefundPolicy, SettlementCurve},
+    position::{Position, PositionStatus},
     belief::BeliefInflection,
 };
 
@@ -17,107 +19,1143 @@ impl SettlementCalculator {
         inflection: &BeliefInflection,
     ) -> u64 {
         let distance = position.time_bucket.distance_from(inflection.timestamp);
-        
-        let payout_multiplier = match market.config.settlement_curve {
-            SettlementCurve::Linear => Self::linear_payout(distance, inflection.sharpness),
-            SettlementCurve::Exponential => Self::exponential_payout(distance, inflection.sharpness),
-            SettlementCurve::Gaussian => Self::gaussian_payout(distance, inflection.sharpness),
-            SettlementCurve::Custom => Self::custom_payout(distance, inflection.sharpness),
-        };
+        let payout_multiplier = Self::multiplier(&market.config, distance, inflection.sharpness);
 
-        let base_payout = position.amount as f64 * payout_multiplier;
+        let base_payout = position.net_stake(&market.config.fees) as f64 * payout_multiplier;
         let volatility_adjusted = base_payout * market.config.volatility_factor;
         
         volatility_adjusted as u64
     }
 
-    /// Linear payout curve
-    fn linear_payout(distance: i64, sharpness: f64) -> f64 {
-        if distance == 0 {
-            return 2.0; // 2x payout for exact match
+    /// Payout multiplier of the market's curve at a distance (seconds) from the inflection
+    ///
+    /// Distance is measured in time buckets, so markets with different bucket
+    /// sizes pay the same for the same relative accuracy. Scoring curves
+    /// depend on every position in the market; here they give the score of a
+    /// stake placed entirely in one bucket (1 inside it, 0 elsewhere).
+    pub fn multiplier(config: &MarketConfig, distance: i64, sharpness: f64) -> f64 {
+        let params = &config.curve_parameters;
+        let distance = distance.unsigned_abs() as f64 / config.time_bucket_size.max(1) as f64;
+
+        match config.settlement_curve {
+            SettlementCurve::Linear => Self::linear_payout(distance, sharpness, params),
+            SettlementCurve::Exponential => Self::exponential_payout(distance, sharpness, params),
+            SettlementCurve::Gaussian => Self::gaussian_payout(distance, sharpness, params),
+            SettlementCurve::Custom => Self::custom_payout(distance, sharpness, params),
+            SettlementCurve::PiecewiseLinear => {
+                Self::piecewise_linear_payout(distance, sharpness, params)
+            }
+            SettlementCurve::LogScore | SettlementCurve::QuadraticScore => {
+                if distance == 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
         }
-        
-        let decay_rate = 0.0001;
-        let multiplier = 2.0 - (distance.abs() as f64 * decay_rate);
-        multiplier.max(0.0) * (1.0 + sharpness * 0.5)
+    }
+
+    /// Payout multiplier of each position, in input order
+    ///
+    /// Scoring curves score each owner's stake distribution across the given
+    /// positions; other curves use `multiplier` on each position's distance.
+    pub fn multipliers(
+        market: &Market,
+        positions: &[Position],
+        inflection: &BeliefInflection,
+    ) -> Vec<f64> {
+        match ScoringRule::for_curve(market.config.settlement_curve) {
+            Some(rule) => scoring::multipliers(rule, positions, inflection.timestamp),
+            None => positions
+                .iter()
+                .map(|pos| {
+                    let distance = pos.time_bucket.distance_from(inflection.timestamp);
+                    Self::multiplier(&market.config, distance, inflection.sharpness)
+                })
+                .collect(),
+        }
+    }
+
+    /// Evaluate the market's curve at each distance, e.g. for plotting
+    pub fn sample_curve(
+        config: &MarketConfig,
+        sharpness: f64,
+        distances: &[i64],
+    ) -> Vec<(i64, f64)> {
+        distances
+            .iter()
+            .map(|&distance| (distance, Self::multiplier(config, distance, sharpness)))
+            .collect()
+    }
+
+    /// Evaluate the market's curve from `-max_distance` to `max_distance` every `step` seconds
+    pub fn sample_range(
+        config: &MarketConfig,
+        sharpness: f64,
+        max_distance: u64,
+        step: u64,
+    ) -> Vec<(i64, f64)> {
+        let max_distance = max_distance.min(i64::MAX as u64) as i64;
+        let step = step.clamp(1, i64::MAX as u64) as i64;
+
+        let mut distances = Vec::new();
+        let mut distance = -max_distance;
+        while distance <= max_distance {
+            distances.push(distance);
+            distance = match distance.checked_add(step) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        Self::sample_curve(config, sharpness, &distances)
+    }
+
+    /// Multiplier boost for sharp inflections
+    fn sharpness_boost(sharpness: f64) -> f64 {
+        1.0 + sharpness * 0.5
+    }
+
+    /// Linear payout curve
+    fn linear_payout(distance: f64, sharpness: f64, params: &CurveParameters) -> f64 {
+        let multiplier = 2.0 - (distance * params.linear_decay_rate);
+        multiplier.max(0.0) * Self::sharpness_boost(sharpness)
     }
 
     /// Exponential decay payout curve
-    fn exponential_payout(distance: i64, sharpness: f64) -> f64 {
-        let decay_constant = 0.001;
-        let multiplier = 2.0 * (-decay_constant * distance.abs() as f64).exp();
-        multiplier * (1.0 + sharpness * 0.5)
+    fn exponential_payout(distance: f64, sharpness: f64, params: &CurveParameters) -> f64 {
+        let multiplier = 2.0 * (-params.exponential_decay * distance).exp();
+        multiplier * Self::sharpness_boost(sharpness)
     }
 
     /// Gaussian distribution payout curve
-    fn gaussian_payout(distance: i64, sharpness: f64) -> f64 {
-        let sigma: f64 = 3600.0; // 1 hour standard deviation
-        let exponent = -(distance as f64).powi(2) / (2.0 * sigma.powi(2));
+    fn gaussian_payout(distance: f64, sharpness: f64, params: &CurveParameters) -> f64 {
+        let exponent = -distance.powi(2) / (2.0 * params.sigma.powi(2));
         let multiplier = 2.0 * exponent.exp();
-        multiplier * (1.0 + sharpness * 0.5)
+        multiplier * Self::sharpness_boost(sharpness)
+    }
+
+    /// Custom payout curve: full payout within the plateau, Gaussian tail beyond it
+    fn custom_payout(distance: f64, sharpness: f64, params: &CurveParameters) -> f64 {
+        Self::gaussian_payout((distance - params.plateau_width).max(0.0), sharpness, params)
     }
 
-    /// Custom payout curve
-    fn custom_payout(distance: i64, sharpness: f64) -> f64 {
-        // Placeholder for custom curves
-        Self::gaussian_payout(distance, sharpness)
+    /// Piecewise-linear payout curve, flat beyond the first and last points
+    fn piecewise_linear_payout(distance: f64, sharpness: f64, params: &CurveParameters) -> f64 {
+        let points = &params.points;
+        let multiplier = match points.iter().position(|point| point.distance >= distance) {
+            None => points.last().map_or(0.0, |point| point.multiplier),
+            Some(0) => points[0].multiplier,
+            Some(i) => {
+                let (a, b) = (points[i - 1], points[i]);
+                let t = (distance - a.distance) / (b.distance - a.distance);
+                a.multiplier + (b.multiplier - a.multiplier) * t
+            }
+        };
+
+        multiplier * Self::sharpness_boost(sharpness)
+    }
+
+    /// Fixed-point weight of a position: net stake times its quantized curve multiplier
+    pub fn payout_weight(
+        market: &Market,
+        position: &Position,
+        inflection: &BeliefInflection,
+    ) -> u128 {
+        let distance = position.time_bucket.distance_from(inflection.timestamp);
+        let multiplier = Self::multiplier(&market.config, distance, inflection.sharpness);
+        Self::weight(position.net_stake(&market.config.fees), multiplier)
     }
 
-    /// Calculate total pool payout
+    /// Fixed-point weight of a stake at a curve multiplier
+    fn weight(amount: u64, multiplier: f64) -> u128 {
+        // NaN and negative multipliers carry no weight
+        let scaled = (multiplier.max(0.0) * WEIGHT_SCALE as f64).round();
+        amount as u128 * scaled as u128
+    }
+
+    /// Distribute the market's pool among winning positions
+    ///
+    /// The pool is `total_value_locked`, which holds net stakes only. The
+    /// settlement fee is deducted first, the rest is split in proportion to
+    /// net stake times each position's entry in `multipliers` using integer
+    /// arithmetic, and the
+    /// lamports left after flooring go one each to the largest remainders
+    /// (ties favour earlier positions). Payouts, fee and `unallocated` always
+    /// sum to `total_value_locked`.
     pub fn calculate_pool_distribution(
         market: &Market,
         winning_positions: &[Position],
         inflection: &BeliefInflection,
-    ) -> Vec<(Position, u64)> {
+    ) -> PoolDistribution {
         let total_pool = market.total_value_locked;
-        
-        // Calculate individual payouts
-        let payouts: Vec<(Position, u64)> = winning_positions
+        let fee = market.config.fees.settlement_fee(total_pool);
+        let distributable = (total_pool - fee) as u128;
+
+        let weights: Vec<u128> = winning_positions
             .iter()
-            .map(|pos| {
-                let payout = Self::calculate_payout(market, pos, inflection);
-                (pos.clone(), payout)
+            .zip(Self::multipliers(market, winning_positions, inflection))
+            .map(|(pos, multiplier)| {
+                Self::weight(pos.net_stake(&market.config.fees), multiplier)
             })
             .collect();
+        let amounts = Self::allocate(distributable, &weights);
 
-        // Normalize to total pool
-        let total_payout: u64 = payouts.iter().map(|(_, p)| p).sum();
-        
-        if total_payout == 0 {
-            return payouts;
+        let allocated: u128 = amounts.iter().sum();
+        let payouts = winning_positions
+            .iter()
+            .cloned()
+            .zip(amounts.into_iter().map(|amount| amount as u64))
+            .collect();
+
+        PoolDistribution {
+            payouts,
+            fee,
+            unallocated: (distributable - allocated) as u64,
         }
+    }
 
-        payouts
-            .into_iter()
-            .map(|(pos, payout)| {
-                let normalized = (payout as f64 / total_payout as f64 * total_pool as f64) as u64;
-                (pos, normalized)
+    /// Classify a position by its curve multiplier
+    pub fn classify(config: &MarketConfig, multiplier: f64) -> PositionStatus {
+        let thresholds = &config.settlement_thresholds;
+        if multiplier >= thresholds.win_multiplier {
+            PositionStatus::Won
+        } else if multiplier >= thresholds.partial_multiplier {
+            PositionStatus::PartialWin
+        } else {
+            PositionStatus::Lost
+        }
+    }
+
+    /// Settle every active position of a market against its inflection
+    ///
+    /// Positions are classified with `MarketConfig::settlement_thresholds`,
+    /// the pool is distributed among `Won` and `PartialWin` positions with
+    /// `calculate_pool_distribution`, and each settled position gets its
+    /// `payout` (zero when lost) and `settled_at`. Scoring curves distribute
+    /// among every settled position instead, keeping the wagering mechanism
+    /// budget-balanced. Positions of other markets or that are no longer
+    /// active are left out of the report.
+    pub fn settle_market(
+        market: &Market,
+        positions: &[Position],
+        inflection: &BeliefInflection,
+    ) -> SettlementReport {
+        let settled_at = market.resolved_at.unwrap_or(inflection.timestamp);
+
+        let mut settled: Vec<Position> = positions
+            .iter()
+            .filter(|pos| pos.market == market.address && pos.is_active())
+            .cloned()
+            .collect();
+        let multipliers = Self::multipliers(market, &settled, inflection);
+        for (position, multiplier) in settled.iter_mut().zip(multipliers) {
+            position.status = Self::classify(&market.config, multiplier);
+            position.settled_at = Some(settled_at);
+            position.payout = Some(0);
+        }
+
+        let pays_all = market.config.settlement_curve.is_scoring_rule();
+        let recipients: Vec<Position> = settled
+            .iter()
+            .filter(|pos| pays_all || pos.is_winner())
+            .cloned()
+            .collect();
+        let distribution = Self::calculate_pool_distribution(market, &recipients, inflection);
+
+        let mut payouts = distribution.payouts.iter().map(|(_, payout)| *payout);
+        for position in settled.iter_mut().filter(|pos| pays_all || pos.is_winner()) {
+            position.payout = payouts.next();
+        }
+
+        SettlementReport {
+            total_staked: settled.iter().map(|pos| pos.amount).sum(),
+            total_paid: distribution.total_paid(),
+            fee: distribution.fee,
+            unallocated: distribution.unallocated,
+            maker_loss: 0,
+            settled_at,
+            inflections: vec![inflection.timestamp],
+            positions: settled,
+        }
+    }
+
+    /// Settle a market against the inflections chosen by its `ResolutionMode`
+    ///
+    /// Only validated inflections count, taken in chronological order. The
+    /// pool is split into equal shares (remainders to the earliest), each
+    /// share is settled with `settle_market`, and each position's payout is
+    /// the sum across inflections. A position's status is the best it reached.
+    /// Returns `None` when the mode selects no inflection.
+    pub fn settle_inflections(
+        market: &Market,
+        positions: &[Position],
+        inflections: &[BeliefInflection],
+    ) -> Option<SettlementReport> {
+        let mut validated: Vec<BeliefInflection> = inflections
+            .iter()
+            .filter(|inflection| inflection.validated)
+            .cloned()
+            .collect();
+        validated.sort_by_key(|inflection| inflection.timestamp);
+
+        let selected = market.config.resolution_mode.select(&validated);
+        let shares = Self::allocate(
+            market.total_value_locked as u128,
+            &vec![1; selected.len()],
+        );
+
+        let mut combined: Option<SettlementReport> = None;
+        for (inflection, share) in selected.iter().zip(shares) {
+            let mut partial = market.clone();
+            partial.total_value_locked = share as u64;
+            let report = Self::settle_market(&partial, positions, inflection);
+
+            combined = Some(match combined {
+                None => report,
+                Some(mut total) => {
+                    for (position, settled) in total.positions.iter_mut().zip(&report.positions) {
+                        position.payout = Some(
+                            position.payout.unwrap_or(0) + settled.payout.unwrap_or(0),
+                        );
+                        position.settled_at = settled.settled_at;
+                        if Self::status_rank(settled.status) > Self::status_rank(position.status) {
+                            position.status = settled.status;
+                        }
+                    }
+                    total.total_paid += report.total_paid;
+                    total.fee += report.fee;
+                    total.unallocated += report.unallocated;
+                    total.maker_loss += report.maker_loss;
+                    total.settled_at = report.settled_at;
+                    total.inflections.extend(report.inflections);
+                    total
+                }
+            });
+        }
+
+        combined
+    }
+
+    /// Settle a `PricingMode::Lmsr` market, where positions hold bucket shares
+    ///
+    /// `holdings` pairs each position with its shares; the position's `amount`
+    /// is what the shares cost. Shares in the bucket containing the inflection
+    /// pay 1 lamport each less the settlement fee, and every other position
+    /// loses. Lamports paid for shares that did not win stay with the market
+    /// maker and are reported as unallocated; when the winning shares owe
+    /// more than was paid in, the market maker covers the difference and it
+    /// is reported as `maker_loss`.
+    pub fn settle_shares(
+        market: &Market,
+        holdings: &[(Position, u64)],
+        inflection: &BeliefInflection,
+    ) -> SettlementReport {
+        let settled_at = market.resolved_at.unwrap_or(inflection.timestamp);
+        let fees = &market.config.fees;
+
+        let mut fee = 0;
+        let positions: Vec<Position> = holdings
+            .iter()
+            .filter(|(pos, _)| pos.market == market.address && pos.is_active())
+            .map(|(position, shares)| {
+                let won = *shares > 0 && position.time_bucket.contains(inflection.timestamp);
+                let gross = if won { *shares } else { 0 };
+                fee += fees.settlement_fee(gross);
+
+                Position {
+                    status: if won { PositionStatus::Won } else { PositionStatus::Lost },
+                    settled_at: Some(settled_at),
+                    payout: Some(gross - fees.settlement_fee(gross)),
+                    ..position.clone()
+                }
             })
-            .collect()
+            .collect();
+
+        let total_staked: u64 = positions.iter().map(|pos| pos.amount).sum();
+        let total_paid: u64 = positions.iter().map(|pos| pos.payout.unwrap_or(0)).sum();
+        SettlementReport {
+            total_staked,
+            total_paid,
+            fee,
+            unallocated: total_staked.saturating_sub(total_paid + fee),
+            maker_loss: (total_paid + fee).saturating_sub(total_staked),
+            settled_at,
+            inflections: vec![inflection.timestamp],
+            positions,
+        }
+    }
+
+    /// Ordering of settled statuses from worst to best
+    fn status_rank(status: PositionStatus) -> u8 {
+        match status {
+            PositionStatus::Won => 2,
+            PositionStatus::PartialWin => 1,
+            _ => 0,
+        }
+    }
+
+    /// Split `total` in proportion to `weights` by largest remainder
+    ///
+    /// Returns all zeros when no weight is positive.
+    pub(crate) fn allocate(total: u128, weights: &[u128]) -> Vec<u128> {
+        let total_weight = weights
+            .iter()
+            .fold(0u128, |acc, &weight| acc.saturating_add(weight));
+        if total_weight == 0 {
+            return vec![0; weights.len()];
+        }
+
+        // Keep weights below 2^64 so `total * weight` cannot overflow
+        let shift = (128 - total_weight.leading_zeros()).saturating_sub(64);
+        let weights: Vec<u128> = weights.iter().map(|weight| weight >> shift).collect();
+        let total_weight: u128 = weights.iter().sum();
+        if total_weight == 0 {
+            return vec![0; weights.len()];
+        }
+
+        let mut shares = Vec::with_capacity(weights.len());
+        let mut remainders = Vec::with_capacity(weights.len());
+        for (index, weight) in weights.iter().enumerate() {
+            let product = total * weight;
+            shares.push(product / total_weight);
+            remainders.push((product % total_weight, index));
+        }
+
+        let leftover = total - shares.iter().sum::<u128>();
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for &(_, index) in remainders.iter().take(leftover as usize) {
+            shares[index] += 1;
+        }
+
+        shares
+    }
+
+    /// Amount a position of a cancelled or expired market can recover
+    ///
+    /// See [`Self::refund_split`] for where it comes from.
+    pub fn refund_amount(market: &Market, position: &Position) -> Result<u64> {
+        let (stake, fee) = Self::refund_split(market, position)?;
+        Ok(stake + fee)
+    }
+
+    /// Split a refund into the net stake, which leaves the pool, and the
+    /// placement fee returned from the market's collected fees
+    ///
+    /// The fee is only returned under `RefundPolicy::RefundFees`; it is zero
+    /// under `RefundPolicy::RetainFees`.
+    pub fn refund_split(market: &Market, position: &Position) -> Result<(u64, u64)> {
+        if !market.is_refundable() {
+            return Err(PredaError::InvalidMarketState {
+                expected: "Cancelled or Expired".to_string(),
+                actual: format!("{:?}", market.state),
+            });
+        }
+
+        if position.market != market.address {
+            return Err(PredaError::NotRefundable(format!(
+                "position {} belongs to another market",
+                position.address
+            )));
+        }
+
+        if !position.is_active() {
+            return Err(PredaError::NotRefundable(format!(
+                "position {} is {:?}",
+                position.address, position.status
+            )));
+        }
+
+        let stake = position.net_stake(&market.config.fees);
+        Ok(match market.config.refund_policy {
+            RefundPolicy::RefundFees => (stake, position.amount - stake),
+            RefundPolicy::RetainFees => (stake, 0),
+        })
+    }
+
+    /// Refund the active positions of a cancelled or expired market
+    ///
+    /// Refunded positions are marked `Withdrawn` when the market was cancelled
+    /// and `Expired` when it expired. Positions that are no longer active are
+    /// skipped.
+    pub fn calculate_refunds(
+        market: &Market,
+        positions: &[Position],
+        settled_at: i64,
+    ) -> Result<RefundDistribution> {
+        let status = match market.state {
+            MarketState::Cancelled => PositionStatus::Withdrawn,
+            _ => PositionStatus::Expired,
+        };
+
+        let mut refunds = Vec::new();
+        let mut retained_fees = 0;
+        let mut returned_fees = 0;
+        for position in positions
+            .iter()
+            .filter(|pos| pos.market == market.address && pos.is_active())
+        {
+            let (stake, fee) = Self::refund_split(market, position)?;
+            let refund = stake + fee;
+            retained_fees += position.amount - refund;
+            returned_fees += fee;
+
+            let mut position = position.clone();
+            position.status = status;
+            position.settled_at = Some(settled_at);
+            position.payout = Some(refund);
+            refunds.push((position, refund));
+        }
+
+        Ok(RefundDistribution {
+            refunds,
+            retained_fees,
+            returned_fees,
+        })
+    }
+}
+
+/// Outcome of settling a market
+#[derive(Debug, Clone)]
+pub struct SettlementReport {
+    /// Settled positions with status, payout and settlement time filled in
+    pub positions: Vec<Position>,
+
+    /// Total staked by the settled positions
+    pub total_staked: u64,
+
+    /// Total paid out to positions
+    pub total_paid: u64,
+
+    /// Protocol fee deducted from the pool
+    pub fee: u64,
+
+    /// Pool left unallocated because no position won
+    pub unallocated: u64,
+
+    /// Paid out beyond the stakes by an LMSR market maker; zero for pooled markets
+    pub maker_loss: u64,
+
+    /// Settlement timestamp
+    pub settled_at: i64,
+
+    /// Timestamps of the inflections settled against
+    pub inflections: Vec<i64>,
+}
+
+impl SettlementReport {
+    /// Number of settled positions with the given status
+    pub fn count(&self, status: PositionStatus) -> usize {
+        self.positions
+            .iter()
+            .filter(|pos| pos.status == status)
+            .count()
+    }
+
+    /// Settled positions with the given status
+    pub fn with_status(&self, status: PositionStatus) -> impl Iterator<Item = &Position> {
+        self.positions.iter().filter(move |pos| pos.status == status)
+    }
+}
+
+/// Outcome of refunding a cancelled or expired market
+#[derive(Debug, Clone)]
+pub struct RefundDistribution {
+    /// Refunded positions with updated status, and the amount each recovers
+    pub refunds: Vec<(Position, u64)>,
+
+    /// Fees kept under `RefundPolicy::RetainFees`
+    pub retained_fees: u64,
+
+    /// Placement fees returned from the market's collected fees under
+    /// `RefundPolicy::RefundFees`; the rest of the refunds leaves the pool
+    pub returned_fees: u64,
+}
+
+impl RefundDistribution {
+    /// Total refunded to positions
+    pub fn total_refunded(&self) -> u64 {
+        self.refunds.iter().map(|(_, refund)| refund).sum()
+    }
+}
+
+/// Fixed-point scale applied to curve multipliers in `payout_weight`
+pub const WEIGHT_SCALE: u128 = 1_000_000_000;
+
+/// Outcome of distributing a market's pool
+#[derive(Debug, Clone)]
+pub struct PoolDistribution {
+    /// Payout for each winning position, in input order
+    pub payouts: Vec<(Position, u64)>,
+
+    /// Protocol fee deducted from the pool
+    pub fee: u64,
+
+    /// Lamports not assigned because no winning position carries weight
+    pub unallocated: u64,
+}
+
+impl PoolDistribution {
+    /// Total paid out to positions
+    pub fn total_paid(&self) -> u64 {
+        self.payouts.iter().map(|(_, payout)| payout).sum()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::market::fixtures;
+    use crate::market::lmsr::LmsrMarketMaker;
     use crate::types::belief::InflectionType;
+    use crate::types::market::{FeeSchedule, MarketConfig, MarketState, PricingMode, ResolutionMode};
+    use crate::types::position::{PositionStatus, TimeBucket};
     use solana_sdk::pubkey::Pubkey;
 
     #[test]
     fn test_linear_payout() {
-        let payout_exact = SettlementCalculator::linear_payout(0, 0.5);
+        let params = CurveParameters::default();
+        let payout_exact = SettlementCalculator::linear_payout(0.0, 0.5, &params);
         assert!(payout_exact > 2.0);
 
-        let payout_near = SettlementCalculator::linear_payout(100, 0.5);
+        let payout_near = SettlementCalculator::linear_payout(0.1, 0.5, &params);
         assert!(payout_near < payout_exact);
     }
 
     #[test]
     fn test_gaussian_payout() {
-        let payout_exact = SettlementCalculator::gaussian_payout(0, 0.5);
+        let params = CurveParameters::default();
+        let payout_exact = SettlementCalculator::gaussian_payout(0.0, 0.5, &params);
         assert!(payout_exact > 2.0);
 
-        let payout_far = SettlementCalculator::gaussian_payout(7200, 0.5);
+        let payout_far = SettlementCalculator::gaussian_payout(2.0, 0.5, &params);
         assert!(payout_far < payout_exact);
+
+        let wide = CurveParameters {
+            sigma: 2.0,
+            ..CurveParameters::default()
+        };
+        assert!(SettlementCalculator::gaussian_payout(2.0, 0.5, &wide) > payout_far);
+    }
+
+    #[test]
+    fn test_custom_plateau() {
+        let params = CurveParameters {
+            plateau_width: 0.5,
+            ..CurveParameters::default()
+        };
+
+        let peak = SettlementCalculator::custom_payout(0.0, 0.0, &params);
+        assert_eq!(peak, 2.0);
+        assert_eq!(SettlementCalculator::custom_payout(0.5, 0.0, &params), peak);
+        assert!(SettlementCalculator::custom_payout(1.0, 0.0, &params) < peak);
+    }
+
+    #[test]
+    fn test_piecewise_linear_payout() {
+        let params = CurveParameters::default();
+
+        assert_eq!(SettlementCalculator::piecewise_linear_payout(0.0, 0.0, &params), 2.0);
+        assert_eq!(SettlementCalculator::piecewise_linear_payout(0.5, 0.0, &params), 1.5);
+        assert_eq!(SettlementCalculator::piecewise_linear_payout(1.5, 0.0, &params), 0.5);
+        assert_eq!(SettlementCalculator::piecewise_linear_payout(3.0, 0.0, &params), 0.0);
+    }
+
+    #[test]
+    fn test_sample_range_is_symmetric() {
+        let curves = [
+            SettlementCurve::Linear,
+            SettlementCurve::Exponential,
+            SettlementCurve::Gaussian,
+            SettlementCurve::Custom,
+            SettlementCurve::PiecewiseLinear,
+        ];
+
+        for curve in curves {
+            let config = MarketConfig {
+                settlement_curve: curve,
+                ..MarketConfig::default()
+            };
+            let samples = SettlementCalculator::sample_range(&config, 0.5, 7200, 900);
+
+            assert_eq!(samples.len(), 17);
+            assert_eq!(samples[0].0, -7200);
+            for (left, right) in samples.iter().zip(samples.iter().rev()) {
+                assert_eq!(left.1, right.1);
+            }
+            // Peak at zero distance, never increasing away from it
+            for pair in samples[8..].windows(2) {
+                assert!(pair[1].1 <= pair[0].1, "{:?} increases", curve);
+            }
+        }
+    }
+
+    #[test]
+    fn test_payouts_scale_with_bucket_size() {
+        let curves = [
+            SettlementCurve::Linear,
+            SettlementCurve::Exponential,
+            SettlementCurve::Gaussian,
+            SettlementCurve::Custom,
+            SettlementCurve::PiecewiseLinear,
+        ];
+        let bucket_sizes = [300u64, 3600, 86_400];
+        // Inflection a quarter bucket past the position's bucket, then 1 and 3 buckets off
+        let offsets = [0.25, 1.0, 3.0];
+
+        for curve in curves {
+            for offset in offsets {
+                let payouts: Vec<u64> = bucket_sizes
+                    .iter()
+                    .map(|&size| {
+                        let mut market = market_with(0, 0);
+                        market.config.settlement_curve = curve;
+                        market.config.time_bucket_size = size;
+
+                        let mut position = fixtures::position(0, 1_000_000_000);
+                        position.time_bucket = TimeBucket::from_duration(0, size);
+                        let inflection = inflection_at(size as i64 + (offset * size as f64) as i64);
+
+                        SettlementCalculator::calculate_payout(&market, &position, &inflection)
+                    })
+                    .collect();
+
+                assert!(
+                    payouts.windows(2).all(|pair| pair[0].abs_diff(pair[1]) <= 1),
+                    "{:?} at {} buckets: {:?}",
+                    curve,
+                    offset,
+                    payouts
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_settle_market_classifies_positions() {
+        let market = market_with(50, 4_000_000_000);
+        // Inflection inside the first bucket; the others are 0.5, 2 and 5.5 buckets away
+        let mut positions: Vec<Position> = [0, 3600, 9000, 3600 * 6]
+            .iter()
+            .map(|&start| {
+                let mut position = fixtures::position(start, 1_000_000_000);
+                position.market = market.address;
+                position
+            })
+            .collect();
+        let mut withdrawn = fixtures::position(0, 1_000_000_000);
+        withdrawn.market = market.address;
+        withdrawn.status = PositionStatus::Withdrawn;
+        positions.push(withdrawn);
+
+        let report =
+            SettlementCalculator::settle_market(&market, &positions, &inflection_at(1800));
+
+        let statuses: Vec<PositionStatus> = report.positions.iter().map(|p| p.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                PositionStatus::Won,
+                PositionStatus::Won,
+                PositionStatus::PartialWin,
+                PositionStatus::Lost,
+            ]
+        );
+        assert_eq!(report.count(PositionStatus::Won), 2);
+        assert_eq!(report.total_staked, 4_000_000_000);
+        assert_eq!(report.total_paid + report.fee + report.unallocated, 4_000_000_000);
+        assert_eq!(report.unallocated, 0);
+
+        assert_eq!(report.positions[3].payout, Some(0));
+        assert!(report.positions[0].payout > report.positions[1].payout);
+        assert!(report.positions[1].payout > report.positions[2].payout);
+        assert!(report.positions.iter().all(|p| p.settled_at == Some(1800)));
+    }
+
+    #[test]
+    fn test_scoring_settlement_rewards_calibrated_owner() {
+        let mut market = market_with(0, 3_000_000_000);
+        market.config.settlement_curve = SettlementCurve::LogScore;
+
+        // One owner hedges across two buckets, the other stakes on the wrong one
+        let hedger = Pubkey::new_unique();
+        let mut positions = vec![
+            fixtures::position(0, 500_000_000),
+            fixtures::position(3600, 500_000_000),
+            fixtures::position(3600, 2_000_000_000),
+        ];
+        for position in &mut positions {
+            position.market = market.address;
+        }
+        positions[0].owner = hedger;
+        positions[1].owner = hedger;
+
+        let report =
+            SettlementCalculator::settle_market(&market, &positions, &inflection_at(1800));
+
+        assert_eq!(report.total_paid, 3_000_000_000);
+        assert_eq!(report.positions[0].payout, report.positions[1].payout);
+        assert!(report.positions[0].payout.unwrap() > 500_000_000);
+        assert_eq!(report.positions[0].status, PositionStatus::Won);
+        assert_eq!(report.positions[2].status, PositionStatus::PartialWin);
+    }
+
+    #[test]
+    fn test_settle_market_without_winners() {
+        let market = market_with(0, 1_000_000_000);
+        let mut position = fixtures::position(0, 1_000_000_000);
+        position.market = market.address;
+
+        let report =
+            SettlementCalculator::settle_market(&market, &[position], &inflection_at(3600 * 10));
+
+        assert_eq!(report.count(PositionStatus::Lost), 1);
+        assert_eq!(report.total_paid, 0);
+        assert_eq!(report.unallocated, 1_000_000_000);
+    }
+
+    #[test]
+    fn test_settle_lmsr_shares() {
+        let mut market = market_with(0, 0);
+        market.config.pricing_mode = PricingMode::Lmsr { liquidity: 1_000_000_000 };
+        let buckets = vec![
+            TimeBucket::from_duration(0, 3600),
+            TimeBucket::from_duration(3600, 3600),
+        ];
+        let mut maker = LmsrMarketMaker::for_market(&market, buckets).unwrap();
+
+        let mut holdings = Vec::new();
+        for (index, shares) in [(0, 500_000_000), (1, 200_000_000)] {
+            let fill = maker.buy(index, shares).unwrap();
+            let mut position = fixtures::position(fill.bucket.start, fill.cost);
+            position.market = market.address;
+            holdings.push((position, shares));
+        }
+
+        let report = SettlementCalculator::settle_shares(&market, &holdings, &inflection_at(4000));
+
+        assert_eq!(report.positions[0].status, PositionStatus::Lost);
+        assert_eq!(report.positions[1].payout, Some(200_000_000));
+        assert_eq!(report.total_staked, maker.collected());
+        assert_eq!(report.unallocated, maker.collected() - 200_000_000);
+        assert_eq!(report.maker_loss, 0);
+
+        // The lone holder of bucket 0 is owed more than everything paid in
+        let report = SettlementCalculator::settle_shares(&market, &holdings, &inflection_at(100));
+        assert_eq!(report.positions[0].payout, Some(500_000_000));
+        assert_eq!(report.unallocated, 0);
+        assert_eq!(report.maker_loss, 500_000_000 - maker.collected());
+        assert!(report.maker_loss <= maker.max_loss());
+    }
+
+    #[test]
+    fn test_settle_multiple_inflections() {
+        let mut market = market_with(0, 2_000_000_001);
+        market.config.resolution_mode = ResolutionMode::MultipleInflections { count: 2 };
+
+        let mut positions: Vec<Position> = [0, 3600 * 10]
+            .iter()
+            .map(|&start| {
+                let mut position = fixtures::position(start, 1_000_000_000);
+                position.market = market.address;
+                position
+            })
+            .collect();
+        positions[1].owner = positions[0].owner;
+
+        let mut unvalidated = inflection_at(3600 * 5);
+        unvalidated.validated = false;
+        let inflections = vec![
+            inflection_at(3600 * 10 + 1800),
+            unvalidated,
+            inflection_at(1800),
+            inflection_at(3600 * 20),
+        ];
+
+        let report =
+            SettlementCalculator::settle_inflections(&market, &positions, &inflections).unwrap();
+
+        // Settled in chronological order; the third inflection is beyond the count
+        assert_eq!(report.inflections, vec![1800, 3600 * 10 + 1800]);
+        assert_eq!(report.settled_at, 3600 * 10 + 1800);
+        // Each position takes one share of the pool; the odd lamport goes to the first share
+        assert_eq!(report.positions[0].payout, Some(1_000_000_001));
+        assert_eq!(report.positions[1].payout, Some(1_000_000_000));
+        assert!(report.positions.iter().all(|p| p.status == PositionStatus::Won));
+        assert_eq!(report.total_paid + report.fee + report.unallocated, 2_000_000_001);
+
+        market.config.resolution_mode = ResolutionMode::Primary { index: 1 };
+        let report =
+            SettlementCalculator::settle_inflections(&market, &positions, &inflections).unwrap();
+        assert_eq!(report.inflections, vec![3600 * 10 + 1800]);
+        assert_eq!(report.positions[0].status, PositionStatus::Lost);
+        assert_eq!(report.positions[1].payout, Some(2_000_000_001));
+
+        market.config.resolution_mode = ResolutionMode::Primary { index: 5 };
+        assert!(
+            SettlementCalculator::settle_inflections(&market, &positions, &inflections).is_none()
+        );
+    }
+
+    #[test]
+    fn test_refunds_for_cancelled_and_expired_markets() {
+        let mut market = market_with(50, 3_000_000_000);
+        let mut positions: Vec<Position> = (0..3)
+            .map(|i| {
+                let mut position = fixtures::position(i * 3600, 1_000_000_000);
+                position.market = market.address;
+                position
+            })
+            .collect();
+        positions[2].status = PositionStatus::Withdrawn;
+
+        // Resolved markets cannot be refunded
+        assert!(SettlementCalculator::calculate_refunds(&market, &positions, 100).is_err());
+
+        market.state = MarketState::Cancelled;
+        let refunds = SettlementCalculator::calculate_refunds(&market, &positions, 100).unwrap();
+        assert_eq!(refunds.refunds.len(), 2);
+        assert_eq!(refunds.total_refunded(), 2_000_000_000);
+        assert_eq!(refunds.retained_fees, 0);
+        assert_eq!(refunds.returned_fees, 0);
+        for (position, refund) in &refunds.refunds {
+            assert_eq!(position.status, PositionStatus::Withdrawn);
+            assert_eq!(position.payout, Some(*refund));
+            assert_eq!(position.settled_at, Some(100));
+        }
+
+        market.state = MarketState::Expired;
+        market.config.refund_policy = RefundPolicy::RetainFees;
+        market.config.fees.placement_bps = 50;
+        let refunds = SettlementCalculator::calculate_refunds(&market, &positions, 100).unwrap();
+        assert_eq!(refunds.refunds[0].0.status, PositionStatus::Expired);
+        assert_eq!(refunds.refunds[0].1, 995_000_000);
+        assert_eq!(refunds.retained_fees, 10_000_000);
+
+        assert!(matches!(
+            SettlementCalculator::refund_amount(&market, &positions[2]),
+            Err(PredaError::NotRefundable(_))
+        ));
+    }
+
+    #[test]
+    fn test_refunds_come_from_pool_and_collected_fees() {
+        let mut market = market_with(0, 0);
+        market.state = MarketState::Cancelled;
+        market.config.fees.placement_bps = 50;
+        let positions: Vec<Position> = [1_000_000_000, 333_333_333, 7]
+            .iter()
+            .enumerate()
+            .map(|(i, &amount)| {
+                let mut position = fixtures::position(i as i64 * 3600, amount);
+                position.market = market.address;
+                position
+            })
+            .collect();
+        let fees = &market.config.fees;
+        market.total_value_locked = positions.iter().map(|pos| pos.net_stake(fees)).sum();
+        let collected: u64 = positions.iter().map(|pos| fees.placement_fee(pos.amount)).sum();
+
+        // Refunding fees pays back exactly the pool and the collected placement fees
+        let refunds = SettlementCalculator::calculate_refunds(&market, &positions, 100).unwrap();
+        assert_eq!(refunds.returned_fees, collected);
+        assert_eq!(
+            refunds.total_refunded(),
+            market.total_value_locked + refunds.returned_fees
+        );
+        assert_eq!(refunds.total_refunded(), positions.iter().map(|pos| pos.amount).sum::<u64>());
+
+        // Retaining fees pays back exactly the pool
+        market.config.refund_policy = RefundPolicy::RetainFees;
+        let refunds = SettlementCalculator::calculate_refunds(&market, &positions, 100).unwrap();
+        assert_eq!(refunds.returned_fees, 0);
+        assert_eq!(refunds.retained_fees, collected);
+        assert_eq!(refunds.total_refunded(), market.total_value_locked);
+    }
+
+    /// Resolved market with a settlement fee and no placement fee
+    fn market_with(fee_bps: u16, total_value_locked: u64) -> Market {
+        let mut market = Market {
+            state: MarketState::Resolved,
+            total_value_locked,
+            ..fixtures::market()
+        };
+        market.config.fees = FeeSchedule {
+            placement_bps: 0,
+            settlement_bps: fee_bps,
+            ..FeeSchedule::default()
+        };
+        market
+    }
+
+    fn inflection_at(timestamp: i64) -> BeliefInflection {
+        BeliefInflection {
+            inflection_type: InflectionType::ThresholdCrossing,
+            timestamp,
+            bsi_value: 0.7,
+            velocity: 0.1,
+            sharpness: 0.5,
+            persistence_duration: 3600,
+            validated: true,
+        }
+    }
+
+    #[test]
+    fn test_placement_fees_stay_out_of_pool() {
+        let mut market = market_with(100, 0);
+        market.config.fees.placement_bps = 50;
+        let fees = market.config.fees;
+        let positions: Vec<Position> = [0, 100 * 3600]
+            .into_iter()
+            .map(|start| Position {
+                market: market.address,
+                ..fixtures::position(start, 1_000_000_000)
+            })
+            .collect();
+        market.total_value_locked = positions.iter().map(|pos| pos.net_stake(&fees)).sum();
+        assert_eq!(market.total_value_locked, 1_990_000_000);
+
+        let report = SettlementCalculator::settle_market(&market, &positions, &inflection_at(1800));
+
+        // The winner takes the net pool less the settlement fee, not the gross stakes
+        assert_eq!(report.count(PositionStatus::Won), 1);
+        assert_eq!(report.fee, 19_900_000);
+        assert_eq!(report.positions[0].payout, Some(1_970_100_000));
+        assert_eq!(
+            report.total_paid + report.fee + report.unallocated,
+            market.total_value_locked
+        );
+    }
+
+    #[test]
+    fn test_pool_distribution_conserves_lamports() {
+        let market = market_with(50, 1_000_000_007);
+        let positions = vec![
+            fixtures::position(0, 300_000_000),
+            fixtures::position(3600, 300_000_000),
+            fixtures::position(7200, 400_000_007),
+        ];
+
+        let distribution = SettlementCalculator::calculate_pool_distribution(
+            &market,
+            &positions,
+            &inflection_at(1800),
+        );
+
+        assert_eq!(distribution.fee, market.config.fees.settlement_fee(1_000_000_007));
+        assert_eq!(distribution.unallocated, 0);
+        assert_eq!(distribution.total_paid() + distribution.fee, 1_000_000_007);
+        assert!(distribution.payouts[0].1 > distribution.payouts[2].1);
+    }
+
+    #[test]
+    fn test_largest_remainder_is_deterministic() {
+        // 10 lamports over three equal weights: 4, 3, 3
+        assert_eq!(SettlementCalculator::allocate(10, &[5, 5, 5]), vec![4, 3, 3]);
+        assert_eq!(SettlementCalculator::allocate(10, &[1, 0, 2]), vec![3, 0, 7]);
+        assert_eq!(SettlementCalculator::allocate(10, &[0, 0]), vec![0, 0]);
+        assert_eq!(
+            SettlementCalculator::allocate(u64::MAX as u128, &[u128::MAX / 2, u128::MAX / 2]),
+            vec![u64::MAX as u128 / 2 + 1, u64::MAX as u128 / 2]
+        );
+    }
+
+    #[test]
+    fn test_unweighted_pool_is_unallocated() {
+        let market = market_with(100, 1_000_000);
+        let distribution =
+            SettlementCalculator::calculate_pool_distribution(&market, &[], &inflection_at(0));
+
+        assert_eq!(distribution.fee, 10_000);
+        assert_eq!(distribution.unallocated, 990_000);
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn payouts_and_fee_sum_to_pool(
+                pool in any::<u64>(),
+                fee_bps in 0u16..=10_000,
+                positions in prop::collection::vec(
+                    (-200_000i64..200_000, 1u64..=u64::MAX / 64),
+                    1..32,
+                ),
+                inflection in -200_000i64..200_000,
+            ) {
+                let market = market_with(fee_bps, pool);
+                let positions: Vec<Position> = positions
+                    .into_iter()
+                    .map(|(start, amount)| fixtures::position(start, amount))
+                    .collect();
+
+                let distribution = SettlementCalculator::calculate_pool_distribution(
+                    &market,
+                    &positions,
+                    &inflection_at(inflection),
+                );
+
+                let paid: u128 = distribution.payouts.iter().map(|(_, p)| *p as u128).sum();
+                prop_assert_eq!(
+                    paid + distribution.fee as u128 + distribution.unallocated as u128,
+                    pool as u128
+                );
+                prop_assert_eq!(distribution.payouts.len(), positions.len());
+            }
+
+            #[test]
+            fn scoring_settlement_is_budget_balanced(
+                pool in 1u64..=u64::MAX,
+                fee_bps in 0u16..=10_000,
+                quadratic in any::<bool>(),
+                stakes in prop::collection::vec((0u8..4, -4i64..4, 1u64..1_000_000_000_000), 1..16),
+                inflection in -4i64 * 3600..4 * 3600,
+            ) {
+                let mut market = market_with(fee_bps, pool);
+                market.config.settlement_curve = if quadratic {
+                    SettlementCurve::QuadraticScore
+                } else {
+                    SettlementCurve::LogScore
+                };
+
+                let owners: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+                let positions: Vec<Position> = stakes
+                    .into_iter()
+                    .map(|(owner, bucket, amount)| {
+                        let mut position = fixtures::position(bucket * 3600, amount);
+                        position.market = market.address;
+                        position.owner = owners[owner as usize];
+                        position
+                    })
+                    .collect();
+
+                let report = SettlementCalculator::settle_market(
+                    &market,
+                    &positions,
+                    &inflection_at(inflection),
+                );
+
+                prop_assert_eq!(report.unallocated, 0);
+                prop_assert_eq!(report.total_paid as u128 + report.fee as u128, pool as u128);
+            }
+
+            #[test]
+            fn weighted_pools_are_fully_allocated(
+                total in any::<u64>(),
+                weights in prop::collection::vec(any::<u64>(), 1..16),
+            ) {
+                let weights: Vec<u128> = weights.into_iter().map(u128::from).collect();
+                let shares = SettlementCalculator::allocate(total as u128, &weights);
+                let allocated: u128 = shares.iter().sum();
+
+                if weights.iter().any(|&weight| weight > 0) {
+                    prop_assert_eq!(allocated, total as u128);
+                } else {
+                    prop_assert_eq!(allocated, 0);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,363 @@
+//! What-if payouts for hypothetical inflection times
+
+use solana_sdk::pubkey::Pubkey;
+
+use super::settlement::SettlementCalculator;
+use crate::error::{PredaError, Result};
+use crate::types::{
+    belief::{BeliefInflection, InflectionType},
+    market::{Market, PricingMode},
+    position::{Position, PositionStatus, TimeBucketAggregate},
+};
+
+/// Payouts of a set of positions across hypothetical inflection times
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutMatrix {
+    /// Simulated inflection times (rows)
+    pub inflection_times: Vec<i64>,
+
+    /// Simulated position addresses (columns)
+    pub positions: Vec<Pubkey>,
+
+    /// `payouts[row][column]` is the payout of a position if the inflection happens at that time
+    pub payouts: Vec<Vec<u64>>,
+
+    /// Protocol fee taken at each inflection time
+    pub fees: Vec<u64>,
+}
+
+impl PayoutMatrix {
+    /// Payout of a position if the inflection happens at `inflection_time`
+    pub fn payout(&self, inflection_time: i64, position: &Pubkey) -> Option<u64> {
+        let row = self
+            .inflection_times
+            .iter()
+            .position(|&t| t == inflection_time)?;
+        let column = self.positions.iter().position(|p| p == position)?;
+        Some(self.payouts[row][column])
+    }
+
+    /// Total paid to the simulated positions at each inflection time
+    pub fn totals(&self) -> Vec<u64> {
+        self.payouts.iter().map(|row| row.iter().sum()).collect()
+    }
+}
+
+/// Simulate what each position pays if the inflection happens at each time
+///
+/// Other participants' stake comes from `aggregates`, one stake per bucket.
+/// `positions` are treated as new stake on top of it, so the simulated pool
+/// is the market's value locked plus their net stakes, both net of placement
+/// fees. Positions already counted in `aggregates` and `total_value_locked`
+/// must be taken out with `exclude_positions` first, or their stake counts
+/// twice. Each time is settled with `SettlementCalculator::settle_market`, so
+/// the market's curve, thresholds, fee and pool normalization all apply.
+///
+/// `PricingMode::Lmsr` markets pay by shares rather than from the pool;
+/// simulate them with [`simulate_share_payouts`].
+pub fn simulate_payouts(
+    market: &Market,
+    positions: &[Position],
+    aggregates: &[TimeBucketAggregate],
+    inflection_times: &[i64],
+    sharpness: f64,
+) -> Result<PayoutMatrix> {
+    if market.config.pricing_mode != PricingMode::Parimutuel {
+        return Err(PredaError::Configuration(format!(
+            "Market {} is priced with LMSR; simulate its shares instead",
+            market.address
+        )));
+    }
+
+    let mut simulated = market.clone();
+    simulated.resolved_at = None;
+
+    let mut stakes: Vec<Position> = positions
+        .iter()
+        .map(|position| open_in(market, position))
+        .collect();
+    let own_stake: u64 = stakes
+        .iter()
+        .map(|pos| pos.net_stake(&market.config.fees))
+        .sum();
+
+    stakes.extend(
+        aggregates
+            .iter()
+            .filter(|aggregate| aggregate.total_staked > 0)
+            .map(|aggregate| Position {
+                address: Pubkey::new_unique(),
+                market: market.address,
+                owner: Pubkey::new_unique(),
+                time_bucket: aggregate.time_bucket,
+                amount: aggregate.total_staked,
+                status: PositionStatus::Active,
+                created_at: market.created_at,
+                settled_at: None,
+                payout: None,
+            }),
+    );
+    simulated.total_value_locked = market.total_value_locked.saturating_add(own_stake);
+
+    let mut payouts = Vec::with_capacity(inflection_times.len());
+    let mut fees = Vec::with_capacity(inflection_times.len());
+    for &timestamp in inflection_times {
+        let inflection = inflection_at(timestamp, sharpness);
+        let report = SettlementCalculator::settle_market(&simulated, &stakes, &inflection);
+        payouts.push(
+            report.positions[..positions.len()]
+                .iter()
+                .map(|pos| pos.payout.unwrap_or(0))
+                .collect(),
+        );
+        fees.push(report.fee);
+    }
+
+    Ok(PayoutMatrix {
+        inflection_times: inflection_times.to_vec(),
+        positions: positions.iter().map(|pos| pos.address).collect(),
+        payouts,
+        fees,
+    })
+}
+
+/// Simulate what positions of a `PricingMode::Lmsr` market pay if the
+/// inflection happens at each time
+///
+/// `holdings` pairs each position with its bucket shares. Shares pay on
+/// their own, so no other stake is needed; each time is settled with
+/// `SettlementCalculator::settle_shares` and `fees` holds the settlement fee
+/// on the winning shares.
+pub fn simulate_share_payouts(
+    market: &Market,
+    holdings: &[(Position, u64)],
+    inflection_times: &[i64],
+    sharpness: f64,
+) -> PayoutMatrix {
+    let mut simulated = market.clone();
+    simulated.resolved_at = None;
+
+    let holdings: Vec<(Position, u64)> = holdings
+        .iter()
+        .map(|(position, shares)| (open_in(market, position), *shares))
+        .collect();
+
+    let mut payouts = Vec::with_capacity(inflection_times.len());
+    let mut fees = Vec::with_capacity(inflection_times.len());
+    for &timestamp in inflection_times {
+        let inflection = inflection_at(timestamp, sharpness);
+        let report = SettlementCalculator::settle_shares(&simulated, &holdings, &inflection);
+        payouts.push(
+            report
+                .positions
+                .iter()
+                .map(|pos| pos.payout.unwrap_or(0))
+                .collect(),
+        );
+        fees.push(report.fee);
+    }
+
+    PayoutMatrix {
+        inflection_times: inflection_times.to_vec(),
+        positions: holdings.iter().map(|(pos, _)| pos.address).collect(),
+        payouts,
+        fees,
+    }
+}
+
+/// Copy of `position` as an active, unsettled position of `market`
+fn open_in(market: &Market, position: &Position) -> Position {
+    Position {
+        market: market.address,
+        status: PositionStatus::Active,
+        settled_at: None,
+        payout: None,
+        ..position.clone()
+    }
+}
+
+/// Validated inflection at `timestamp` for simulation
+fn inflection_at(timestamp: i64, sharpness: f64) -> BeliefInflection {
+    BeliefInflection {
+        inflection_type: InflectionType::ThresholdCrossing,
+        timestamp,
+        bsi_value: 0.0,
+        velocity: 0.0,
+        sharpness,
+        persistence_duration: 0,
+        validated: true,
+    }
+}
+
+/// Take placed positions out of a market's pool and bucket aggregates
+///
+/// Returns the market with their net stake removed from
+/// `total_value_locked` and the aggregates without their stake, so they can
+/// be simulated as new stake. Positions that are inactive or belong to
+/// another market are ignored.
+pub fn exclude_positions(
+    market: &Market,
+    aggregates: &[TimeBucketAggregate],
+    positions: &[Position],
+) -> (Market, Vec<TimeBucketAggregate>) {
+    let mut market = market.clone();
+    let mut aggregates = aggregates.to_vec();
+    for position in positions
+        .iter()
+        .filter(|pos| pos.market == market.address && pos.is_active())
+    {
+        market.total_value_locked = market
+            .total_value_locked
+            .saturating_sub(position.net_stake(&market.config.fees));
+        if let Some(aggregate) = aggregates
+            .iter_mut()
+            .find(|agg| agg.time_bucket == position.time_bucket)
+        {
+            aggregate.total_staked = aggregate.total_staked.saturating_sub(position.amount);
+            aggregate.position_count = aggregate.position_count.saturating_sub(1);
+        }
+    }
+
+    aggregates.retain(|agg| agg.total_staked > 0);
+    let total: u64 = aggregates.iter().map(|agg| agg.total_staked).sum();
+    for aggregate in &mut aggregates {
+        aggregate.implied_probability =
+            TimeBucketAggregate::calculate_implied_probability(aggregate.total_staked, total);
+        aggregate.avg_position_size =
+            aggregate.total_staked / aggregate.position_count.max(1) as u64;
+    }
+    (market, aggregates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::fixtures::{aggregate, position};
+    use crate::types::market::MarketConfig;
+
+    fn market(total_value_locked: u64) -> Market {
+        Market {
+            total_value_locked,
+            ..crate::market::fixtures::market()
+        }
+    }
+
+    #[test]
+    fn test_payout_matrix() {
+        let market = market(2_000_000_000);
+        let aggregates = vec![aggregate(0, 1_000_000_000), aggregate(3600, 1_000_000_000)];
+        let positions = vec![position(0, 100_000_000), position(7200, 100_000_000)];
+        let times = [1800, 9000, 100_000];
+
+        let matrix = simulate_payouts(&market, &positions, &aggregates, &times, 0.5).unwrap();
+
+        assert_eq!(matrix.payouts.len(), 3);
+        assert!(matrix.payouts.iter().all(|row| row.len() == 2));
+
+        // Each position pays most when the inflection lands in its own bucket
+        assert!(matrix.payouts[0][0] > matrix.payouts[1][0]);
+        assert!(matrix.payouts[1][1] > matrix.payouts[0][1]);
+
+        // Nobody is close to an inflection far in the future
+        assert_eq!(matrix.totals()[2], 0);
+        assert_eq!(
+            matrix.payout(1800, &positions[0].address),
+            Some(matrix.payouts[0][0])
+        );
+    }
+
+    #[test]
+    fn test_other_stake_dilutes_payouts() {
+        let positions = vec![position(0, 100_000_000)];
+        let fees = market(0).config.fees;
+        let other = fees.placement_fee(1_000_000_000);
+
+        let alone = simulate_payouts(&market(0), &positions, &[], &[1800], 0.5).unwrap();
+        let crowded = simulate_payouts(
+            &market(1_000_000_000 - other),
+            &positions,
+            &[aggregate(0, 1_000_000_000)],
+            &[1800],
+            0.5,
+        )
+        .unwrap();
+
+        // Alone, the position recovers its net stake
+        assert_eq!(alone.payouts[0][0], positions[0].net_stake(&fees));
+        // Sharing the winning bucket with 10x the stake leaves a tenth of the pool
+        let pool = 1_000_000_000 - other + positions[0].net_stake(&fees) - crowded.fees[0];
+        assert!(crowded.payouts[0][0].abs_diff(pool / 11) <= 1);
+    }
+
+    #[test]
+    fn test_placed_positions_count_once() {
+        let fees = market(0).config.fees;
+        let other = 1_000_000_000;
+        let placed = position(0, 100_000_000);
+
+        // The market's pool and aggregates already include the placed position
+        let market = Market {
+            total_value_locked: other - fees.placement_fee(other) + placed.net_stake(&fees),
+            ..market(0)
+        };
+        let placed = Position {
+            market: market.address,
+            ..placed
+        };
+        let aggregates = vec![aggregate(0, placed.amount), aggregate(864_000, other)];
+
+        let (excluded, rest) =
+            exclude_positions(&market, &aggregates, std::slice::from_ref(&placed));
+        assert_eq!(
+            excluded.total_value_locked,
+            other - fees.placement_fee(other)
+        );
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].total_staked, other);
+
+        // Alone in the winning bucket, the position takes the whole pool
+        let once = simulate_payouts(
+            &excluded,
+            std::slice::from_ref(&placed),
+            &rest,
+            &[1800],
+            0.5,
+        )
+        .unwrap();
+        assert_eq!(once.payouts[0][0], market.total_value_locked - once.fees[0]);
+
+        // Counted twice, it would share the bucket with itself
+        let twice = simulate_payouts(&market, &[placed], &aggregates, &[1800], 0.5).unwrap();
+        assert!(twice.payouts[0][0] < once.payouts[0][0]);
+    }
+
+    #[test]
+    fn test_lmsr_markets_settle_shares() {
+        let market = Market {
+            config: MarketConfig {
+                pricing_mode: PricingMode::Lmsr { liquidity: 1_000 },
+                ..MarketConfig::default()
+            },
+            ..market(0)
+        };
+        let positions = vec![position(0, 100_000_000), position(3600, 100_000_000)];
+
+        // Pooled simulation would ignore the shares
+        assert!(matches!(
+            simulate_payouts(&market, &positions, &[], &[1800], 0.5),
+            Err(PredaError::Configuration(_))
+        ));
+
+        let holdings = vec![
+            (positions[0].clone(), 150_000_000),
+            (positions[1].clone(), 120_000_000),
+        ];
+        let matrix = simulate_share_payouts(&market, &holdings, &[1800, 5400, 100_000], 0.5);
+        let fees = &market.config.fees;
+        let paid = |shares: u64| shares - fees.settlement_fee(shares);
+        assert_eq!(matrix.payouts[0], vec![paid(150_000_000), 0]);
+        assert_eq!(matrix.payouts[1], vec![0, paid(120_000_000)]);
+        assert_eq!(matrix.totals()[2], 0);
+        assert_eq!(matrix.fees[0], fees.settlement_fee(150_000_000));
+    }
+}
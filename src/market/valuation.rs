@@ -0,0 +1,553 @@
+//! Valuing open positions under the market-implied inflection distribution
+//!
+//! Payouts are evaluated at inflection times sampled from the implied
+//! distribution, settling the valued positions against the rest of the pool
+//! with `simulation::simulate_payouts`. A position's mark-to-market and a
+//! market's exposure use the exact sampled payout distribution; a portfolio
+//! treats markets as independent and uses a normal approximation for its
+//! interval. `PricingMode::Lmsr` markets pay by shares and are not valued
+//! here.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use super::{distribution::InflectionTimeDistribution, simulation};
+use crate::error::Result;
+use crate::types::{
+    market::Market,
+    position::{Position, TimeBucketAggregate},
+};
+
+/// Probability covered by payout confidence intervals
+pub const CONFIDENCE_LEVEL: f64 = 0.9;
+
+/// Standard normal quantile for `CONFIDENCE_LEVEL`, used for portfolios
+const CONFIDENCE_Z: f64 = 1.644_853_626_951_472_2;
+
+/// Mark-to-market valuation of one position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkToMarket {
+    /// Position address
+    pub position: Pubkey,
+
+    /// Amount staked (lamports)
+    pub cost_basis: u64,
+
+    /// Expected payout (lamports)
+    pub expected_payout: f64,
+
+    /// Expected payout minus cost basis
+    pub unrealized_pnl: f64,
+
+    /// Standard deviation of the payout
+    pub std_dev: f64,
+
+    /// Payout range holding `CONFIDENCE_LEVEL` of the probability
+    pub confidence_interval: (u64, u64),
+
+    /// Probability the payout exceeds the cost basis
+    pub probability_of_profit: f64,
+
+    /// Amount returned by withdrawing now
+    pub withdrawal_value: u64,
+}
+
+/// Combined valuation of a user's positions in one market
+///
+/// Positions in a market settle on the same inflection, so their payouts are
+/// summed per sampled inflection time before taking the interval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketExposure {
+    /// Market address
+    pub market: Pubkey,
+
+    /// Valuation of each position
+    pub positions: Vec<MarkToMarket>,
+
+    /// Total staked (lamports)
+    pub cost_basis: u64,
+
+    /// Expected total payout
+    pub expected_payout: f64,
+
+    /// Expected total payout minus cost basis
+    pub unrealized_pnl: f64,
+
+    /// Standard deviation of the total payout
+    pub std_dev: f64,
+
+    /// Total payout range holding `CONFIDENCE_LEVEL` of the probability
+    pub confidence_interval: (u64, u64),
+
+    /// Smallest possible total payout
+    pub worst_case: u64,
+
+    /// Largest possible total payout
+    pub best_case: u64,
+}
+
+/// Valuation rolled up across markets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioValuation {
+    /// Exposure in each market
+    pub markets: Vec<MarketExposure>,
+
+    /// Total staked (lamports)
+    pub cost_basis: u64,
+
+    /// Expected total payout
+    pub expected_payout: f64,
+
+    /// Expected total payout minus cost basis
+    pub unrealized_pnl: f64,
+
+    /// Standard deviation of the total payout, markets taken as independent
+    pub std_dev: f64,
+
+    /// Normal-approximation payout interval at `CONFIDENCE_LEVEL`, clamped to
+    /// the worst and best cases
+    pub confidence_interval: (u64, u64),
+
+    /// Smallest possible total payout
+    pub worst_case: u64,
+}
+
+/// Discrete payout distribution: `(probability, payout)` pairs
+struct Outcomes(Vec<(f64, u64)>);
+
+/// Payout of a placed position at sampled inflection times, with their
+/// probabilities
+///
+/// Each segment of the distribution is cut into pieces no wider than the
+/// market's bucket size, and the inflection is placed at each piece's
+/// midpoint with zero sharpness. `aggregates` and `total_value_locked` are
+/// the market's current stake, including the position; it is taken out with
+/// `simulation::exclude_positions` and settled against the rest with
+/// `simulation::simulate_payouts`, so the pool, curve and fees all apply.
+/// Probabilities sum to 1. Fails for `PricingMode::Lmsr` markets, which
+/// `simulate_payouts` does not settle.
+pub fn payout_outcomes(
+    market: &Market,
+    position: &Position,
+    aggregates: &[TimeBucketAggregate],
+    distribution: &InflectionTimeDistribution,
+) -> Result<Vec<(f64, u64)>> {
+    let times = sample_times(market, distribution);
+    let payouts = payouts_at(market, std::slice::from_ref(position), aggregates, &times)?;
+    Ok(Outcomes::of(position, &times, &payouts, 0).0)
+}
+
+/// Expected payout of a placed position (lamports)
+pub fn expected_payout(
+    market: &Market,
+    position: &Position,
+    aggregates: &[TimeBucketAggregate],
+    distribution: &InflectionTimeDistribution,
+) -> Result<f64> {
+    Ok(payout_outcomes(market, position, aggregates, distribution)?
+        .into_iter()
+        .map(|(probability, payout)| probability * payout as f64)
+        .sum())
+}
+
+/// Fair price of an open position for a transfer or listing (lamports)
+///
+/// The holder can always withdraw instead of holding to settlement, so the
+/// value is the larger of the expected payout and the withdrawal value.
+pub fn fair_value(
+    market: &Market,
+    position: &Position,
+    aggregates: &[TimeBucketAggregate],
+    distribution: &InflectionTimeDistribution,
+) -> Result<u64> {
+    let expected = expected_payout(market, position, aggregates, distribution)?.round() as u64;
+    Ok(expected.max(position.withdrawal_value(&market.config.fees)))
+}
+
+impl Position {
+    /// Value the position under the market-implied inflection distribution
+    ///
+    /// `aggregates` are the market's current stake, including this position;
+    /// see [`payout_outcomes`]. Positions that are no longer active are
+    /// marked at their recorded payout (zero if none) with no spread.
+    pub fn mark_to_market(
+        &self,
+        market: &Market,
+        aggregates: &[TimeBucketAggregate],
+        distribution: &InflectionTimeDistribution,
+    ) -> Result<MarkToMarket> {
+        let outcomes = Outcomes(payout_outcomes(market, self, aggregates, distribution)?);
+        Ok(MarkToMarket::new(market, self, &outcomes))
+    }
+}
+
+impl MarkToMarket {
+    fn new(market: &Market, position: &Position, outcomes: &Outcomes) -> Self {
+        let expected_payout = outcomes.mean();
+        Self {
+            position: position.address,
+            cost_basis: position.amount,
+            expected_payout,
+            unrealized_pnl: expected_payout - position.amount as f64,
+            std_dev: outcomes.std_dev(),
+            confidence_interval: outcomes.interval(),
+            probability_of_profit: outcomes.probability_above(position.amount),
+            withdrawal_value: position.withdrawal_value(&market.config.fees),
+        }
+    }
+}
+
+impl MarketExposure {
+    /// Value a user's positions in `market` together
+    ///
+    /// The open positions are settled together against the rest of the
+    /// market's stake in `aggregates`, which includes them.
+    pub fn mark(
+        market: &Market,
+        positions: &[Position],
+        aggregates: &[TimeBucketAggregate],
+        distribution: &InflectionTimeDistribution,
+    ) -> Result<Self> {
+        let times = sample_times(market, distribution);
+        let payouts = payouts_at(market, positions, aggregates, &times)?;
+        let mut totals = vec![0u64; times.len()];
+        let mut marks = Vec::with_capacity(positions.len());
+
+        for (column, position) in positions.iter().enumerate() {
+            let outcomes = Outcomes::of(position, &times, &payouts, column);
+
+            for (total, &(_, payout)) in totals.iter_mut().zip(&outcomes.0) {
+                *total = total.saturating_add(payout);
+            }
+            marks.push(MarkToMarket::new(market, position, &outcomes));
+        }
+
+        let joint = Outcomes(times.iter().map(|&(p, _)| p).zip(totals).collect());
+        let cost_basis = positions.iter().map(|pos| pos.amount).sum();
+        let expected_payout = joint.mean();
+
+        Ok(Self {
+            market: market.address,
+            positions: marks,
+            cost_basis,
+            expected_payout,
+            unrealized_pnl: expected_payout - cost_basis as f64,
+            std_dev: joint.std_dev(),
+            confidence_interval: joint.interval(),
+            worst_case: joint.0.iter().map(|&(_, payout)| payout).min().unwrap_or(0),
+            best_case: joint.0.iter().map(|&(_, payout)| payout).max().unwrap_or(0),
+        })
+    }
+}
+
+impl PortfolioValuation {
+    /// Roll up exposures across markets
+    pub fn from_markets(markets: Vec<MarketExposure>) -> Self {
+        let cost_basis = markets.iter().map(|m| m.cost_basis).sum();
+        let expected_payout: f64 = markets.iter().map(|m| m.expected_payout).sum();
+        let std_dev = markets
+            .iter()
+            .map(|m| m.std_dev.powi(2))
+            .sum::<f64>()
+            .sqrt();
+        let worst_case: u64 = markets.iter().map(|m| m.worst_case).sum();
+        let best_case: u64 = markets.iter().map(|m| m.best_case).sum();
+
+        let spread = CONFIDENCE_Z * std_dev;
+        let low = (expected_payout - spread).max(worst_case as f64).floor() as u64;
+        let high = (expected_payout + spread).min(best_case as f64).ceil() as u64;
+
+        Self {
+            markets,
+            cost_basis,
+            expected_payout,
+            unrealized_pnl: expected_payout - cost_basis as f64,
+            std_dev,
+            confidence_interval: (low.min(high), high),
+            worst_case,
+        }
+    }
+}
+
+impl Outcomes {
+    /// Payouts of a position at the sampled times, from column `column` of
+    /// `payouts_at`; fixed once it is closed
+    fn of(position: &Position, times: &[(f64, i64)], payouts: &[Vec<u64>], column: usize) -> Self {
+        Self(
+            times
+                .iter()
+                .zip(payouts)
+                .map(|(&(p, _), row)| {
+                    let payout = if position.is_active() {
+                        row[column]
+                    } else {
+                        position.payout.unwrap_or(0)
+                    };
+                    (p, payout)
+                })
+                .collect(),
+        )
+    }
+
+    fn mean(&self) -> f64 {
+        self.0.iter().map(|&(p, payout)| p * payout as f64).sum()
+    }
+
+    fn std_dev(&self) -> f64 {
+        let mean = self.mean();
+        self.0
+            .iter()
+            .map(|&(p, payout)| p * (payout as f64 - mean).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    fn probability_above(&self, threshold: u64) -> f64 {
+        self.0
+            .iter()
+            .filter(|&&(_, payout)| payout > threshold)
+            .map(|&(p, _)| p)
+            .sum()
+    }
+
+    /// Central interval holding `CONFIDENCE_LEVEL` of the probability
+    fn interval(&self) -> (u64, u64) {
+        let tail = (1.0 - CONFIDENCE_LEVEL) / 2.0;
+        (self.quantile(tail), self.quantile(1.0 - tail))
+    }
+
+    fn quantile(&self, q: f64) -> u64 {
+        let mut sorted = self.0.clone();
+        sorted.sort_by_key(|&(_, payout)| payout);
+
+        let mut cumulative = 0.0;
+        for &(p, payout) in &sorted {
+            cumulative += p;
+            if cumulative >= q - 1e-12 {
+                return payout;
+            }
+        }
+        sorted.last().map_or(0, |&(_, payout)| payout)
+    }
+}
+
+/// Inflection times sampled from the distribution, with their probabilities
+fn sample_times(market: &Market, distribution: &InflectionTimeDistribution) -> Vec<(f64, i64)> {
+    let step = market.config.time_bucket_size.max(1) as i64;
+    let mut times = Vec::new();
+
+    for segment in distribution.segments() {
+        if segment.probability <= 0.0 {
+            continue;
+        }
+
+        let mut start = segment.start;
+        while start < segment.end {
+            let end = (start + step).min(segment.end);
+            times.push((
+                segment.density() * (end - start) as f64,
+                start + (end - start) / 2,
+            ));
+            start = end;
+        }
+    }
+
+    times
+}
+
+/// Payouts of `positions` at each sampled time, one row per time and one
+/// column per position
+///
+/// Open positions are taken out of the market's stake and settled together
+/// against the rest; closed positions get zero columns.
+fn payouts_at(
+    market: &Market,
+    positions: &[Position],
+    aggregates: &[TimeBucketAggregate],
+    times: &[(f64, i64)],
+) -> Result<Vec<Vec<u64>>> {
+    let open: Vec<Position> = positions
+        .iter()
+        .filter(|position| position.is_active())
+        .cloned()
+        .collect();
+    let (rest, others) = simulation::exclude_positions(market, aggregates, &open);
+    let inflection_times: Vec<i64> = times.iter().map(|&(_, timestamp)| timestamp).collect();
+    let matrix = simulation::simulate_payouts(&rest, &open, &others, &inflection_times, 0.0)?;
+
+    Ok(matrix
+        .payouts
+        .into_iter()
+        .map(|row| {
+            let mut open = row.into_iter();
+            positions
+                .iter()
+                .map(|position| {
+                    if position.is_active() {
+                        open.next().unwrap_or(0)
+                    } else {
+                        0
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::fixtures::{self, aggregate};
+    use crate::types::position::{PositionStatus, TimeBucket};
+
+    /// Start of a bucket far enough from the others to lose outright
+    const FAR: i64 = 360_000;
+
+    fn distribution(weights: &[(i64, f64)]) -> InflectionTimeDistribution {
+        let weights: Vec<(TimeBucket, f64)> = weights
+            .iter()
+            .map(|&(start, weight)| (TimeBucket::from_duration(start, 3600), weight))
+            .collect();
+        InflectionTimeDistribution::from_weights(&weights).unwrap()
+    }
+
+    fn position(market: &Market, start: i64, amount: u64) -> Position {
+        Position {
+            market: market.address,
+            ..fixtures::position(start, amount)
+        }
+    }
+
+    /// Place `positions` in `market` alongside `other` stake in the far bucket,
+    /// returning the market's aggregates
+    fn place(market: &mut Market, positions: &[Position], other: u64) -> Vec<TimeBucketAggregate> {
+        let fees = market.config.fees;
+        market.total_value_locked = positions
+            .iter()
+            .map(|pos| pos.net_stake(&fees))
+            .sum::<u64>()
+            + (other - fees.placement_fee(other));
+
+        let mut aggregates = vec![aggregate(FAR, other)];
+        for position in positions {
+            aggregates.push(aggregate(position.time_bucket.start, position.amount));
+        }
+        aggregates
+    }
+
+    #[test]
+    fn test_expected_payout_weights_outcomes() {
+        let mut market = fixtures::market();
+        let position = position(&market, 3600, 100_000_000);
+        let aggregates = place(&mut market, std::slice::from_ref(&position), 900_000_000);
+
+        let certain = distribution(&[(3600, 1.0)]);
+        let split = distribution(&[(3600, 1.0), (FAR, 1.0)]);
+
+        let outcomes = payout_outcomes(&market, &position, &aggregates, &split).unwrap();
+        let total: f64 = outcomes.iter().map(|(p, _)| p).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // Alone in the winning bucket, the position takes the whole pool
+        let hit = expected_payout(&market, &position, &aggregates, &certain).unwrap();
+        let half = expected_payout(&market, &position, &aggregates, &split).unwrap();
+        assert_eq!(hit, market.total_value_locked as f64);
+        assert!((half - hit / 2.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_payout_shares_pool_with_same_bucket() {
+        let mut market = fixtures::market();
+        let ours = position(&market, 3600, 100_000_000);
+        let theirs = position(&market, 3600, 300_000_000);
+        let aggregates = place(&mut market, &[ours.clone(), theirs], 900_000_000);
+
+        let hit =
+            expected_payout(&market, &ours, &aggregates, &distribution(&[(3600, 1.0)])).unwrap();
+        let expected = market.total_value_locked as f64 / 4.0;
+        assert!((hit - expected).abs() <= 1.0);
+    }
+
+    #[test]
+    fn test_fair_value_floors_at_withdrawal_value() {
+        let mut market = fixtures::market();
+        let position = position(&market, 3600, 100_000_000);
+        let aggregates = place(&mut market, std::slice::from_ref(&position), 900_000_000);
+        let fees = &market.config.fees;
+
+        let far = distribution(&[(FAR, 1.0)]);
+        assert_eq!(
+            fair_value(&market, &position, &aggregates, &far).unwrap(),
+            position.withdrawal_value(fees)
+        );
+
+        let near = distribution(&[(3600, 1.0)]);
+        assert!(
+            fair_value(&market, &position, &aggregates, &near).unwrap()
+                > position.withdrawal_value(fees)
+        );
+    }
+
+    #[test]
+    fn test_mark_to_market() {
+        let mut market = fixtures::market();
+        let position = position(&market, 3600, 100_000_000);
+        let aggregates = place(&mut market, std::slice::from_ref(&position), 200_000_000);
+        let split = distribution(&[(3600, 1.0), (FAR, 3.0)]);
+
+        let mark = position
+            .mark_to_market(&market, &aggregates, &split)
+            .unwrap();
+        let hit = expected_payout(
+            &market,
+            &position,
+            &aggregates,
+            &distribution(&[(3600, 1.0)]),
+        )
+        .unwrap();
+
+        assert!((mark.expected_payout - hit / 4.0).abs() < 1.0);
+        assert!(mark.unrealized_pnl < 0.0);
+        assert!((mark.probability_of_profit - 0.25).abs() < 1e-9);
+        assert_eq!(mark.confidence_interval.0, 0);
+        assert_eq!(mark.confidence_interval.1, hit.round() as u64);
+
+        let settled = Position {
+            status: PositionStatus::Won,
+            payout: Some(250_000_000),
+            ..position
+        };
+        let mark = settled
+            .mark_to_market(&market, &aggregates, &split)
+            .unwrap();
+        assert_eq!(mark.confidence_interval, (250_000_000, 250_000_000));
+        assert_eq!(mark.unrealized_pnl, 150_000_000.0);
+    }
+
+    #[test]
+    fn test_exposure_hedges_within_market() {
+        let mut market = fixtures::market();
+        let positions = [
+            position(&market, 3600, 100_000_000),
+            position(&market, 36_000, 100_000_000),
+        ];
+        let aggregates = place(&mut market, &positions, 100_000_000);
+        let split = distribution(&[(3600, 1.0), (36_000, 1.0)]);
+
+        let exposure = MarketExposure::mark(&market, &positions, &aggregates, &split).unwrap();
+        let individual: f64 = exposure.positions.iter().map(|m| m.std_dev).sum();
+
+        // Exactly one of the two buckets wins, so the total barely varies
+        assert!(exposure.std_dev < individual / 10.0);
+        assert!(exposure.worst_case > 0);
+        assert_eq!(exposure.cost_basis, 200_000_000);
+
+        let portfolio = PortfolioValuation::from_markets(vec![exposure.clone(), exposure]);
+        assert_eq!(portfolio.cost_basis, 400_000_000);
+        let (low, high) = portfolio.confidence_interval;
+        assert!(
+            low as f64 <= portfolio.expected_payout && portfolio.expected_payout <= high as f64
+        );
+        assert!(low >= portfolio.worst_case);
+    }
+}
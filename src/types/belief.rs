@@ -3,6 +3,10 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
+use super::market::MarketConfig;
+use super::custom::CustomConditionRegistry;
+use crate::error::{ConditionError, ConditionErrorKind};
+
 /// Belief State Index - core measurement construct of Preda
 ///
 /// Represents a continuously updated aggregation of belief signals across defined domains.
@@ -31,7 +35,10 @@ pub struct BeliefStateIndex {
 }
 
 /// Belief condition types for market resolution
-#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+///
+/// Borsh encoding is implemented by hand because the derive cannot handle
+/// the recursive composite variants.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BeliefCondition {
     /// Sentiment polarity shift
     SentimentShift {
@@ -55,6 +62,10 @@ pub enum BeliefCondition {
     },
 
     /// Narrative velocity threshold
+    ///
+    /// Holds while the BSI velocity magnitude is at least
+    /// `velocity_threshold`, provided it accelerated by at least
+    /// `acceleration_threshold` per hour when it first got there.
     NarrativeVelocity {
         velocity_threshold: f64,
         acceleration_threshold: f64,
@@ -67,6 +78,153 @@ pub enum BeliefCondition {
         parameters: Vec<(String, f64)>,
         persistence_window: u64,
     },
+
+    /// All nested conditions must be met at the same time
+    All { conditions: Vec<BeliefCondition> },
+
+    /// At least one nested condition must be met
+    Any { conditions: Vec<BeliefCondition> },
+
+    /// Nested condition must stay unmet for its persistence window
+    Not { condition: Box<BeliefCondition> },
+
+    /// Nested conditions must be met in order
+    Sequence {
+        steps: Vec<BeliefCondition>,
+        max_gap: u64, // seconds between consecutive steps
+    },
+}
+
+impl BorshSerialize for BeliefCondition {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            BeliefCondition::SentimentShift { from_polarity, to_polarity, persistence_window } => {
+                BorshSerialize::serialize(&0u8, writer)?;
+                BorshSerialize::serialize(&from_polarity, writer)?;
+                BorshSerialize::serialize(&to_polarity, writer)?;
+                BorshSerialize::serialize(&persistence_window, writer)
+            }
+            BeliefCondition::ProbabilityThreshold { threshold, direction, persistence_window } => {
+                BorshSerialize::serialize(&1u8, writer)?;
+                BorshSerialize::serialize(&threshold, writer)?;
+                BorshSerialize::serialize(&direction, writer)?;
+                BorshSerialize::serialize(&persistence_window, writer)
+            }
+            BeliefCondition::ModelConsensus { min_models, convergence_band, persistence_window } => {
+                BorshSerialize::serialize(&2u8, writer)?;
+                BorshSerialize::serialize(&min_models, writer)?;
+                BorshSerialize::serialize(&convergence_band, writer)?;
+                BorshSerialize::serialize(&persistence_window, writer)
+            }
+            BeliefCondition::NarrativeVelocity {
+                velocity_threshold,
+                acceleration_threshold,
+                persistence_window,
+            } => {
+                BorshSerialize::serialize(&3u8, writer)?;
+                BorshSerialize::serialize(&velocity_threshold, writer)?;
+                BorshSerialize::serialize(&acceleration_threshold, writer)?;
+                BorshSerialize::serialize(&persistence_window, writer)
+            }
+            BeliefCondition::Custom { condition_type, parameters, persistence_window } => {
+                BorshSerialize::serialize(&4u8, writer)?;
+                BorshSerialize::serialize(&condition_type, writer)?;
+                BorshSerialize::serialize(&parameters, writer)?;
+                BorshSerialize::serialize(&persistence_window, writer)
+            }
+            BeliefCondition::All { conditions } => {
+                BorshSerialize::serialize(&5u8, writer)?;
+                BorshSerialize::serialize(&conditions, writer)
+            }
+            BeliefCondition::Any { conditions } => {
+                BorshSerialize::serialize(&6u8, writer)?;
+                BorshSerialize::serialize(&conditions, writer)
+            }
+            BeliefCondition::Not { condition } => {
+                BorshSerialize::serialize(&7u8, writer)?;
+                BorshSerialize::serialize(&condition, writer)
+            }
+            BeliefCondition::Sequence { steps, max_gap } => {
+                BorshSerialize::serialize(&8u8, writer)?;
+                BorshSerialize::serialize(&steps, writer)?;
+                BorshSerialize::serialize(&max_gap, writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for BeliefCondition {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let condition = match u8::deserialize_reader(reader)? {
+            0 => BeliefCondition::SentimentShift {
+                from_polarity: f64::deserialize_reader(reader)?,
+                to_polarity: f64::deserialize_reader(reader)?,
+                persistence_window: u64::deserialize_reader(reader)?,
+            },
+            1 => BeliefCondition::ProbabilityThreshold {
+                threshold: f64::deserialize_reader(reader)?,
+                direction: ThresholdDirection::deserialize_reader(reader)?,
+                persistence_window: u64::deserialize_reader(reader)?,
+            },
+            2 => BeliefCondition::ModelConsensus {
+                min_models: u32::deserialize_reader(reader)?,
+                convergence_band: f64::deserialize_reader(reader)?,
+                persistence_window: u64::deserialize_reader(reader)?,
+            },
+            3 => BeliefCondition::NarrativeVelocity {
+                velocity_threshold: f64::deserialize_reader(reader)?,
+                acceleration_threshold: f64::deserialize_reader(reader)?,
+                persistence_window: u64::deserialize_reader(reader)?,
+            },
+            4 => BeliefCondition::Custom {
+                condition_type: String::deserialize_reader(reader)?,
+                parameters: Vec::deserialize_reader(reader)?,
+                persistence_window: u64::deserialize_reader(reader)?,
+            },
+            5 => BeliefCondition::All {
+                conditions: Vec::deserialize_reader(reader)?,
+            },
+            6 => BeliefCondition::Any {
+                conditions: Vec::deserialize_reader(reader)?,
+            },
+            7 => BeliefCondition::Not {
+                condition: Box::deserialize_reader(reader)?,
+            },
+            8 => BeliefCondition::Sequence {
+                steps: Vec::deserialize_reader(reader)?,
+                max_gap: u64::deserialize_reader(reader)?,
+            },
+            tag => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unknown belief condition variant: {}", tag),
+                ))
+            }
+        };
+        Ok(condition)
+    }
+}
+
+/// Result of evaluating a belief condition against BSI history
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConditionStatus {
+    /// Condition is met, including its persistence window
+    pub satisfied: bool,
+
+    /// Underlying predicate holds at the latest BSI
+    pub holding: bool,
+
+    /// Timestamp when the predicate started holding (current run)
+    pub first_true_at: Option<i64>,
+
+    /// Timestamp when the persistence window was first completed (current run)
+    pub satisfied_at: Option<i64>,
+
+    /// How long the predicate has held (seconds)
+    pub held_for: u64,
+
+    /// Persistence window the predicate must hold for (seconds)
+    pub required: u64,
 }
 
 /// Threshold direction for probability-based conditions
@@ -183,9 +341,53 @@ impl BeliefStateIndex {
     pub fn is_volatile(&self) -> bool {
         self.volatility > 0.5
     }
+
+    /// Map the BSI value onto a 0.0 to 1.0 probability scale
+    pub fn probability(&self) -> f64 {
+        ((self.value + 1.0) / 2.0).clamp(0.0, 1.0)
+    }
+}
+
+impl ConditionStatus {
+    /// Status for a predicate that does not hold at the latest BSI
+    pub fn unmet(required: u64) -> Self {
+        Self {
+            satisfied: false,
+            holding: false,
+            first_true_at: None,
+            satisfied_at: None,
+            held_for: 0,
+            required,
+        }
+    }
+
+    /// Seconds the predicate still has to hold before the condition is met
+    pub fn remaining(&self) -> u64 {
+        if self.satisfied {
+            0
+        } else {
+            self.required.saturating_sub(self.held_for)
+        }
+    }
 }
 
 impl BeliefCondition {
+    /// Evaluate this condition against a chronologically ordered BSI history
+    ///
+    /// Custom conditions are resolved through the global registry.
+    pub fn evaluate(&self, history: &[BeliefStateIndex]) -> ConditionStatus {
+        crate::bsi::ConditionEvaluator::evaluate(self, history)
+    }
+
+    /// Evaluate this condition, resolving custom conditions through `registry`
+    pub fn evaluate_with(
+        &self,
+        history: &[BeliefStateIndex],
+        registry: &CustomConditionRegistry,
+    ) -> ConditionStatus {
+        crate::bsi::ConditionEvaluator::evaluate_with(self, history, registry)
+    }
+
     /// Get the persistence window for this condition
     pub fn persistence_window(&self) -> u64 {
         match self {
@@ -194,38 +396,242 @@ impl BeliefCondition {
             BeliefCondition::ModelConsensus { persistence_window, .. } => *persistence_window,
             BeliefCondition::NarrativeVelocity { persistence_window, .. } => *persistence_window,
             BeliefCondition::Custom { persistence_window, .. } => *persistence_window,
+            BeliefCondition::All { conditions } => conditions
+                .iter()
+                .map(|c| c.persistence_window())
+                .max()
+                .unwrap_or(0),
+            BeliefCondition::Any { conditions } => conditions
+                .iter()
+                .map(|c| c.persistence_window())
+                .min()
+                .unwrap_or(0),
+            BeliefCondition::Not { condition } => condition.persistence_window(),
+            BeliefCondition::Sequence { steps, .. } => {
+                steps.iter().map(|c| c.persistence_window()).sum()
+            }
         }
     }
 
+    /// Check if this condition combines other conditions
+    pub fn is_composite(&self) -> bool {
+        matches!(
+            self,
+            BeliefCondition::All { .. }
+                | BeliefCondition::Any { .. }
+                | BeliefCondition::Not { .. }
+                | BeliefCondition::Sequence { .. }
+        )
+    }
+
     /// Validate the belief condition parameters
-    pub fn validate(&self) -> Result<(), String> {
+    ///
+    /// Custom conditions are checked against the global registry.
+    pub fn validate(&self) -> Result<(), ConditionError> {
+        self.validate_with(&CustomConditionRegistry::read_global())
+    }
+
+    /// Validate the belief condition, checking custom conditions against `registry`
+    pub fn validate_with(&self, registry: &CustomConditionRegistry) -> Result<(), ConditionError> {
         match self {
-            BeliefCondition::SentimentShift { from_polarity, to_polarity, .. } => {
-                if from_polarity.abs() > 1.0 || to_polarity.abs() > 1.0 {
-                    return Err("Polarity values must be between -1.0 and 1.0".to_string());
+            BeliefCondition::SentimentShift {
+                from_polarity,
+                to_polarity,
+                persistence_window,
+            } => {
+                check_range("from_polarity", *from_polarity, -1.0, 1.0)?;
+                check_range("to_polarity", *to_polarity, -1.0, 1.0)?;
+                if from_polarity == to_polarity {
+                    return Err(ConditionError::new(
+                        "to_polarity",
+                        ConditionErrorKind::Degenerate("equals from_polarity".to_string()),
+                    ));
                 }
-                Ok(())
+                check_window("persistence_window", *persistence_window)
             }
-            BeliefCondition::ProbabilityThreshold { threshold, .. } => {
-                if *threshold < 0.0 || *threshold > 1.0 {
-                    return Err("Threshold must be between 0.0 and 1.0".to_string());
+            BeliefCondition::ProbabilityThreshold {
+                threshold,
+                direction,
+                persistence_window,
+            } => {
+                check_range("threshold", *threshold, 0.0, 1.0)?;
+                let degenerate = match direction {
+                    ThresholdDirection::Above if *threshold == 0.0 => Some("always above 0.0"),
+                    ThresholdDirection::Below if *threshold == 1.0 => Some("always below 1.0"),
+                    ThresholdDirection::Cross if *threshold == 0.0 || *threshold == 1.0 => {
+                        Some("probability cannot cross a bound of its range")
+                    }
+                    _ => None,
+                };
+                if let Some(reason) = degenerate {
+                    return Err(ConditionError::new(
+                        "threshold",
+                        ConditionErrorKind::Degenerate(reason.to_string()),
+                    ));
                 }
-                Ok(())
+                check_window("persistence_window", *persistence_window)
             }
-            BeliefCondition::ModelConsensus { min_models, convergence_band, .. } => {
+            BeliefCondition::ModelConsensus {
+                min_models,
+                convergence_band,
+                persistence_window,
+            } => {
                 if *min_models < 2 {
-                    return Err("Minimum 2 models required for consensus".to_string());
+                    return Err(ConditionError::new(
+                        "min_models",
+                        ConditionErrorKind::OutOfRange {
+                            min: 2.0,
+                            max: u32::MAX as f64,
+                            actual: *min_models as f64,
+                        },
+                    ));
+                }
+                check_range("convergence_band", *convergence_band, 0.0, 1.0)?;
+                if *convergence_band == 0.0 {
+                    return Err(ConditionError::new("convergence_band", ConditionErrorKind::Zero));
+                }
+                check_window("persistence_window", *persistence_window)
+            }
+            BeliefCondition::NarrativeVelocity {
+                velocity_threshold,
+                acceleration_threshold,
+                persistence_window,
+            } => {
+                check_range("velocity_threshold", *velocity_threshold, 0.0, MAX_VELOCITY)?;
+                if *velocity_threshold == 0.0 {
+                    return Err(ConditionError::new("velocity_threshold", ConditionErrorKind::Zero));
+                }
+                check_range(
+                    "acceleration_threshold",
+                    *acceleration_threshold,
+                    0.0,
+                    MAX_ACCELERATION,
+                )?;
+                check_window("persistence_window", *persistence_window)
+            }
+            BeliefCondition::Custom {
+                condition_type,
+                parameters,
+                persistence_window,
+            } => {
+                registry.validate(condition_type, parameters)?;
+                check_window("persistence_window", *persistence_window)
+            }
+            BeliefCondition::All { conditions } | BeliefCondition::Any { conditions } => {
+                if conditions.is_empty() {
+                    return Err(ConditionError::new(
+                        "conditions",
+                        ConditionErrorKind::TooFewConditions { min: 1, actual: 0 },
+                    ));
                 }
-                if *convergence_band <= 0.0 || *convergence_band > 1.0 {
-                    return Err("Convergence band must be between 0.0 and 1.0".to_string());
+                conditions.iter().enumerate().try_for_each(|(i, c)| {
+                    c.validate_with(registry)
+                        .map_err(|e| e.nested(&format!("conditions[{}]", i)))
+                })
+            }
+            BeliefCondition::Not { condition } => condition
+                .validate_with(registry)
+                .map_err(|e| e.nested("condition")),
+            BeliefCondition::Sequence { steps, max_gap } => {
+                if steps.len() < 2 {
+                    return Err(ConditionError::new(
+                        "steps",
+                        ConditionErrorKind::TooFewConditions {
+                            min: 2,
+                            actual: steps.len(),
+                        },
+                    ));
                 }
-                Ok(())
+                check_window("max_gap", *max_gap)?;
+                steps.iter().enumerate().try_for_each(|(i, c)| {
+                    c.validate_with(registry)
+                        .map_err(|e| e.nested(&format!("steps[{}]", i)))
+                })
+            }
+        }
+    }
+
+    /// Validate the condition for a market created at `created_at` with `config`
+    ///
+    /// On top of `validate`, the persistence window plus one time bucket must
+    /// fit before the market expires. An `expiration_time` of 0 means the
+    /// market has no expiration. Custom conditions are checked against the
+    /// global registry.
+    pub fn validate_for_market(
+        &self,
+        config: &MarketConfig,
+        created_at: i64,
+    ) -> Result<(), ConditionError> {
+        self.validate_for_market_with(
+            config,
+            created_at,
+            &CustomConditionRegistry::read_global(),
+        )
+    }
+
+    /// Validate the condition for a market, checking custom conditions
+    /// against `registry`
+    pub fn validate_for_market_with(
+        &self,
+        config: &MarketConfig,
+        created_at: i64,
+        registry: &CustomConditionRegistry,
+    ) -> Result<(), ConditionError> {
+        self.validate_with(registry)?;
+
+        if config.expiration_time > 0 {
+            let lifetime = config.expiration_time - created_at;
+            let window = self.persistence_window();
+            let required = window.saturating_add(config.time_bucket_size);
+
+            if lifetime < 0 || required > lifetime as u64 {
+                return Err(ConditionError::new(
+                    "persistence_window",
+                    ConditionErrorKind::ExceedsMarketLifetime {
+                        window,
+                        bucket: config.time_bucket_size,
+                        lifetime,
+                    },
+                ));
             }
-            _ => Ok(()),
         }
+
+        Ok(())
     }
 }
 
+/// Largest BSI velocity magnitude: the index moves within -1.0 to 1.0
+const MAX_VELOCITY: f64 = 2.0;
+
+/// Largest acceleration threshold, in velocity change per hour
+const MAX_ACCELERATION: f64 = 2.0 * MAX_VELOCITY;
+
+/// Check that a value is finite and within `min..=max`
+fn check_range(field: &str, value: f64, min: f64, max: f64) -> Result<(), ConditionError> {
+    if !value.is_finite() {
+        return Err(ConditionError::new(field, ConditionErrorKind::NotFinite(value)));
+    }
+    if value < min || value > max {
+        return Err(ConditionError::new(
+            field,
+            ConditionErrorKind::OutOfRange {
+                min,
+                max,
+                actual: value,
+            },
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a window in seconds is non-zero
+fn check_window(field: &str, window: u64) -> Result<(), ConditionError> {
+    if window == 0 {
+        return Err(ConditionError::new(field, ConditionErrorKind::Zero));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,4 +668,384 @@ mod tests {
         };
         assert!(invalid_condition.validate().is_err());
     }
+
+    #[test]
+    fn test_composite_condition() {
+        let sentiment = BeliefCondition::SentimentShift {
+            from_polarity: -0.2,
+            to_polarity: 0.6,
+            persistence_window: 3600,
+        };
+        let consensus = BeliefCondition::ModelConsensus {
+            min_models: 3,
+            convergence_band: 0.1,
+            persistence_window: 7200,
+        };
+
+        let all = BeliefCondition::All {
+            conditions: vec![sentiment.clone(), consensus.clone()],
+        };
+        assert!(all.validate().is_ok());
+        assert!(all.is_composite());
+        assert_eq!(all.persistence_window(), 7200);
+
+        let any = BeliefCondition::Any {
+            conditions: vec![sentiment.clone(), consensus.clone()],
+        };
+        assert_eq!(any.persistence_window(), 3600);
+
+        let sequence = BeliefCondition::Sequence {
+            steps: vec![sentiment.clone(), consensus],
+            max_gap: 1800,
+        };
+        assert_eq!(sequence.persistence_window(), 10800);
+
+        let nested_invalid = BeliefCondition::Not {
+            condition: Box::new(BeliefCondition::All {
+                conditions: vec![BeliefCondition::SentimentShift {
+                    from_polarity: -1.5,
+                    to_polarity: 0.6,
+                    persistence_window: 3600,
+                }],
+            }),
+        };
+        assert!(nested_invalid.validate().is_err());
+
+        let empty = BeliefCondition::Any { conditions: vec![] };
+        assert!(empty.validate().is_err());
+
+        let single_step = BeliefCondition::Sequence {
+            steps: vec![sentiment],
+            max_gap: 1800,
+        };
+        assert!(single_step.validate().is_err());
+    }
+
+    #[test]
+    fn test_composite_borsh_roundtrip() {
+        let condition = BeliefCondition::Sequence {
+            steps: vec![
+                BeliefCondition::ProbabilityThreshold {
+                    threshold: 0.7,
+                    direction: ThresholdDirection::Cross,
+                    persistence_window: 600,
+                },
+                BeliefCondition::Not {
+                    condition: Box::new(BeliefCondition::NarrativeVelocity {
+                        velocity_threshold: 0.5,
+                        acceleration_threshold: 0.1,
+                        persistence_window: 1800,
+                    }),
+                },
+            ],
+            max_gap: 7200,
+        };
+
+        let bytes = condition.try_to_vec().unwrap();
+        let decoded = BeliefCondition::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded.try_to_vec().unwrap(), bytes);
+        assert_eq!(decoded.persistence_window(), 2400);
+    }
+
+    #[test]
+    fn test_custom_condition_requires_registration() {
+        use crate::types::custom::{CustomConditionHandler, CustomParameters, ParameterSpec};
+
+        struct HighConfidence;
+
+        impl CustomConditionHandler for HighConfidence {
+            fn parameters(&self) -> Vec<ParameterSpec> {
+                vec![ParameterSpec::required("min_confidence", 0.0, 1.0)]
+            }
+
+            fn holds(
+                &self,
+                parameters: &CustomParameters,
+                history: &[BeliefStateIndex],
+                index: usize,
+            ) -> bool {
+                history[index].confidence >= parameters.get_or("min_confidence", 1.0)
+            }
+        }
+
+        let condition = BeliefCondition::Custom {
+            condition_type: "high_confidence".to_string(),
+            parameters: vec![("min_confidence".to_string(), 0.9)],
+            persistence_window: 60,
+        };
+
+        let mut registry = CustomConditionRegistry::new();
+        let err = condition.validate_with(&registry).unwrap_err();
+        assert_eq!(err.field, "condition_type");
+
+        registry.register("high_confidence", HighConfidence);
+        assert!(condition.validate_with(&registry).is_ok());
+
+        let out_of_range = BeliefCondition::Custom {
+            condition_type: "high_confidence".to_string(),
+            parameters: vec![("min_confidence".to_string(), 1.2)],
+            persistence_window: 60,
+        };
+        assert!(out_of_range.validate_with(&registry).is_err());
+
+        let history: Vec<BeliefStateIndex> = [(0, 0.95), (60, 0.97)]
+            .iter()
+            .map(|&(last_updated, confidence)| BeliefStateIndex {
+                last_updated,
+                confidence,
+                ..BeliefStateIndex::new("BTC".to_string())
+            })
+            .collect();
+        let status = condition.evaluate_with(&history, &registry);
+        assert!(status.satisfied);
+    }
+
+    #[test]
+    fn test_structured_validation_errors() {
+        let degenerate = BeliefCondition::SentimentShift {
+            from_polarity: 0.4,
+            to_polarity: 0.4,
+            persistence_window: 3600,
+        };
+        let err = degenerate.validate().unwrap_err();
+        assert_eq!(err.field, "to_polarity");
+        assert!(matches!(err.kind, ConditionErrorKind::Degenerate(_)));
+
+        let no_window = BeliefCondition::SentimentShift {
+            from_polarity: -0.2,
+            to_polarity: 0.6,
+            persistence_window: 0,
+        };
+        let err = no_window.validate().unwrap_err();
+        assert_eq!(err, ConditionError::new("persistence_window", ConditionErrorKind::Zero));
+
+        let uncrossable = BeliefCondition::ProbabilityThreshold {
+            threshold: 1.0,
+            direction: ThresholdDirection::Cross,
+            persistence_window: 600,
+        };
+        assert_eq!(uncrossable.validate().unwrap_err().field, "threshold");
+
+        let nan_velocity = BeliefCondition::NarrativeVelocity {
+            velocity_threshold: f64::NAN,
+            acceleration_threshold: 0.2,
+            persistence_window: 600,
+        };
+        let err = nan_velocity.validate().unwrap_err();
+        assert_eq!(err.field, "velocity_threshold");
+        assert!(matches!(err.kind, ConditionErrorKind::NotFinite(_)));
+
+        let nested = BeliefCondition::Sequence {
+            steps: vec![
+                BeliefCondition::SentimentShift {
+                    from_polarity: -0.2,
+                    to_polarity: 0.6,
+                    persistence_window: 3600,
+                },
+                BeliefCondition::Not {
+                    condition: Box::new(nan_velocity),
+                },
+            ],
+            max_gap: 600,
+        };
+        assert_eq!(
+            nested.validate().unwrap_err().field,
+            "steps[1].condition.velocity_threshold"
+        );
+    }
+
+    #[test]
+    fn test_validate_for_market() {
+        let condition = BeliefCondition::SentimentShift {
+            from_polarity: -0.2,
+            to_polarity: 0.6,
+            persistence_window: 7200,
+        };
+        let mut config = MarketConfig::default();
+
+        // No expiration configured
+        assert!(condition.validate_for_market(&config, 1_000).is_ok());
+
+        config.expiration_time = 1_000 + 7200 + 3600;
+        assert!(condition.validate_for_market(&config, 1_000).is_ok());
+
+        config.expiration_time -= 1;
+        let err = condition.validate_for_market(&config, 1_000).unwrap_err();
+        assert_eq!(err.field, "persistence_window");
+        assert!(matches!(
+            err.kind,
+            ConditionErrorKind::ExceedsMarketLifetime { .. }
+        ));
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn direction() -> impl Strategy<Value = ThresholdDirection> {
+            prop_oneof![
+                Just(ThresholdDirection::Above),
+                Just(ThresholdDirection::Below),
+                Just(ThresholdDirection::Cross),
+            ]
+        }
+
+        fn valid_leaf() -> impl Strategy<Value = BeliefCondition> {
+            prop_oneof![
+                (-1.0..=1.0f64, -1.0..=1.0f64, 1..86_400u64)
+                    .prop_filter("shift must move", |(from, to, _)| from != to)
+                    .prop_map(|(from_polarity, to_polarity, persistence_window)| {
+                        BeliefCondition::SentimentShift {
+                            from_polarity,
+                            to_polarity,
+                            persistence_window,
+                        }
+                    }),
+                (0.01..0.99f64, direction(), 1..86_400u64).prop_map(
+                    |(threshold, direction, persistence_window)| {
+                        BeliefCondition::ProbabilityThreshold {
+                            threshold,
+                            direction,
+                            persistence_window,
+                        }
+                    }
+                ),
+                (2..50u32, 0.001..=1.0f64, 1..86_400u64).prop_map(
+                    |(min_models, convergence_band, persistence_window)| {
+                        BeliefCondition::ModelConsensus {
+                            min_models,
+                            convergence_band,
+                            persistence_window,
+                        }
+                    }
+                ),
+                (0.001..=MAX_VELOCITY, 0.0..=MAX_ACCELERATION, 1..86_400u64).prop_map(
+                    |(velocity_threshold, acceleration_threshold, persistence_window)| {
+                        BeliefCondition::NarrativeVelocity {
+                            velocity_threshold,
+                            acceleration_threshold,
+                            persistence_window,
+                        }
+                    }
+                ),
+            ]
+        }
+
+        fn valid_condition() -> impl Strategy<Value = BeliefCondition> {
+            valid_leaf().prop_recursive(3, 24, 4, |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 1..4)
+                        .prop_map(|conditions| BeliefCondition::All { conditions }),
+                    prop::collection::vec(inner.clone(), 1..4)
+                        .prop_map(|conditions| BeliefCondition::Any { conditions }),
+                    inner.clone().prop_map(|c| BeliefCondition::Not {
+                        condition: Box::new(c)
+                    }),
+                    (prop::collection::vec(inner, 2..4), 1..86_400u64)
+                        .prop_map(|(steps, max_gap)| BeliefCondition::Sequence { steps, max_gap }),
+                ]
+            })
+        }
+
+        fn non_finite() -> impl Strategy<Value = f64> {
+            prop_oneof![
+                Just(f64::NAN),
+                Just(f64::INFINITY),
+                Just(f64::NEG_INFINITY)
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn valid_conditions_pass(condition in valid_condition()) {
+                prop_assert!(condition.validate_with(&CustomConditionRegistry::new()).is_ok());
+            }
+
+            #[test]
+            fn non_finite_values_name_their_field(bad in non_finite(), field in 0..6usize) {
+                let (condition, name) = match field {
+                    0 => (BeliefCondition::SentimentShift {
+                        from_polarity: bad,
+                        to_polarity: 0.5,
+                        persistence_window: 60,
+                    }, "from_polarity"),
+                    1 => (BeliefCondition::SentimentShift {
+                        from_polarity: -0.5,
+                        to_polarity: bad,
+                        persistence_window: 60,
+                    }, "to_polarity"),
+                    2 => (BeliefCondition::ProbabilityThreshold {
+                        threshold: bad,
+                        direction: ThresholdDirection::Above,
+                        persistence_window: 60,
+                    }, "threshold"),
+                    3 => (BeliefCondition::ModelConsensus {
+                        min_models: 3,
+                        convergence_band: bad,
+                        persistence_window: 60,
+                    }, "convergence_band"),
+                    4 => (BeliefCondition::NarrativeVelocity {
+                        velocity_threshold: bad,
+                        acceleration_threshold: 0.1,
+                        persistence_window: 60,
+                    }, "velocity_threshold"),
+                    _ => (BeliefCondition::NarrativeVelocity {
+                        velocity_threshold: 0.5,
+                        acceleration_threshold: bad,
+                        persistence_window: 60,
+                    }, "acceleration_threshold"),
+                };
+
+                let err = condition.validate().unwrap_err();
+                prop_assert_eq!(err.field, name);
+                prop_assert!(matches!(err.kind, ConditionErrorKind::NotFinite(_)));
+            }
+
+            #[test]
+            fn out_of_range_threshold_rejected(
+                threshold in prop_oneof![-100.0..-0.0001f64, 1.0001..100.0f64],
+                direction in direction(),
+            ) {
+                let condition = BeliefCondition::ProbabilityThreshold {
+                    threshold,
+                    direction,
+                    persistence_window: 60,
+                };
+                let err = condition.validate().unwrap_err();
+                prop_assert_eq!(err.field, "threshold");
+                let is_out_of_range = matches!(err.kind, ConditionErrorKind::OutOfRange { .. });
+                prop_assert!(is_out_of_range);
+            }
+
+            #[test]
+            fn nested_errors_carry_path(
+                mut conditions in prop::collection::vec(valid_leaf(), 1..6),
+                index in any::<prop::sample::Index>(),
+            ) {
+                let position = index.index(conditions.len());
+                conditions[position] = BeliefCondition::SentimentShift {
+                    from_polarity: 0.3,
+                    to_polarity: 0.3,
+                    persistence_window: 60,
+                };
+
+                let err = BeliefCondition::All { conditions }.validate().unwrap_err();
+                prop_assert_eq!(err.field, format!("conditions[{}].to_polarity", position));
+            }
+
+            #[test]
+            fn market_lifetime_bounds_window(
+                condition in valid_leaf(),
+                bucket in 1..7_200u64,
+                lifetime in 1..200_000i64,
+            ) {
+                let mut config = MarketConfig::default();
+                config.time_bucket_size = bucket;
+                config.expiration_time = 1_000 + lifetime;
+
+                let fits = condition.persistence_window() + bucket <= lifetime as u64;
+                prop_assert_eq!(condition.validate_for_market(&config, 1_000).is_ok(), fits);
+            }
+        }
+    }
 }
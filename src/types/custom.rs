@@ -0,0 +1,322 @@
+//! Registry of handlers for `BeliefCondition::Custom` condition types
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock, RwLockReadGuard};
+
+use super::belief::BeliefStateIndex;
+use crate::error::{ConditionError, ConditionErrorKind};
+
+/// Handler giving a custom condition type its validation and evaluation semantics
+pub trait CustomConditionHandler: Send + Sync {
+    /// Parameters accepted by this condition type
+    fn parameters(&self) -> Vec<ParameterSpec>;
+
+    /// Check relationships between parameters once each is within its declared range
+    fn validate(&self, _parameters: &CustomParameters) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Whether the condition's predicate holds at `history[index]`
+    fn holds(
+        &self,
+        parameters: &CustomParameters,
+        history: &[BeliefStateIndex],
+        index: usize,
+    ) -> bool;
+}
+
+/// Declared parameter of a custom condition type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterSpec {
+    /// Parameter name
+    pub name: String,
+
+    /// Minimum allowed value (inclusive)
+    pub min: f64,
+
+    /// Maximum allowed value (inclusive)
+    pub max: f64,
+
+    /// Value used when the parameter is omitted; required if `None`
+    pub default: Option<f64>,
+}
+
+/// Parameters of a custom condition, with declared defaults applied
+#[derive(Debug, Clone, Default)]
+pub struct CustomParameters {
+    values: HashMap<String, f64>,
+}
+
+/// Registry of custom condition handlers keyed by `condition_type`
+#[derive(Default)]
+pub struct CustomConditionRegistry {
+    handlers: HashMap<String, Arc<dyn CustomConditionHandler>>,
+}
+
+impl ParameterSpec {
+    /// Create a required parameter
+    pub fn required(name: &str, min: f64, max: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            min,
+            max,
+            default: None,
+        }
+    }
+
+    /// Create an optional parameter with a default value
+    pub fn optional(name: &str, min: f64, max: f64, default: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            min,
+            max,
+            default: Some(default),
+        }
+    }
+}
+
+impl CustomParameters {
+    /// Get a parameter value
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values.get(name).copied()
+    }
+
+    /// Get a parameter value, falling back to `default`
+    pub fn get_or(&self, name: &str, default: f64) -> f64 {
+        self.get(name).unwrap_or(default)
+    }
+}
+
+impl CustomConditionRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide registry consulted by `BeliefCondition::validate` and `evaluate`
+    ///
+    /// Prefer passing a registry to the `*_with` entry points, such as
+    /// `BeliefCondition::validate_with` and `ConditionEvaluator::evaluate_with`.
+    pub fn global() -> &'static RwLock<CustomConditionRegistry> {
+        static GLOBAL: OnceLock<RwLock<CustomConditionRegistry>> = OnceLock::new();
+        GLOBAL.get_or_init(|| RwLock::new(CustomConditionRegistry::new()))
+    }
+
+    /// Read access to the global registry
+    pub(crate) fn read_global() -> RwLockReadGuard<'static, CustomConditionRegistry> {
+        Self::global()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Register a handler, replacing any existing handler for the same type
+    pub fn register<H>(&mut self, condition_type: &str, handler: H)
+    where
+        H: CustomConditionHandler + 'static,
+    {
+        self.handlers
+            .insert(condition_type.to_string(), Arc::new(handler));
+    }
+
+    /// Remove a handler
+    pub fn unregister(&mut self, condition_type: &str) -> bool {
+        self.handlers.remove(condition_type).is_some()
+    }
+
+    /// Check if a condition type is registered
+    pub fn contains(&self, condition_type: &str) -> bool {
+        self.handlers.contains_key(condition_type)
+    }
+
+    /// Get registered condition types
+    pub fn condition_types(&self) -> Vec<String> {
+        let mut types: Vec<String> = self.handlers.keys().cloned().collect();
+        types.sort();
+        types
+    }
+
+    /// Validate a custom condition's type and parameters
+    pub fn validate(
+        &self,
+        condition_type: &str,
+        parameters: &[(String, f64)],
+    ) -> Result<(), ConditionError> {
+        let handler = self.handlers.get(condition_type).ok_or_else(|| {
+            ConditionError::new(
+                "condition_type",
+                ConditionErrorKind::UnknownConditionType(condition_type.to_string()),
+            )
+        })?;
+
+        let parameters = Self::resolve(handler.as_ref(), parameters)?;
+        handler.validate(&parameters).map_err(|reason| {
+            ConditionError::new("parameters", ConditionErrorKind::Rejected(reason))
+        })
+    }
+
+    /// Predicate series of a custom condition over the history
+    ///
+    /// Unknown types and invalid parameters never hold.
+    pub fn holds(
+        &self,
+        condition_type: &str,
+        parameters: &[(String, f64)],
+        history: &[BeliefStateIndex],
+    ) -> Vec<bool> {
+        let resolved = self.handlers.get(condition_type).and_then(|handler| {
+            Self::resolve(handler.as_ref(), parameters)
+                .ok()
+                .map(|params| (handler, params))
+        });
+
+        match resolved {
+            Some((handler, params)) => (0..history.len())
+                .map(|index| handler.holds(&params, history, index))
+                .collect(),
+            None => vec![false; history.len()],
+        }
+    }
+
+    /// Check parameters against the handler's specs and apply defaults
+    fn resolve(
+        handler: &dyn CustomConditionHandler,
+        parameters: &[(String, f64)],
+    ) -> Result<CustomParameters, ConditionError> {
+        let specs = handler.parameters();
+        let mut values = HashMap::new();
+        let field = |name: &str| format!("parameters.{}", name);
+
+        for (name, value) in parameters {
+            let spec = specs
+                .iter()
+                .find(|spec| &spec.name == name)
+                .ok_or_else(|| {
+                    ConditionError::new(field(name), ConditionErrorKind::UnknownParameter)
+                })?;
+
+            if !value.is_finite() {
+                return Err(ConditionError::new(
+                    field(name),
+                    ConditionErrorKind::NotFinite(*value),
+                ));
+            }
+            if *value < spec.min || *value > spec.max {
+                return Err(ConditionError::new(
+                    field(name),
+                    ConditionErrorKind::OutOfRange {
+                        min: spec.min,
+                        max: spec.max,
+                        actual: *value,
+                    },
+                ));
+            }
+
+            if values.insert(name.clone(), *value).is_some() {
+                return Err(ConditionError::new(
+                    field(name),
+                    ConditionErrorKind::DuplicateParameter,
+                ));
+            }
+        }
+
+        for spec in &specs {
+            if !values.contains_key(&spec.name) {
+                let default = spec.default.ok_or_else(|| {
+                    ConditionError::new(field(&spec.name), ConditionErrorKind::MissingParameter)
+                })?;
+                values.insert(spec.name.clone(), default);
+            }
+        }
+
+        Ok(CustomParameters { values })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Holds while BSI confidence stays within a band
+    struct ConfidenceBand;
+
+    impl CustomConditionHandler for ConfidenceBand {
+        fn parameters(&self) -> Vec<ParameterSpec> {
+            vec![
+                ParameterSpec::required("min", 0.0, 1.0),
+                ParameterSpec::optional("max", 0.0, 1.0, 1.0),
+            ]
+        }
+
+        fn validate(&self, parameters: &CustomParameters) -> Result<(), String> {
+            if parameters.get_or("min", 0.0) > parameters.get_or("max", 1.0) {
+                return Err("min cannot exceed max".to_string());
+            }
+            Ok(())
+        }
+
+        fn holds(
+            &self,
+            parameters: &CustomParameters,
+            history: &[BeliefStateIndex],
+            index: usize,
+        ) -> bool {
+            let confidence = history[index].confidence;
+            confidence >= parameters.get_or("min", 0.0)
+                && confidence <= parameters.get_or("max", 1.0)
+        }
+    }
+
+    fn params(values: &[(&str, f64)]) -> Vec<(String, f64)> {
+        values.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_parameter_validation() {
+        let mut registry = CustomConditionRegistry::new();
+        registry.register("confidence_band", ConfidenceBand);
+
+        assert!(registry
+            .validate("confidence_band", &params(&[("min", 0.6)]))
+            .is_ok());
+
+        let err = registry
+            .validate("confidence_band", &params(&[("min", 1.5)]))
+            .unwrap_err();
+        assert_eq!(err.field, "parameters.min");
+        assert!(matches!(err.kind, ConditionErrorKind::OutOfRange { .. }));
+        assert!(registry
+            .validate("unknown", &params(&[("min", 0.6)]))
+            .is_err());
+        assert!(registry.validate("confidence_band", &[]).is_err());
+        let err = registry
+            .validate("confidence_band", &params(&[("min", 0.6), ("max", 1.5)]))
+            .unwrap_err();
+        assert_eq!(err.field, "parameters.max");
+        assert!(registry
+            .validate("confidence_band", &params(&[("min", 0.6), ("extra", 1.0)]))
+            .is_err());
+        assert!(registry
+            .validate("confidence_band", &params(&[("min", 0.8), ("max", 0.5)]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_custom_holds() {
+        let mut registry = CustomConditionRegistry::new();
+        registry.register("confidence_band", ConfidenceBand);
+
+        let history: Vec<BeliefStateIndex> = [0.4, 0.7, 0.9]
+            .iter()
+            .map(|&confidence| BeliefStateIndex {
+                confidence,
+                ..BeliefStateIndex::new("BTC".to_string())
+            })
+            .collect();
+
+        let series = registry.holds("confidence_band", &params(&[("min", 0.6)]), &history);
+        assert_eq!(series, vec![false, true, true]);
+
+        let series = registry.holds("unknown", &params(&[("min", 0.6)]), &history);
+        assert_eq!(series, vec![false, false, false]);
+    }
+}
@@ -0,0 +1,830 @@
+//! Human-readable belief condition language
+//!
+//! Conditions are written as short phrases and combined with `and`, `or`,
+//! `not` and `then`:
+//!
+//! ```text
+//! sentiment from -0.2 to 0.6 hold 1h
+//! probability above 0.7 hold 30m
+//! consensus >=3 models within 0.05 hold 2h
+//! velocity above 0.5 accel 0.2 hold 30m
+//! custom confidence_band(min=0.6, max=0.9) hold 1h
+//! (probability cross 0.7 hold 10m then not velocity above 0.5 hold 30m within 2h)
+//! ```
+//!
+//! `not` binds tightest, then `and`, then `or`. A chain of `then` steps builds
+//! a `Sequence` and must end with `within <gap>`. Durations combine `d`, `h`,
+//! `m` and `s` units, e.g. `1h30m`.
+//!
+//! `Display` for `BeliefCondition` prints the canonical form, which parses back
+//! to the same condition. `All` and `Any` with a single element print as that
+//! element.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use super::belief::{BeliefCondition, ThresholdDirection};
+
+/// Error produced when parsing a belief condition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte range of the offending input
+    pub span: Range<usize>,
+
+    /// Description of the problem
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Render the error with the source line and a marker under the span
+    pub fn render(&self, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let width = self.span.end.saturating_sub(start).max(1);
+        format!(
+            "error: {}\n  {}\n  {}{}",
+            self.message,
+            source,
+            " ".repeat(source[..start].chars().count()),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a belief condition from its text form
+pub fn parse_condition(input: &str) -> Result<BeliefCondition, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.len(),
+    };
+
+    let condition = parser.expr()?;
+    if let Some(token) = parser.peek() {
+        return Err(ParseError::new(
+            token.span.clone(),
+            format!("unexpected {}", token.kind),
+        ));
+    }
+    Ok(condition)
+}
+
+impl FromStr for BeliefCondition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_condition(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Duration(u64),
+    Word(String),
+    LParen,
+    RParen,
+    Comma,
+    Equals,
+    AtLeast,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Number(n) => write!(f, "number `{}`", n),
+            TokenKind::Duration(d) => write!(f, "duration `{}`", Duration(*d)),
+            TokenKind::Word(w) => write!(f, "`{}`", w),
+            TokenKind::LParen => write!(f, "`(`"),
+            TokenKind::RParen => write!(f, "`)`"),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Equals => write!(f, "`=`"),
+            TokenKind::AtLeast => write!(f, "`>=`"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+
+        let kind = match c {
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                i += 1;
+                continue;
+            }
+            b'(' => TokenKind::LParen,
+            b')' => TokenKind::RParen,
+            b',' => TokenKind::Comma,
+            b'=' => TokenKind::Equals,
+            b'>' if bytes.get(i + 1) == Some(&b'=') => {
+                i += 1;
+                TokenKind::AtLeast
+            }
+            b'0'..=b'9' | b'-' | b'+' | b'.' => {
+                i += 1;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                let text = &input[start..i];
+                tokens.push(Token {
+                    kind: literal(text, start..i)?,
+                    span: start..i,
+                });
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Word(input[start..i].to_string()),
+                    span: start..i,
+                });
+                continue;
+            }
+            _ => {
+                let len = input[start..].chars().next().map_or(1, char::len_utf8);
+                return Err(ParseError::new(
+                    start..start + len,
+                    format!("unexpected character `{}`", &input[start..start + len]),
+                ));
+            }
+        };
+
+        i += 1;
+        tokens.push(Token {
+            kind,
+            span: start..i,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Lex a numeric literal as a number or a duration such as `1h30m`
+fn literal(text: &str, span: Range<usize>) -> Result<TokenKind, ParseError> {
+    if let Ok(value) = text.parse::<f64>() {
+        if value.is_finite() {
+            return Ok(TokenKind::Number(value));
+        }
+    }
+
+    parse_duration(text)
+        .map(TokenKind::Duration)
+        .ok_or_else(|| ParseError::new(span, format!("invalid number or duration `{}`", text)))
+}
+
+fn parse_duration(text: &str) -> Option<u64> {
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86_400,
+            _ => return None,
+        };
+        let amount: u64 = digits.parse().ok()?;
+        total = total.checked_add(amount.checked_mul(unit)?)?;
+        digits.clear();
+    }
+
+    if digits.is_empty() && !text.is_empty() {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => Err(ParseError::new(
+                self.end..self.end,
+                format!("expected {}, found end of input", expected),
+            )),
+        }
+    }
+
+    fn unexpected<T>(token: &Token, expected: &str) -> Result<T, ParseError> {
+        Err(ParseError::new(
+            token.span.clone(),
+            format!("expected {}, found {}", expected, token.kind),
+        ))
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(w), .. }) if w.eq_ignore_ascii_case(word))
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = self.is_word(word);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), ParseError> {
+        let expected = format!("`{}`", word);
+        let token = self.next(&expected)?;
+        match &token.kind {
+            TokenKind::Word(w) if w.eq_ignore_ascii_case(word) => Ok(()),
+            _ => Self::unexpected(&token, &expected),
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        let expected = kind.to_string();
+        let token = self.next(&expected)?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Self::unexpected(&token, &expected)
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> Result<(String, Range<usize>), ParseError> {
+        let token = self.next(expected)?;
+        match token.kind {
+            TokenKind::Word(w) => Ok((w, token.span)),
+            _ => Self::unexpected(&token, expected),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        let token = self.next("number")?;
+        match token.kind {
+            TokenKind::Number(n) => Ok(n),
+            _ => Self::unexpected(&token, "number"),
+        }
+    }
+
+    fn count(&mut self) -> Result<u32, ParseError> {
+        let token = self.next("model count")?;
+        match token.kind {
+            TokenKind::Number(n) if n >= 0.0 && n.fract() == 0.0 && n <= u32::MAX as f64 => {
+                Ok(n as u32)
+            }
+            _ => Self::unexpected(&token, "model count"),
+        }
+    }
+
+    fn duration(&mut self) -> Result<u64, ParseError> {
+        let token = self.next("duration")?;
+        match token.kind {
+            TokenKind::Duration(d) => Ok(d),
+            _ => Self::unexpected(&token, "duration such as `30m` or `1h`"),
+        }
+    }
+
+    fn hold(&mut self) -> Result<u64, ParseError> {
+        self.expect_word("hold")?;
+        self.duration()
+    }
+
+    /// expr := or ('then' or)* ['within' duration]
+    fn expr(&mut self) -> Result<BeliefCondition, ParseError> {
+        let first = self.or()?;
+        if !self.is_word("then") {
+            return Ok(first);
+        }
+
+        let mut steps = vec![first];
+        while self.eat_word("then") {
+            steps.push(self.or()?);
+        }
+        self.expect_word("within")?;
+        let max_gap = self.duration()?;
+
+        Ok(BeliefCondition::Sequence { steps, max_gap })
+    }
+
+    /// or := and ('or' and)*
+    fn or(&mut self) -> Result<BeliefCondition, ParseError> {
+        let mut conditions = vec![self.and()?];
+        while self.eat_word("or") {
+            conditions.push(self.and()?);
+        }
+
+        Ok(if conditions.len() == 1 {
+            conditions.remove(0)
+        } else {
+            BeliefCondition::Any { conditions }
+        })
+    }
+
+    /// and := unary ('and' unary)*
+    fn and(&mut self) -> Result<BeliefCondition, ParseError> {
+        let mut conditions = vec![self.unary()?];
+        while self.eat_word("and") {
+            conditions.push(self.unary()?);
+        }
+
+        Ok(if conditions.len() == 1 {
+            conditions.remove(0)
+        } else {
+            BeliefCondition::All { conditions }
+        })
+    }
+
+    /// unary := 'not' unary | '(' expr ')' | leaf
+    fn unary(&mut self) -> Result<BeliefCondition, ParseError> {
+        if self.eat_word("not") {
+            return Ok(BeliefCondition::Not {
+                condition: Box::new(self.unary()?),
+            });
+        }
+
+        if matches!(
+            self.peek(),
+            Some(Token {
+                kind: TokenKind::LParen,
+                ..
+            })
+        ) {
+            self.pos += 1;
+            let condition = self.expr()?;
+            self.expect(TokenKind::RParen)?;
+            return Ok(condition);
+        }
+
+        self.leaf()
+    }
+
+    fn leaf(&mut self) -> Result<BeliefCondition, ParseError> {
+        const EXPECTED: &str =
+            "`sentiment`, `probability`, `consensus`, `velocity`, `custom`, `not` or `(`";
+
+        let (keyword, span) = self.word(EXPECTED)?;
+        match keyword.to_ascii_lowercase().as_str() {
+            "sentiment" => {
+                self.expect_word("from")?;
+                let from_polarity = self.number()?;
+                self.expect_word("to")?;
+                let to_polarity = self.number()?;
+                Ok(BeliefCondition::SentimentShift {
+                    from_polarity,
+                    to_polarity,
+                    persistence_window: self.hold()?,
+                })
+            }
+            "probability" => {
+                let (word, span) = self.word("`above`, `below` or `cross`")?;
+                let direction = match word.to_ascii_lowercase().as_str() {
+                    "above" => ThresholdDirection::Above,
+                    "below" => ThresholdDirection::Below,
+                    "cross" => ThresholdDirection::Cross,
+                    _ => {
+                        return Err(ParseError::new(
+                            span,
+                            format!("expected `above`, `below` or `cross`, found `{}`", word),
+                        ))
+                    }
+                };
+                let threshold = self.number()?;
+                Ok(BeliefCondition::ProbabilityThreshold {
+                    threshold,
+                    direction,
+                    persistence_window: self.hold()?,
+                })
+            }
+            "consensus" => {
+                self.expect(TokenKind::AtLeast)?;
+                let min_models = self.count()?;
+                self.expect_word("models")?;
+                self.expect_word("within")?;
+                let convergence_band = self.number()?;
+                Ok(BeliefCondition::ModelConsensus {
+                    min_models,
+                    convergence_band,
+                    persistence_window: self.hold()?,
+                })
+            }
+            "velocity" => {
+                self.expect_word("above")?;
+                let velocity_threshold = self.number()?;
+                let acceleration_threshold = if self.eat_word("accel") {
+                    self.number()?
+                } else {
+                    0.0
+                };
+                Ok(BeliefCondition::NarrativeVelocity {
+                    velocity_threshold,
+                    acceleration_threshold,
+                    persistence_window: self.hold()?,
+                })
+            }
+            "custom" => {
+                let (condition_type, _) = self.word("custom condition type")?;
+                self.expect(TokenKind::LParen)?;
+
+                let mut parameters = Vec::new();
+                if matches!(
+                    self.peek(),
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    })
+                ) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        let (name, _) = self.word("parameter name")?;
+                        self.expect(TokenKind::Equals)?;
+                        parameters.push((name, self.number()?));
+
+                        let token = self.next("`,` or `)`")?;
+                        match token.kind {
+                            TokenKind::Comma => continue,
+                            TokenKind::RParen => break,
+                            _ => return Self::unexpected(&token, "`,` or `)`"),
+                        }
+                    }
+                }
+
+                Ok(BeliefCondition::Custom {
+                    condition_type,
+                    parameters,
+                    persistence_window: self.hold()?,
+                })
+            }
+            _ => Err(ParseError::new(
+                span,
+                format!("expected {}, found `{}`", EXPECTED, keyword),
+            )),
+        }
+    }
+}
+
+/// Duration in seconds, printed with the largest units that divide it
+struct Duration(u64);
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(u64, &str); 4] = [(86_400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+
+        let (size, unit) = UNITS
+            .iter()
+            .find(|(size, _)| self.0.is_multiple_of(*size))
+            .copied()
+            .unwrap_or((1, "s"));
+        write!(f, "{}{}", self.0 / size, unit)
+    }
+}
+
+/// Binding strength used to decide where parentheses are needed
+fn precedence(condition: &BeliefCondition) -> u8 {
+    match condition {
+        BeliefCondition::Sequence { .. } => 0,
+        BeliefCondition::Any { .. } => 1,
+        BeliefCondition::All { .. } => 2,
+        BeliefCondition::Not { .. } => 3,
+        _ => 4,
+    }
+}
+
+fn write_operand(
+    f: &mut fmt::Formatter<'_>,
+    condition: &BeliefCondition,
+    min_precedence: u8,
+) -> fmt::Result {
+    if precedence(condition) < min_precedence {
+        write!(f, "({})", condition)
+    } else {
+        write!(f, "{}", condition)
+    }
+}
+
+fn write_joined(
+    f: &mut fmt::Formatter<'_>,
+    conditions: &[BeliefCondition],
+    separator: &str,
+    min_precedence: u8,
+) -> fmt::Result {
+    for (i, condition) in conditions.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        write_operand(f, condition, min_precedence)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for BeliefCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BeliefCondition::SentimentShift {
+                from_polarity,
+                to_polarity,
+                persistence_window,
+            } => write!(
+                f,
+                "sentiment from {} to {} hold {}",
+                from_polarity,
+                to_polarity,
+                Duration(*persistence_window)
+            ),
+            BeliefCondition::ProbabilityThreshold {
+                threshold,
+                direction,
+                persistence_window,
+            } => {
+                let direction = match direction {
+                    ThresholdDirection::Above => "above",
+                    ThresholdDirection::Below => "below",
+                    ThresholdDirection::Cross => "cross",
+                };
+                write!(
+                    f,
+                    "probability {} {} hold {}",
+                    direction,
+                    threshold,
+                    Duration(*persistence_window)
+                )
+            }
+            BeliefCondition::ModelConsensus {
+                min_models,
+                convergence_band,
+                persistence_window,
+            } => write!(
+                f,
+                "consensus >={} models within {} hold {}",
+                min_models,
+                convergence_band,
+                Duration(*persistence_window)
+            ),
+            BeliefCondition::NarrativeVelocity {
+                velocity_threshold,
+                acceleration_threshold,
+                persistence_window,
+            } => {
+                write!(f, "velocity above {}", velocity_threshold)?;
+                if *acceleration_threshold != 0.0 {
+                    write!(f, " accel {}", acceleration_threshold)?;
+                }
+                write!(f, " hold {}", Duration(*persistence_window))
+            }
+            BeliefCondition::Custom {
+                condition_type,
+                parameters,
+                persistence_window,
+            } => {
+                write!(f, "custom {}(", condition_type)?;
+                for (i, (name, value)) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}={}", name, value)?;
+                }
+                write!(f, ") hold {}", Duration(*persistence_window))
+            }
+            BeliefCondition::All { conditions } => write_joined(f, conditions, " and ", 3),
+            BeliefCondition::Any { conditions } => write_joined(f, conditions, " or ", 2),
+            BeliefCondition::Not { condition } => {
+                f.write_str("not ")?;
+                write_operand(f, condition, 3)
+            }
+            BeliefCondition::Sequence { steps, max_gap } => {
+                write_joined(f, steps, " then ", 1)?;
+                write!(f, " within {}", Duration(*max_gap))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_primitives() {
+        assert_eq!(
+            parse_condition("sentiment from -0.2 to 0.6 hold 1h").unwrap(),
+            BeliefCondition::SentimentShift {
+                from_polarity: -0.2,
+                to_polarity: 0.6,
+                persistence_window: 3600,
+            }
+        );
+        assert_eq!(
+            parse_condition("probability above 0.7 hold 30m").unwrap(),
+            BeliefCondition::ProbabilityThreshold {
+                threshold: 0.7,
+                direction: ThresholdDirection::Above,
+                persistence_window: 1800,
+            }
+        );
+        assert_eq!(
+            parse_condition("consensus >=3 models within 0.05 hold 2h").unwrap(),
+            BeliefCondition::ModelConsensus {
+                min_models: 3,
+                convergence_band: 0.05,
+                persistence_window: 7200,
+            }
+        );
+        assert_eq!(
+            parse_condition("velocity above 0.5 accel 0.2 hold 1h30m").unwrap(),
+            BeliefCondition::NarrativeVelocity {
+                velocity_threshold: 0.5,
+                acceleration_threshold: 0.2,
+                persistence_window: 5400,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_composites() {
+        let condition = parse_condition(
+            "sentiment from -0.2 to 0.6 hold 1h and consensus >=3 models within 0.05 hold 2h \
+             or not probability below 0.3 hold 10m",
+        )
+        .unwrap();
+
+        match condition {
+            BeliefCondition::Any { conditions } => {
+                assert!(matches!(conditions[0], BeliefCondition::All { .. }));
+                assert!(matches!(conditions[1], BeliefCondition::Not { .. }));
+            }
+            other => panic!("unexpected condition: {:?}", other),
+        }
+
+        let sequence = parse_condition(
+            "probability cross 0.7 hold 10m then not velocity above 0.5 hold 30m within 2h",
+        )
+        .unwrap();
+        assert!(matches!(
+            sequence,
+            BeliefCondition::Sequence { max_gap: 7200, ref steps } if steps.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_canonical_display() {
+        let text = "(sentiment from -0.2 to 0.6 hold 1h or probability above 0.7 hold 30m) \
+                    and custom confidence_band(min=0.6, max=0.9) hold 1d";
+        let condition: BeliefCondition = text.parse().unwrap();
+
+        assert_eq!(condition.to_string(), text);
+        assert_eq!(
+            condition.to_string().parse::<BeliefCondition>().unwrap(),
+            condition
+        );
+    }
+
+    #[test]
+    fn test_parse_error_spans() {
+        let input = "probability above 0.7 hold 30x";
+        let err = parse_condition(input).unwrap_err();
+        assert_eq!(&input[err.span.clone()], "30x");
+
+        let err = parse_condition("sentiment from -0.2 hold 1h").unwrap_err();
+        assert_eq!(err.span, 20..24);
+        assert!(err.message.contains("`to`"));
+
+        let input = "sentiment from -0.2 to 0.6";
+        let err = parse_condition(input).unwrap_err();
+        assert_eq!(err.span, input.len()..input.len());
+
+        let err =
+            parse_condition("probability above 0.7 hold 1h then sentiment from 0 to 1 hold 1h")
+                .unwrap_err();
+        assert!(err.message.contains("`within`"));
+
+        let rendered = parse_condition("velocity above ? hold 1h")
+            .unwrap_err()
+            .render("velocity above ? hold 1h");
+        assert!(rendered.ends_with("                 ^"));
+    }
+
+    fn leaf() -> impl Strategy<Value = BeliefCondition> {
+        let direction = prop_oneof![
+            Just(ThresholdDirection::Above),
+            Just(ThresholdDirection::Below),
+            Just(ThresholdDirection::Cross),
+        ];
+
+        prop_oneof![
+            (-1.0..=1.0f64, -1.0..=1.0f64, 0..1_000_000u64).prop_map(|(from, to, window)| {
+                BeliefCondition::SentimentShift {
+                    from_polarity: from,
+                    to_polarity: to,
+                    persistence_window: window,
+                }
+            }),
+            (0.0..=1.0f64, direction, 0..1_000_000u64).prop_map(
+                |(threshold, direction, window)| {
+                    BeliefCondition::ProbabilityThreshold {
+                        threshold,
+                        direction,
+                        persistence_window: window,
+                    }
+                }
+            ),
+            (any::<u32>(), 0.0..=1.0f64, 0..1_000_000u64).prop_map(|(models, band, window)| {
+                BeliefCondition::ModelConsensus {
+                    min_models: models,
+                    convergence_band: band,
+                    persistence_window: window,
+                }
+            }),
+            (0.0..=2.0f64, 0.0..=4.0f64, 0..1_000_000u64).prop_map(|(velocity, accel, window)| {
+                BeliefCondition::NarrativeVelocity {
+                    velocity_threshold: velocity,
+                    acceleration_threshold: accel,
+                    persistence_window: window,
+                }
+            }),
+            (
+                "[a-z_][a-z0-9_]{0,8}",
+                prop::collection::vec(("[a-z_][a-z0-9_]{0,8}", -1e6..1e6f64), 0..3),
+                0..1_000_000u64
+            )
+                .prop_map(|(condition_type, parameters, window)| {
+                    BeliefCondition::Custom {
+                        condition_type,
+                        parameters,
+                        persistence_window: window,
+                    }
+                }),
+        ]
+    }
+
+    fn condition() -> impl Strategy<Value = BeliefCondition> {
+        leaf().prop_recursive(4, 32, 3, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 2..4)
+                    .prop_map(|conditions| BeliefCondition::All { conditions }),
+                prop::collection::vec(inner.clone(), 2..4)
+                    .prop_map(|conditions| BeliefCondition::Any { conditions }),
+                inner.clone().prop_map(|c| BeliefCondition::Not {
+                    condition: Box::new(c)
+                }),
+                (prop::collection::vec(inner, 2..4), 0..1_000_000u64)
+                    .prop_map(|(steps, max_gap)| BeliefCondition::Sequence { steps, max_gap }),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn display_round_trips(condition in condition()) {
+            let text = condition.to_string();
+            let parsed = parse_condition(&text);
+            prop_assert_eq!(parsed, Ok(condition), "text: {}", text);
+        }
+    }
+}
@@ -4,7 +4,9 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 
-use super::belief::BeliefCondition;
+use super::belief::{BeliefCondition, BeliefInflection};
+use super::position::TimeBucket;
+use crate::error::PredaError;
 
 /// Market structure for time-shifted prediction markets
 #[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
@@ -36,7 +38,8 @@ pub struct Market {
     /// Resolution timestamp (if resolved)
     pub resolved_at: Option<i64>,
 
-    /// Total value locked in market
+    /// Total value locked in market: the net stake of its active positions,
+    /// so placement fees never enter the pool
     pub total_value_locked: u64,
 
     /// Number of participants
@@ -111,8 +114,88 @@ pub struct MarketConfig {
     /// Settlement curve type
     pub settlement_curve: SettlementCurve,
 
-    /// Fee percentage (basis points)
-    pub fee_bps: u16,
+    /// Settlement curve shape parameters
+    #[serde(default)]
+    pub curve_parameters: CurveParameters,
+
+    /// Curve multiplier thresholds classifying settled positions
+    #[serde(default)]
+    pub settlement_thresholds: SettlementThresholds,
+
+    /// Protocol fees
+    #[serde(default)]
+    pub fees: FeeSchedule,
+
+    /// Fee handling when a cancelled or expired market refunds positions
+    #[serde(default)]
+    pub refund_policy: RefundPolicy,
+
+    /// Which validated inflections the market settles against
+    #[serde(default)]
+    pub resolution_mode: ResolutionMode,
+
+    /// How positions are priced
+    #[serde(default)]
+    pub pricing_mode: PricingMode,
+
+    /// BSI domain the market tracks, such as "BTC"; risk limits count stake
+    /// per domain
+    #[serde(default)]
+    pub domain: Option<String>,
+}
+
+/// How positions in a market are priced
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub enum PricingMode {
+    /// Stakes share one pool, split by the settlement curve
+    #[default]
+    Parimutuel,
+
+    /// LMSR market maker selling bucket shares that pay 1 lamport each;
+    /// `liquidity` is the parameter `b` in lamports
+    Lmsr { liquidity: u64 },
+}
+
+/// Inflections a market settles against
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub enum ResolutionMode {
+    /// Settle the whole pool on the first validated inflection
+    #[default]
+    FirstInflection,
+
+    /// Split the pool equally across the first `count` validated inflections
+    MultipleInflections { count: u32 },
+
+    /// Settle the whole pool on the validated inflection at `index` (0-based)
+    Primary { index: u32 },
+}
+
+/// Protocol fee schedule
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct FeeSchedule {
+    /// Flat market creation fee (lamports)
+    pub creation_fee: u64,
+
+    /// Fee on position placement (basis points of the amount paid)
+    pub placement_bps: u16,
+
+    /// Fee on withdrawal before resolution (basis points of the net stake)
+    pub withdrawal_bps: u16,
+
+    /// Fee on the pool at settlement (basis points)
+    pub settlement_bps: u16,
+}
+
+/// Fee handling for refunds of cancelled and expired markets
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub enum RefundPolicy {
+    /// Return the full stake
+    #[default]
+    RefundFees,
+
+    /// Return the stake less the position fee
+    RetainFees,
 }
 
 /// Settlement curve types for volatility-aware payouts
@@ -127,8 +210,60 @@ pub enum SettlementCurve {
     /// Gaussian distribution around inflection
     Gaussian,
 
-    /// Custom curve with parameters
+    /// Full payout within a plateau, then a Gaussian tail
     Custom,
+
+    /// Linear interpolation between configured points
+    PiecewiseLinear,
+
+    /// Logarithmic scoring of each owner's stake distribution over buckets
+    LogScore,
+
+    /// Quadratic (Brier) scoring of each owner's stake distribution over buckets
+    QuadraticScore,
+}
+
+/// Shape parameters for settlement curves
+///
+/// Distances are measured in multiples of `MarketConfig::time_bucket_size`.
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq)]
+#[serde(default)]
+pub struct CurveParameters {
+    /// Gaussian standard deviation (buckets), also used for the `Custom` tail
+    pub sigma: f64,
+
+    /// Linear multiplier decay per bucket of distance
+    pub linear_decay_rate: f64,
+
+    /// Exponential decay constant (per bucket)
+    pub exponential_decay: f64,
+
+    /// Distance (buckets) paid at full multiplier by the `Custom` curve
+    pub plateau_width: f64,
+
+    /// Points of the `PiecewiseLinear` curve, in increasing distance
+    pub points: Vec<CurvePoint>,
+}
+
+/// Curve multiplier thresholds used to classify positions at settlement
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq)]
+#[serde(default)]
+pub struct SettlementThresholds {
+    /// Minimum multiplier for `PositionStatus::Won`
+    pub win_multiplier: f64,
+
+    /// Minimum multiplier for `PositionStatus::PartialWin`; below it a position is `Lost`
+    pub partial_multiplier: f64,
+}
+
+/// Point on a piecewise-linear settlement curve
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq)]
+pub struct CurvePoint {
+    /// Distance from the inflection (buckets)
+    pub distance: f64,
+
+    /// Payout multiplier at this distance
+    pub multiplier: f64,
 }
 
 impl Market {
@@ -152,10 +287,58 @@ impl Market {
         self.state == MarketState::Active
     }
 
+    /// Check if positions can be refunded
+    pub fn is_refundable(&self) -> bool {
+        matches!(self.state, MarketState::Cancelled | MarketState::Expired)
+    }
+
     /// Get time until expiration (seconds)
     pub fn time_until_expiration(&self, current_time: i64) -> i64 {
         self.config.expiration_time - current_time
     }
+
+    /// All buckets a position can target, in time order
+    ///
+    /// The grid starts at `created_at` and steps by `time_bucket_size`; every
+    /// bucket starting before `expiration_time` is included. Empty for markets
+    /// without an expiration, whose grid is unbounded.
+    pub fn bucket_grid(&self) -> Vec<TimeBucket> {
+        if self.config.expiration_time <= self.created_at || self.config.time_bucket_size == 0 {
+            return vec![];
+        }
+
+        (self.created_at..self.config.expiration_time)
+            .step_by(self.config.time_bucket_size as usize)
+            .map(|start| TimeBucket::from_duration(start, self.config.time_bucket_size))
+            .collect()
+    }
+
+    /// Grid bucket containing `timestamp`, if it lies in the market's lifetime
+    pub fn bucket_for(&self, timestamp: i64) -> Option<TimeBucket> {
+        let size = self.config.time_bucket_size as i64;
+        let expires = self.config.expiration_time;
+        if size == 0 || timestamp < self.created_at || (expires > 0 && timestamp >= expires) {
+            return None;
+        }
+
+        let start = timestamp - (timestamp - self.created_at) % size;
+        Some(TimeBucket::from_duration(start, self.config.time_bucket_size))
+    }
+
+    /// Check that `bucket` is on the market's bucket grid
+    pub fn validate_bucket(&self, bucket: &TimeBucket) -> Result<(), PredaError> {
+        match self.bucket_for(bucket.start) {
+            Some(grid) if grid == *bucket => Ok(()),
+            Some(grid) => Err(PredaError::InvalidTimeBucket(format!(
+                "{}..{} is not aligned to the market grid; nearest bucket is {}..{}",
+                bucket.start, bucket.end, grid.start, grid.end
+            ))),
+            None => Err(PredaError::InvalidTimeBucket(format!(
+                "{}..{} is outside the market lifetime",
+                bucket.start, bucket.end
+            ))),
+        }
+    }
 }
 
 impl MarketConfig {
@@ -169,7 +352,13 @@ impl MarketConfig {
             oracle_update_frequency: 300,   // 5 minutes
             volatility_factor: 1.0,
             settlement_curve: SettlementCurve::Gaussian,
-            fee_bps: 50, // 0.5%
+            curve_parameters: CurveParameters::default(),
+            settlement_thresholds: SettlementThresholds::default(),
+            fees: FeeSchedule::default(),
+            refund_policy: RefundPolicy::RefundFees,
+            resolution_mode: ResolutionMode::FirstInflection,
+            pricing_mode: PricingMode::Parimutuel,
+            domain: None,
         }
     }
 
@@ -191,16 +380,179 @@ impl MarketConfig {
             return Err("Volatility factor must be positive".to_string());
         }
 
-        if self.fee_bps > 10000 {
-            return Err("Fee cannot exceed 100%".to_string());
+        self.fees.validate()?;
+
+        if self.resolution_mode == (ResolutionMode::MultipleInflections { count: 0 }) {
+            return Err("Multiple inflection mode needs at least 1 inflection".to_string());
         }
 
-        Ok(())
+        if self.pricing_mode == (PricingMode::Lmsr { liquidity: 0 }) {
+            return Err("LMSR liquidity must be greater than 0".to_string());
+        }
+
+        self.curve_parameters.validate(self.settlement_curve)?;
+        self.settlement_thresholds.validate()
     }
 
-    /// Calculate fee amount for a given position size
+    /// Calculate placement fee for a given position size
     pub fn calculate_fee(&self, position_size: u64) -> u64 {
-        (position_size as u128 * self.fee_bps as u128 / 10000) as u64
+        self.fees.placement_fee(position_size)
+    }
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            creation_fee: 10_000_000, // 0.01 SOL
+            placement_bps: 50,        // 0.5%
+            withdrawal_bps: 10,       // 0.1%
+            settlement_bps: 0,
+        }
+    }
+}
+
+impl FeeSchedule {
+    /// Validate fee schedule
+    pub fn validate(&self) -> Result<(), String> {
+        if self.placement_bps > 10000 {
+            return Err("Placement fee cannot exceed 100%".to_string());
+        }
+
+        if self.withdrawal_bps > 10000 {
+            return Err("Withdrawal fee cannot exceed 100%".to_string());
+        }
+
+        if self.settlement_bps > 10000 {
+            return Err("Settlement fee cannot exceed 100%".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Fee charged when placing a position of `amount`
+    pub fn placement_fee(&self, amount: u64) -> u64 {
+        Self::apply_bps(amount, self.placement_bps)
+    }
+
+    /// Fee charged when withdrawing a net stake of `stake`
+    pub fn withdrawal_fee(&self, stake: u64) -> u64 {
+        Self::apply_bps(stake, self.withdrawal_bps)
+    }
+
+    /// Fee taken from a settled pool
+    pub fn settlement_fee(&self, pool: u64) -> u64 {
+        Self::apply_bps(pool, self.settlement_bps)
+    }
+
+    fn apply_bps(amount: u64, bps: u16) -> u64 {
+        (amount as u128 * bps.min(10000) as u128 / 10000) as u64
+    }
+}
+
+impl Default for CurveParameters {
+    fn default() -> Self {
+        Self {
+            sigma: 1.0,              // one bucket standard deviation
+            linear_decay_rate: 0.36, // zero payout after ~5.5 buckets
+            exponential_decay: 3.6,
+            plateau_width: 0.0,
+            points: vec![
+                CurvePoint { distance: 0.0, multiplier: 2.0 },
+                CurvePoint { distance: 1.0, multiplier: 1.0 },
+                CurvePoint { distance: 2.0, multiplier: 0.0 },
+            ],
+        }
+    }
+}
+
+impl ResolutionMode {
+    /// Select the inflections to settle against from validated inflections in chronological order
+    pub fn select<'a>(&self, validated: &'a [BeliefInflection]) -> &'a [BeliefInflection] {
+        match *self {
+            ResolutionMode::FirstInflection => &validated[..validated.len().min(1)],
+            ResolutionMode::MultipleInflections { count } => {
+                &validated[..validated.len().min(count as usize)]
+            }
+            ResolutionMode::Primary { index } => {
+                let index = index as usize;
+                validated.get(index..=index).unwrap_or(&[])
+            }
+        }
+    }
+}
+
+impl SettlementCurve {
+    /// Check if the curve scores stake distributions rather than distances
+    pub fn is_scoring_rule(&self) -> bool {
+        matches!(self, SettlementCurve::LogScore | SettlementCurve::QuadraticScore)
+    }
+}
+
+impl Default for SettlementThresholds {
+    fn default() -> Self {
+        Self {
+            win_multiplier: 1.0,     // within ~1 bucket on the default Gaussian
+            partial_multiplier: 0.1, // within ~2.5 buckets
+        }
+    }
+}
+
+impl SettlementThresholds {
+    /// Validate settlement thresholds
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.partial_multiplier.is_finite() || self.partial_multiplier <= 0.0 {
+            return Err("Partial win multiplier must be positive".to_string());
+        }
+
+        if !self.win_multiplier.is_finite() || self.win_multiplier < self.partial_multiplier {
+            return Err("Win multiplier cannot be below partial win multiplier".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl CurveParameters {
+    /// Validate curve parameters for the given curve
+    pub fn validate(&self, curve: SettlementCurve) -> Result<(), String> {
+        if !self.sigma.is_finite() || self.sigma <= 0.0 {
+            return Err("Curve sigma must be positive".to_string());
+        }
+
+        if !self.linear_decay_rate.is_finite() || self.linear_decay_rate <= 0.0 {
+            return Err("Linear decay rate must be positive".to_string());
+        }
+
+        if !self.exponential_decay.is_finite() || self.exponential_decay <= 0.0 {
+            return Err("Exponential decay constant must be positive".to_string());
+        }
+
+        if !self.plateau_width.is_finite() || self.plateau_width < 0.0 {
+            return Err("Plateau width cannot be negative".to_string());
+        }
+
+        for (i, point) in self.points.iter().enumerate() {
+            if !point.distance.is_finite() || point.distance < 0.0 {
+                return Err(format!("Curve point {} distance must be non-negative", i));
+            }
+            if !point.multiplier.is_finite() || point.multiplier < 0.0 {
+                return Err(format!("Curve point {} multiplier must be non-negative", i));
+            }
+            if i > 0 && point.distance <= self.points[i - 1].distance {
+                return Err("Curve points must have strictly increasing distances".to_string());
+            }
+        }
+
+        if curve == SettlementCurve::PiecewiseLinear {
+            if self.points.len() < 2 {
+                return Err("Piecewise linear curve needs at least 2 points".to_string());
+            }
+            if self.points.iter().all(|point| point.multiplier == 0.0) {
+                return Err("Piecewise linear curve must pay out somewhere".to_string());
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -237,6 +589,54 @@ mod tests {
 
         config.min_position_size = 2_000_000_000;
         assert!(config.validate().is_err());
+
+        config = MarketConfig::default();
+        config.pricing_mode = PricingMode::Lmsr { liquidity: 0 };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_curve_parameter_validation() {
+        let mut config = MarketConfig::default();
+        config.settlement_curve = SettlementCurve::PiecewiseLinear;
+        assert!(config.validate().is_ok());
+
+        config.curve_parameters.points.swap(0, 1);
+        assert!(config.validate().is_err());
+
+        config.curve_parameters = CurveParameters {
+            points: vec![CurvePoint { distance: 0.0, multiplier: 2.0 }],
+            ..CurveParameters::default()
+        };
+        assert!(config.validate().is_err());
+
+        config.settlement_curve = SettlementCurve::Gaussian;
+        assert!(config.validate().is_ok());
+
+        config.curve_parameters.sigma = 0.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_settlement_threshold_validation() {
+        let mut config = MarketConfig::default();
+        config.settlement_thresholds.win_multiplier = 0.05;
+        assert!(config.validate().is_err());
+
+        config.settlement_thresholds = SettlementThresholds {
+            win_multiplier: 0.5,
+            partial_multiplier: 0.5,
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_curve_parameters_default_when_missing() {
+        let mut value = serde_json::to_value(MarketConfig::default()).unwrap();
+        value.as_object_mut().unwrap().remove("curve_parameters");
+
+        let config: MarketConfig = serde_json::from_value(value).unwrap();
+        assert_eq!(config.curve_parameters, CurveParameters::default());
     }
 
     #[test]
@@ -246,6 +646,17 @@ mod tests {
         assert_eq!(fee, 5_000_000); // 0.5% = 0.005 SOL
     }
 
+    #[test]
+    fn test_fee_schedule() {
+        let mut config = MarketConfig::default();
+        assert_eq!(config.fees.creation_fee, 10_000_000); // 0.01 SOL
+        assert_eq!(config.fees.withdrawal_fee(1_000_000_000), 1_000_000); // 0.1%
+        assert_eq!(config.fees.settlement_fee(1_000_000_000), 0);
+
+        config.fees.withdrawal_bps = 10_001;
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_market_state_checks() {
         let market = Market {
@@ -271,4 +682,47 @@ mod tests {
         assert!(!market.is_resolved());
         assert!(market.can_accept_positions());
     }
+
+    #[test]
+    fn test_bucket_grid() {
+        let market = Market {
+            address: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            market_type: MarketType::SentimentTransition,
+            belief_condition: super::super::belief::BeliefCondition::SentimentShift {
+                from_polarity: -0.2,
+                to_polarity: 0.6,
+                persistence_window: 3600,
+            },
+            description: "Test market".to_string(),
+            state: MarketState::Active,
+            config: MarketConfig {
+                expiration_time: 1_000 + 3 * 3600 + 1800,
+                ..MarketConfig::default()
+            },
+            created_at: 1_000,
+            resolved_at: None,
+            total_value_locked: 0,
+            participant_count: 0,
+            oracle_addresses: vec![],
+        };
+
+        let grid = market.bucket_grid();
+        assert_eq!(grid.len(), 4);
+        assert_eq!(grid[0], TimeBucket::from_duration(1_000, 3600));
+        assert_eq!(market.bucket_for(1_000 + 3 * 3600 + 10), Some(grid[3]));
+        assert_eq!(market.bucket_for(999), None);
+        assert_eq!(market.bucket_for(market.config.expiration_time), None);
+
+        assert!(grid.iter().all(|bucket| market.validate_bucket(bucket).is_ok()));
+        assert!(market
+            .validate_bucket(&TimeBucket::from_duration(0, 3600))
+            .is_err());
+        assert!(market
+            .validate_bucket(&TimeBucket::from_duration(1_000, 1800))
+            .is_err());
+        assert!(market
+            .validate_bucket(&TimeBucket::from_duration(1_000 + 4 * 3600, 3600))
+            .is_err());
+    }
 }
@@ -1,9 +1,16 @@
 //! Core type definitions for the Preda SDK
 
 pub mod belief;
+pub mod custom;
+pub mod dsl;
 pub mod market;
 pub mod position;
 
-pub use belief::{BeliefCondition, BeliefInflection, BeliefSignal, BeliefStateIndex};
+pub use belief::{
+    BeliefCondition, BeliefInflection, BeliefSignal, BeliefStateIndex, ConditionStatus,
+};
+pub use dsl::{parse_condition, ParseError};
 pub use market::{Market, MarketConfig, MarketState, MarketType};
-pub use position::{Position, PositionStatus, TimeBucket};
+pub use position::{
+    Listing, Position, PositionGroup, PositionStatus, RangeWeighting, TimeBucket,
+};
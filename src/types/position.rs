@@ -4,6 +4,8 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 
+use super::market::FeeSchedule;
+
 /// User position in a time-shifted prediction market
 #[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct Position {
@@ -67,6 +69,57 @@ pub enum PositionStatus {
     Expired,
 }
 
+/// How a range position splits its stake across buckets
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum RangeWeighting {
+    /// Equal stake in every bucket
+    Uniform,
+
+    /// Stake falling linearly with distance from `center`
+    Triangular { center: i64 },
+
+    /// Stake following a normal curve around `center`, `sigma` in buckets
+    Gaussian { center: i64, sigma: f64 },
+}
+
+/// Positions placed together across a range of time buckets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionGroup {
+    /// Range covered, from the first bucket start to the last bucket end
+    pub range: TimeBucket,
+
+    /// Weighting used to split the stake
+    pub weighting: RangeWeighting,
+
+    /// One position per funded bucket, in time order
+    pub positions: Vec<Position>,
+}
+
+/// Position offered for sale on the secondary market
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct Listing {
+    /// Listing address (PDA of the position)
+    pub address: Pubkey,
+
+    /// Position for sale
+    pub position: Pubkey,
+
+    /// Market of the position
+    pub market: Pubkey,
+
+    /// Position owner selling it
+    pub seller: Pubkey,
+
+    /// Asking price (lamports)
+    pub price: u64,
+
+    /// Listing timestamp
+    pub created_at: i64,
+
+    /// Timestamp from which the listing can no longer be bought
+    pub expires_at: Option<i64>,
+}
+
 /// Aggregated position data for a time bucket
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeBucketAggregate {
@@ -105,6 +158,17 @@ impl Position {
         matches!(self.status, PositionStatus::Won | PositionStatus::PartialWin)
     }
 
+    /// Stake left at risk after the placement fee
+    pub fn net_stake(&self, fees: &FeeSchedule) -> u64 {
+        self.amount - fees.placement_fee(self.amount)
+    }
+
+    /// Amount returned when withdrawing before resolution
+    pub fn withdrawal_value(&self, fees: &FeeSchedule) -> u64 {
+        let stake = self.net_stake(fees);
+        stake - fees.withdrawal_fee(stake)
+    }
+
     /// Calculate return on investment (if settled)
     pub fn roi(&self) -> Option<f64> {
         self.payout.map(|payout| {
@@ -171,6 +235,60 @@ impl TimeBucket {
     }
 }
 
+impl RangeWeighting {
+    /// Relative weight of `bucket` within `range`
+    ///
+    /// Distances are measured in buckets of `bucket_size` seconds from the
+    /// bucket midpoint. The triangle reaches zero half a bucket beyond the end
+    /// of the range farthest from its center, so every bucket keeps some weight.
+    pub fn weight(&self, bucket: &TimeBucket, range: &TimeBucket, bucket_size: u64) -> f64 {
+        let size = bucket_size as f64;
+        let midpoint = (bucket.start as f64 + bucket.end as f64) / 2.0;
+
+        match *self {
+            RangeWeighting::Uniform => 1.0,
+            RangeWeighting::Triangular { center } => {
+                let reach = (center - range.start).max(range.end - center) as f64 / size;
+                (reach + 0.5 - (midpoint - center as f64).abs() / size).max(0.0)
+            }
+            RangeWeighting::Gaussian { center, sigma } => {
+                let distance = (midpoint - center as f64) / size;
+                (-(distance * distance) / (2.0 * sigma * sigma)).exp()
+            }
+        }
+    }
+
+    /// Center of the weighting, if it has one
+    pub fn center(&self) -> Option<i64> {
+        match *self {
+            RangeWeighting::Uniform => None,
+            RangeWeighting::Triangular { center } | RangeWeighting::Gaussian { center, .. } => {
+                Some(center)
+            }
+        }
+    }
+}
+
+impl PositionGroup {
+    /// Total staked across the group
+    pub fn total_amount(&self) -> u64 {
+        self.positions.iter().map(|pos| pos.amount).sum()
+    }
+
+    /// Buckets funded by the group
+    pub fn time_buckets(&self) -> Vec<TimeBucket> {
+        self.positions.iter().map(|pos| pos.time_bucket).collect()
+    }
+}
+
+impl Listing {
+    /// Check if the listing can no longer be bought at `current_time`
+    pub fn is_expired(&self, current_time: i64) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| current_time >= expires_at)
+    }
+}
+
 impl TimeBucketAggregate {
     /// Calculate implied probability from stake distribution
     pub fn calculate_implied_probability(bucket_stake: u64, total_market_stake: u64) -> f64 {